@@ -0,0 +1,305 @@
+#[path = "../../os/src/fs/easy_fs/layout.rs"]
+mod layout;
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::{env, fs, process};
+
+use layout::{
+    BLOCK_SIZE, DIR_ENTRY_SIZE, DIRECT_COUNT, DISK_INODE_SIZE, DirEntry, DiskInode, INDIRECT_COUNT,
+    InodeType, MAX_DATA_BLOCKS, ROOT_INODE_ID, SuperBlock, write_u32,
+};
+
+/// The blocks of the inode bitmap, which allows 4096 inodes.
+const INODE_BITMAP_BLOCKS: u32 = 1;
+
+/// Packs files into an easy-fs image, which the kernel
+/// mounts from its virtio block device.
+///
+/// Usage: mkfs <image> <size> [file]...
+///
+/// The size is in bytes with an optional K or M suffix, and
+/// the files are put into the root directory in the given
+/// order under their file names, which must be unique.
+fn main() {
+    if let Err(err) = run(env::args().skip(1).collect()) {
+        eprintln!("mkfs: {err}");
+        process::exit(1);
+    }
+}
+
+fn run(args: Vec<String>) -> Result<(), String> {
+    let [image_path, size, file_paths @ ..] = args.as_slice() else {
+        return Err("Usage: mkfs <image> <size> [file]...".to_string());
+    };
+    let size = parse_size(size).ok_or(format!("Invalid size {size}"))?;
+
+    let mut files = Vec::new();
+    for path in file_paths {
+        let name = Path::new(path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or(format!("Invalid file path {path}"))?;
+        let content = fs::read(path).map_err(|err| format!("Failed to read {path}: {err}"))?;
+        files.push((name, content));
+    }
+
+    fs::write(image_path, build_image(size, &files)?)
+        .map_err(|err| format!("Failed to write {image_path}: {err}"))
+}
+
+/// Returns an image of `size` bytes with the `files`, as
+/// (name, content), in its root directory.
+fn build_image(size: usize, files: &[(&str, Vec<u8>)]) -> Result<Vec<u8>, String> {
+    let mut image = Image::new(size / BLOCK_SIZE)?;
+
+    let mut names = HashSet::new();
+    let mut root_dir = Vec::new();
+    for (name, content) in files {
+        if !names.insert(name) {
+            return Err(format!("Duplicate file name {name}"));
+        }
+
+        let inode_id = image.alloc_inode()?;
+        image.write_inode(inode_id, InodeType::File, content)?;
+        let entry = DirEntry::new(name, inode_id).ok_or(format!("Invalid file name {name}"))?;
+        let mut bytes = [0; DIR_ENTRY_SIZE];
+        entry.encode(&mut bytes);
+        root_dir.extend_from_slice(&bytes);
+    }
+    image.write_inode(ROOT_INODE_ID, InodeType::Directory, &root_dir)?;
+    Ok(image.blocks.concat())
+}
+
+/// Parses the `size` in bytes with an optional K or M suffix.
+fn parse_size(size: &str) -> Option<usize> {
+    let (number, unit) = match size.as_bytes().last()? {
+        b'K' => (&size[..size.len() - 1], 1 << 10),
+        b'M' => (&size[..size.len() - 1], 1 << 20),
+        _ => (size, 1),
+    };
+    number.parse::<usize>().ok()?.checked_mul(unit)
+}
+
+/// An image built in memory, whose inodes and data blocks
+/// are allocated in order.
+struct Image {
+    super_block: SuperBlock,
+    blocks: Vec<[u8; BLOCK_SIZE]>,
+    next_inode_id: u32,
+    next_data_bit: u32,
+}
+
+impl Image {
+    fn new(total_blocks: usize) -> Result<Self, String> {
+        let total_blocks = u32::try_from(total_blocks).map_err(|_| "The size is too large")?;
+        let min_blocks = SuperBlock::new(u32::MAX, INODE_BITMAP_BLOCKS).get_data_bitmap_start() + 2;
+        if total_blocks < min_blocks {
+            return Err(format!("The size is less than {min_blocks} blocks"));
+        }
+
+        let super_block = SuperBlock::new(total_blocks, INODE_BITMAP_BLOCKS);
+        let mut blocks = vec![[0; BLOCK_SIZE]; total_blocks as usize];
+        super_block.encode(&mut blocks[0]);
+        let mut image = Self {
+            super_block,
+            blocks,
+            next_inode_id: ROOT_INODE_ID,
+            next_data_bit: 0,
+        };
+        image.alloc_inode()?;
+        Ok(image)
+    }
+
+    fn alloc_inode(&mut self) -> Result<u32, String> {
+        let inode_id = self.next_inode_id;
+        if inode_id >= self.super_block.inode_bitmap_blocks * (BLOCK_SIZE * 8) as u32 {
+            return Err("Too many files".to_string());
+        }
+        self.set_bit(self.super_block.get_inode_bitmap_start(), inode_id);
+        self.next_inode_id += 1;
+        Ok(inode_id)
+    }
+
+    fn alloc_data_block(&mut self) -> Result<u32, String> {
+        let bit = self.next_data_bit;
+        if bit >= self.super_block.data_area_blocks {
+            return Err("The image is full".to_string());
+        }
+        self.set_bit(self.super_block.get_data_bitmap_start(), bit);
+        self.next_data_bit += 1;
+        Ok(self.super_block.get_data_area_start() + bit)
+    }
+
+    fn set_bit(&mut self, bitmap_start: u32, bit: u32) {
+        let bit = bit as usize;
+        let block = &mut self.blocks[bitmap_start as usize + bit / (BLOCK_SIZE * 8)];
+        block[(bit % (BLOCK_SIZE * 8)) / 8] |= 1 << (bit % 8);
+    }
+
+    /// Writes the inode `inode_id` of the `inode_type` with
+    /// the `content` as its data.
+    fn write_inode(
+        &mut self,
+        inode_id: u32,
+        inode_type: InodeType,
+        content: &[u8],
+    ) -> Result<(), String> {
+        let mut inode = DiskInode::new(inode_type);
+        inode.size = u32::try_from(content.len()).map_err(|_| "The file is too large")?;
+        if content.len().div_ceil(BLOCK_SIZE) > MAX_DATA_BLOCKS {
+            return Err("The file is too large".to_string());
+        }
+
+        let mut block_ids = Vec::new();
+        for chunk in content.chunks(BLOCK_SIZE) {
+            let block_id = self.alloc_data_block()?;
+            self.blocks[block_id as usize][..chunk.len()].copy_from_slice(chunk);
+            block_ids.push(block_id);
+        }
+
+        let (direct, rest) = block_ids.split_at(block_ids.len().min(DIRECT_COUNT));
+        inode.direct[..direct.len()].copy_from_slice(direct);
+        if !rest.is_empty() {
+            let (indirect, rest) = rest.split_at(rest.len().min(INDIRECT_COUNT));
+            inode.indirect = self.write_index_block(indirect)?;
+            if !rest.is_empty() {
+                let indirect_ids = rest
+                    .chunks(INDIRECT_COUNT)
+                    .map(|ids| self.write_index_block(ids))
+                    .collect::<Result<Vec<_>, _>>()?;
+                inode.doubly_indirect = self.write_index_block(&indirect_ids)?;
+            }
+        }
+
+        let (block_id, offset) = self.super_block.get_inode_pos(inode_id);
+        inode.encode(&mut self.blocks[block_id as usize][offset..offset + DISK_INODE_SIZE]);
+        Ok(())
+    }
+
+    /// Writes an index block of the `block_ids` and returns
+    /// its block ID.
+    fn write_index_block(&mut self, block_ids: &[u32]) -> Result<u32, String> {
+        let index_block_id = self.alloc_data_block()?;
+        for (i, &block_id) in block_ids.iter().enumerate() {
+            write_u32(&mut self.blocks[index_block_id as usize], i * 4, block_id);
+        }
+        Ok(index_block_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use layout::{SuperBlock, read_u32};
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("4K"), Some(4 << 10));
+        assert_eq!(parse_size("16M"), Some(16 << 20));
+        assert_eq!(parse_size(""), None);
+        assert_eq!(parse_size("K"), None);
+        assert_eq!(parse_size("4G"), None);
+        assert_eq!(parse_size("-1"), None);
+        assert_eq!(parse_size(&format!("{}M", usize::MAX)), None);
+    }
+
+    #[test]
+    fn test_layout() {
+        let small = b"hello".to_vec();
+        // Takes the direct, indirect and doubly indirect blocks.
+        let large: Vec<u8> = (0..(DIRECT_COUNT + INDIRECT_COUNT + 1) * BLOCK_SIZE)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let image = build_image(
+            1 << 20,
+            &[("small", small.clone()), ("large", large.clone())],
+        )
+        .unwrap();
+        assert_eq!(image.len(), 1 << 20);
+
+        let super_block = SuperBlock::decode(get_block(&image, 0)).unwrap();
+        assert_eq!(super_block.total_blocks as usize, image.len() / BLOCK_SIZE);
+        assert_eq!(
+            super_block.get_data_area_start() + super_block.data_area_blocks,
+            super_block.total_blocks
+        );
+        assert!(
+            super_block.data_bitmap_blocks as usize * BLOCK_SIZE * 8
+                >= super_block.data_area_blocks as usize
+        );
+
+        let root = read_inode(&image, &super_block, ROOT_INODE_ID);
+        assert!(root.is_dir());
+        let root_dir = read_content(&image, &root);
+        let entries: Vec<DirEntry> = root_dir
+            .chunks(DIR_ENTRY_SIZE)
+            .map(DirEntry::decode)
+            .collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].get_name(), "small");
+        assert_eq!(entries[1].get_name(), "large");
+
+        for (entry, content) in entries.iter().zip([small, large]) {
+            let inode = read_inode(&image, &super_block, entry.get_inode_id());
+            assert!(!inode.is_dir());
+            assert_eq!(read_content(&image, &inode), content);
+        }
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        let files = [("a", Vec::new()), ("a", Vec::new())];
+        assert!(
+            build_image(1 << 20, &files).is_err(),
+            "the names are the same"
+        );
+        assert!(build_image(1 << 20, &[("", Vec::new())]).is_err());
+        assert!(build_image(1 << 20, &[("a", vec![0; 1 << 20])]).is_err());
+        assert!(
+            build_image(BLOCK_SIZE * 4, &[]).is_err(),
+            "the size is too small"
+        );
+    }
+
+    fn get_block(image: &[u8], block_id: u32) -> &[u8] {
+        let start = block_id as usize * BLOCK_SIZE;
+        &image[start..start + BLOCK_SIZE]
+    }
+
+    fn read_inode(image: &[u8], super_block: &SuperBlock, inode_id: u32) -> DiskInode {
+        let (block_id, offset) = super_block.get_inode_pos(inode_id);
+        DiskInode::decode(&get_block(image, block_id)[offset..offset + DISK_INODE_SIZE])
+    }
+
+    /// Reads the data of the `inode` through its index blocks.
+    fn read_content(image: &[u8], inode: &DiskInode) -> Vec<u8> {
+        let read_index = |block_id: u32| -> Vec<u32> {
+            let block = get_block(image, block_id);
+            (0..INDIRECT_COUNT)
+                .map(|i| read_u32(block, i * 4))
+                .collect()
+        };
+
+        let mut block_ids = inode.direct.to_vec();
+        if inode.indirect != 0 {
+            block_ids.extend(read_index(inode.indirect));
+        }
+        if inode.doubly_indirect != 0 {
+            for indirect in read_index(inode.doubly_indirect) {
+                if indirect != 0 {
+                    block_ids.extend(read_index(indirect));
+                }
+            }
+        }
+
+        let mut content: Vec<u8> = block_ids
+            .iter()
+            .take_while(|&&block_id| block_id != 0)
+            .flat_map(|&block_id| get_block(image, block_id).to_vec())
+            .collect();
+        content.truncate(inode.size as usize);
+        content
+    }
+}
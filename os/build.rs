@@ -1,114 +1,161 @@
 use std::env;
-use std::fs::{self, File};
+use std::fs;
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
 fn main() {
-    UserApp::generate_asm();
-    println!("cargo::rerun-if-changed=src/link_apps.rs");
-    println!("cargo::rerun-if-changed=../user/src/bin");
-    println!("cargo::rerun-if-changed=../user/target/riscv64gc-unknown-none-elf/release");
+    Initramfs::generate_cpio();
+    println!("cargo::rerun-if-changed={}", Initramfs::APP_SRC_DIR);
+    println!("cargo::rerun-if-changed={}", Initramfs::APP_ELF_DIR);
+    println!("cargo::rerun-if-changed={}", Initramfs::DATA_DIR);
+    println!("cargo::rerun-if-env-changed=TEST");
 }
 
-struct UserApp;
+struct Initramfs;
 
-impl UserApp {
-    const OUTPUT: &str = "src/link_apps.S";
-    const SRC_DIR: &str = "../user/src/bin";
-    const SRC_EXTENSION: &str = ".rs";
-    const ELF_DIR: &str = "../user/target/riscv64gc-unknown-none-elf/release";
+impl Initramfs {
+    const OUTPUT_NAME: &str = "initramfs.cpio";
+    const APP_SRC_DIR: &str = "../user/src/bin";
+    const APP_SRC_EXTENSION: &str = ".rs";
+    const APP_ELF_DIR: &str = "../user/target/riscv64gc-unknown-none-elf/release";
+    /// The files copied into the root of the initramfs as is.
+    const DATA_DIR: &str = "../user/initramfs";
+    /// The directory holding the apps in the initramfs.
+    const APP_DIR: &str = "bin";
+    /// The empty directories that the kernel mounts the
+    /// other file systems on.
+    const EMPTY_DIRS: [&str; 3] = ["dev", "mnt", "tmp"];
 
-    fn generate_asm() {
-        let mut names = Self::get_app_names();
-        names.sort();
+    const DIR_MODE: u32 = 0o040755;
+    const APP_MODE: u32 = 0o100755;
+    const DATA_MODE: u32 = 0o100644;
+
+    fn generate_cpio() {
+        let mut writer = CpioWriter::new();
+        Self::write_apps(&mut writer).unwrap();
+        for dir in Self::EMPTY_DIRS {
+            writer.write_entry(dir, Self::DIR_MODE, &[]);
+        }
+        Self::write_data_dir(&mut writer, Path::new(Self::DATA_DIR), "").unwrap();
 
-        let mut dst = File::create(Self::OUTPUT).unwrap();
-        Self::write_app_asms(&mut dst, names).unwrap();
+        let output = PathBuf::from(env::var("OUT_DIR").unwrap()).join(Self::OUTPUT_NAME);
+        let mut dst = fs::File::create(output).unwrap();
+        dst.write_all(&writer.finish()).unwrap();
     }
 
     fn get_app_names() -> Vec<String> {
         let include_test = env::var("TEST").is_ok_and(|s| s == "1");
 
-        fs::read_dir(Self::SRC_DIR)
+        let mut names = fs::read_dir(Self::APP_SRC_DIR)
             .unwrap()
             .map(|entry| entry.unwrap().file_name().into_string().unwrap())
             .map(|file_name| {
                 file_name
-                    .strip_suffix(Self::SRC_EXTENSION)
+                    .strip_suffix(Self::APP_SRC_EXTENSION)
                     .unwrap()
                     .to_string()
             })
-            .filter(|name| {
-                if include_test {
-                    true
-                } else {
-                    !name.starts_with("test_")
-                }
-            })
-            .collect::<Vec<_>>()
+            .filter(|name| include_test || !name.starts_with("test_"))
+            .collect::<Vec<_>>();
+        names.sort();
+        names
     }
 
-    fn write_app_asms(dst: &mut File, app_names: Vec<String>) -> io::Result<()> {
-        let total_apps = app_names.len();
-
-        // Write the top summary part
-        writeln!(
-            dst,
-            r#"# This file is generated by the build.rs
-
-    .align 3
-    .section .data
-    .global _num_apps
-_num_apps:
-    .quad {total_apps}"#,
-        )?;
-
-        for i in 0..total_apps {
-            writeln!(
-                dst,
-                r#"    .quad app_{i}_name_start
-    .quad app_{i}_name_end
-    .quad app_{i}_elf_start
-    .quad app_{i}_elf_end"#
-            )?;
+    fn write_apps(writer: &mut CpioWriter) -> io::Result<()> {
+        writer.write_entry(Self::APP_DIR, Self::DIR_MODE, &[]);
+        for name in Self::get_app_names() {
+            let elf = fs::read(Path::new(Self::APP_ELF_DIR).join(&name))?;
+            writer.write_entry(&format!("{}/{name}", Self::APP_DIR), Self::APP_MODE, &elf);
         }
+        Ok(())
+    }
 
-        // Write the app names
-        for i in 0..total_apps {
-            writeln!(
-                dst,
-                r#"
-    .section .data
-    .global app_{i}_name_start
-    .global app_{i}_name_end
-app_{i}_name_start:
-    .ascii "{app_name_bytes}"
-app_{i}_name_end:"#,
-                app_name_bytes = app_names[i]
-            )?;
+    /// Writes the entries under the `dir` recursively in the
+    /// order of their names, where `prefix` is the path of
+    /// the `dir` in the archive.
+    fn write_data_dir(writer: &mut CpioWriter, dir: &Path, prefix: &str) -> io::Result<()> {
+        if !dir.is_dir() {
+            return Ok(());
         }
 
-        // Write the per-app part
-        write!(
-            dst,
-            "
-    .align 3"
-        )?;
-
-        for i in 0..total_apps {
-            writeln!(
-                dst,
-                r#"
-    .section .data
-    .global app_{i}_elf_start
-    .global app_{i}_elf_end
-app_{i}_elf_start:
-    .incbin "{elf_dir}/{app_name}"
-app_{i}_elf_end:"#,
-                elf_dir = Self::ELF_DIR,
-                app_name = app_names[i]
-            )?;
+        let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            let name = entry.file_name().into_string().unwrap();
+            let path = format!("{prefix}{name}");
+            if entry.file_type()?.is_dir() {
+                writer.write_entry(&path, Self::DIR_MODE, &[]);
+                Self::write_data_dir(writer, &entry.path(), &format!("{path}/"))?;
+            } else {
+                writer.write_entry(&path, Self::DATA_MODE, &fs::read(entry.path())?);
+            }
         }
-
         Ok(())
     }
 }
+
+/// Builds a cpio archive in the "new ASCII" format, see
+/// [here].
+///
+/// [here]: https://man7.org/linux/man-pages/man5/cpio.5.html
+struct CpioWriter {
+    bytes: Vec<u8>,
+    next_ino: u32,
+}
+
+impl CpioWriter {
+    const MAGIC: &str = "070701";
+    const TRAILER_NAME: &str = "TRAILER!!!";
+
+    fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            next_ino: 1,
+        }
+    }
+
+    fn write_entry(&mut self, name: &str, mode: u32, data: &[u8]) {
+        let ino = self.next_ino;
+        self.next_ino += 1;
+        let nlink = if mode & 0o040000 != 0 { 2 } else { 1 };
+
+        // The fields are ino, mode, uid, gid, nlink, mtime,
+        // filesize, devmajor, devminor, rdevmajor, rdevminor,
+        // namesize and check. The mtime is zero to keep the
+        // archive reproducible.
+        let fields = [
+            ino,
+            mode,
+            0,
+            0,
+            nlink,
+            0,
+            data.len() as u32,
+            0,
+            0,
+            0,
+            0,
+            name.len() as u32 + 1,
+            0,
+        ];
+        self.bytes.extend_from_slice(Self::MAGIC.as_bytes());
+        for field in fields {
+            self.bytes
+                .extend_from_slice(format!("{field:08x}").as_bytes());
+        }
+        self.bytes.extend_from_slice(name.as_bytes());
+        self.bytes.push(0);
+        self.pad();
+        self.bytes.extend_from_slice(data);
+        self.pad();
+    }
+
+    fn pad(&mut self) {
+        self.bytes.resize(self.bytes.len().next_multiple_of(4), 0);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.write_entry(Self::TRAILER_NAME, 0, &[]);
+        self.bytes
+    }
+}
@@ -1,22 +1,68 @@
 pub mod log;
 
 use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicBool, Ordering};
 
+use crate::drivers::ns16550a;
+use crate::machine;
 use crate::sbi;
+use crate::sync::spin::SpinLock;
+
+/// Serializes the prints of the harts so that their lines
+/// do not interleave. A print formats the whole line while
+/// holding it.
+static STDOUT: SpinLock<Stdout> = SpinLock::new(Stdout);
+
+/// Whether the UART has been mapped and configured. The
+/// console falls back to the SBI until then, or for good if
+/// the device tree has no UART.
+static IS_UART_READY: AtomicBool = AtomicBool::new(false);
 
 struct Stdout;
 
+impl Stdout {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        if IS_UART_READY.load(Ordering::Acquire) {
+            ns16550a::write_bytes(bytes);
+        } else {
+            bytes.iter().for_each(|&c| sbi::console_putchar(c as usize));
+        }
+    }
+}
+
 impl fmt::Write for Stdout {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        for c in s.chars() {
-            sbi::console_putchar(c as usize);
-        }
+        self.write_bytes(s.as_bytes());
         Ok(())
     }
 }
 
+/// Switches the console from the SBI to the UART, which
+/// should be called once the kernel space is active.
+pub fn init() {
+    if let Some(uart) = machine::get_uart() {
+        ns16550a::init(uart.base_pa);
+        IS_UART_READY.store(true, Ordering::Release);
+    }
+}
+
 pub fn print(args: fmt::Arguments) {
-    Stdout.write_fmt(args).unwrap();
+    STDOUT.lock().write_fmt(args).unwrap();
+}
+
+/// Writes the `bytes` as is, which need not be UTF-8.
+pub fn write_bytes(bytes: &[u8]) {
+    STDOUT.lock().write_bytes(bytes);
+}
+
+/// Returns the next byte received by the console, or
+/// [None] if nothing has arrived.
+pub fn getchar() -> Option<u8> {
+    if IS_UART_READY.load(Ordering::Acquire) {
+        ns16550a::read_byte()
+    } else {
+        sbi::console_getchar()
+    }
 }
 
 #[macro_export]
@@ -0,0 +1,63 @@
+pub(crate) mod block;
+pub(crate) mod goldfish_rtc;
+pub(crate) mod ns16550a;
+pub(crate) mod plic;
+pub(crate) mod virtio;
+
+extern crate alloc;
+
+use alloc::sync::Arc;
+
+use crate::drivers::block::BlockDevice;
+use crate::drivers::virtio::{DEVICE_ID_BLOCK, VirtioMmio};
+use crate::machine;
+use crate::{info, log, warn};
+
+/// Sets up the drivers of the PLIC, the RTC and the first
+/// virtio block device found in the device tree. The UART
+/// is set up by the console.
+///
+/// The interrupt handlers are registered here, so it should
+/// be called before the harts initialize traps.
+pub(crate) fn init() {
+    let plic = machine::get_plic().expect("No PLIC in the device tree");
+    plic::init(plic.base_pa);
+
+    if let Some(rtc) = machine::get_rtc() {
+        goldfish_rtc::init(rtc.base_pa);
+    }
+
+    init_virtio_blk();
+}
+
+fn init_virtio_blk() {
+    for device in machine::get_virtio_devices() {
+        let transport = match VirtioMmio::probe(device.base_pa) {
+            Ok(Some(transport)) if transport.get_device_id() == DEVICE_ID_BLOCK => transport,
+            Ok(_) => continue,
+            Err(err) => {
+                warn!("virtio at {:#x}: {:?}", device.base_pa, err);
+                continue;
+            }
+        };
+        let Some(irq) = device.irq else {
+            warn!("virtio-blk at {:#x}: No interrupt", device.base_pa);
+            continue;
+        };
+
+        match virtio::blk::init(transport, irq) {
+            Ok(num_blocks) => {
+                info!("virtio-blk at {:#x}: {} blocks", device.base_pa, num_blocks);
+                return;
+            }
+            Err(err) => {
+                warn!("virtio-blk at {:#x}: {:?}", device.base_pa, err);
+            }
+        }
+    }
+}
+
+/// Returns the block device for the file system, if any.
+pub(crate) fn get_block_device() -> Option<Arc<dyn BlockDevice>> {
+    virtio::blk::get_virtio_blk().map(|blk| blk as Arc<dyn BlockDevice>)
+}
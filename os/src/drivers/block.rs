@@ -0,0 +1,30 @@
+/// The size of a block in bytes, which is also the sector
+/// size of virtio-blk.
+pub(crate) const BLOCK_SIZE: usize = 512;
+
+#[allow(dead_code)]
+#[derive(Debug)]
+pub(crate) enum BlockError {
+    /// (block ID, number of blocks).
+    OutOfRange(usize, usize),
+    /// (buffer length).
+    InvalidBuffer(usize),
+    /// (status returned by the device).
+    DeviceError(u8),
+    /// No memory for the DMA buffers.
+    OutOfMemory,
+}
+
+/// A device storing data in blocks of [BLOCK_SIZE] bytes.
+pub(crate) trait BlockDevice: Send + Sync {
+    /// Returns the number of blocks of the device.
+    fn get_num_blocks(&self) -> usize;
+
+    /// Reads the block `block_id` into the `buf`, which must
+    /// be [BLOCK_SIZE] bytes long.
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) -> Result<(), BlockError>;
+
+    /// Writes the `buf` to the block `block_id`, which must
+    /// be [BLOCK_SIZE] bytes long.
+    fn write_block(&self, block_id: usize, buf: &[u8]) -> Result<(), BlockError>;
+}
@@ -0,0 +1,33 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::mm::prelude::get_va_from_pa;
+
+// Register offsets of the goldfish RTC, see [here].
+//
+// [here]: https://android.googlesource.com/platform/external/qemu/+/master/docs/GOLDFISH-VIRTUAL-HARDWARE.TXT
+const TIME_LOW: usize = 0x00;
+const TIME_HIGH: usize = 0x04;
+
+/// The virtual address of the RTC registers, or zero if
+/// there is no RTC.
+static BASE_VA: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the RTC at the `base_pa` as the one to read.
+pub(crate) fn init(base_pa: usize) {
+    BASE_VA.store(get_va_from_pa(base_pa), Ordering::Release);
+}
+
+/// Returns the nanoseconds since the Unix epoch from the
+/// goldfish RTC, or [None] if there is no RTC.
+pub(crate) fn read_time_nanos() -> Option<u64> {
+    let base = match BASE_VA.load(Ordering::Acquire) {
+        0 => return None,
+        base => base,
+    };
+
+    // Reading the low half latches the high half, so it
+    // must be read first.
+    let low = unsafe { ((base + TIME_LOW) as *const u32).read_volatile() };
+    let high = unsafe { ((base + TIME_HIGH) as *const u32).read_volatile() };
+    Some(((high as u64) << 32) | low as u64)
+}
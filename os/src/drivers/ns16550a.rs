@@ -0,0 +1,82 @@
+use core::hint;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::mm::prelude::get_va_from_pa;
+
+/// The virtual address of the UART registers.
+static BASE_VA: AtomicUsize = AtomicUsize::new(0);
+
+// Register offsets of the ns16550a UART, see [here].
+//
+// [here]: http://caro.su/msx/ocm_de1/16550.pdf
+/// Receiver Buffer Register (read).
+const RBR: usize = 0;
+/// Transmitter Holding Register (write).
+const THR: usize = 0;
+/// Interrupt Enable Register.
+const IER: usize = 1;
+/// FIFO Control Register (write).
+const FCR: usize = 2;
+/// Line Control Register.
+const LCR: usize = 3;
+/// Modem Control Register.
+const MCR: usize = 4;
+/// Line Status Register.
+const LSR: usize = 5;
+
+const IER_RX_AVAILABLE: u8 = 1 << 0;
+const FCR_FIFO_ENABLE: u8 = 1 << 0;
+const FCR_RX_FIFO_RESET: u8 = 1 << 1;
+const FCR_TX_FIFO_RESET: u8 = 1 << 2;
+const LCR_EIGHT_BITS: u8 = 0b11;
+/// Gates the interrupt output of the UART.
+const MCR_OUT2: u8 = 1 << 3;
+const LSR_DATA_READY: u8 = 1 << 0;
+const LSR_THR_EMPTY: u8 = 1 << 5;
+
+/// The depth of the transmitter FIFO, which can be filled
+/// at once whenever it is empty.
+const TX_FIFO_DEPTH: usize = 16;
+
+/// Configures the UART at the `base_pa` for 8-bit
+/// characters without parity and with the FIFOs enabled,
+/// and enables the receive interrupt, which is delivered
+/// once the interrupt controller enables it too.
+pub(crate) fn init(base_pa: usize) {
+    BASE_VA.store(get_va_from_pa(base_pa), Ordering::Release);
+    write_reg(IER, 0);
+    write_reg(LCR, LCR_EIGHT_BITS);
+    write_reg(FCR, FCR_FIFO_ENABLE | FCR_RX_FIFO_RESET | FCR_TX_FIFO_RESET);
+    write_reg(MCR, MCR_OUT2);
+    write_reg(IER, IER_RX_AVAILABLE);
+}
+
+/// Transmits the `bytes`, filling the transmitter FIFO
+/// whenever it becomes empty.
+pub(crate) fn write_bytes(bytes: &[u8]) {
+    for chunk in bytes.chunks(TX_FIFO_DEPTH) {
+        while read_reg(LSR) & LSR_THR_EMPTY == 0 {
+            hint::spin_loop();
+        }
+        chunk.iter().for_each(|&c| write_reg(THR, c));
+    }
+}
+
+/// Returns the next received byte, or [None] if nothing
+/// has arrived.
+pub(crate) fn read_byte() -> Option<u8> {
+    if read_reg(LSR) & LSR_DATA_READY == 0 {
+        return None;
+    }
+    Some(read_reg(RBR))
+}
+
+fn read_reg(offset: usize) -> u8 {
+    let addr = BASE_VA.load(Ordering::Acquire) + offset;
+    unsafe { (addr as *const u8).read_volatile() }
+}
+
+fn write_reg(offset: usize, value: u8) {
+    let addr = BASE_VA.load(Ordering::Acquire) + offset;
+    unsafe { (addr as *mut u8).write_volatile(value) }
+}
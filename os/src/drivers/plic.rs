@@ -0,0 +1,78 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::mm::prelude::get_va_from_pa;
+
+// Register offsets of the PLIC, see [here].
+//
+// [here]: https://github.com/riscv/riscv-plic-spec/blob/master/riscv-plic.adoc
+/// The priority of source i is at PRIORITY + 4 * i.
+const PRIORITY: usize = 0x00_0000;
+/// The enable bits of context c are at ENABLE + 0x80 * c.
+const ENABLE: usize = 0x00_2000;
+const ENABLE_STRIDE: usize = 0x80;
+/// The threshold of context c is at THRESHOLD + 0x1000 * c,
+/// followed by its claim/complete register.
+const THRESHOLD: usize = 0x20_0000;
+const CLAIM_COMPLETE: usize = 0x20_0004;
+const CONTEXT_STRIDE: usize = 0x1000;
+
+/// The virtual address of the PLIC registers.
+static BASE_VA: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the PLIC at the `base_pa` as the one to program.
+pub(crate) fn init(base_pa: usize) {
+    BASE_VA.store(get_va_from_pa(base_pa), Ordering::Release);
+}
+
+/// Returns the context of the hart in supervisor mode. The
+/// QEMU virt machine gives each hart an M-mode context
+/// followed by an S-mode one.
+fn get_context(hart_id: usize) -> usize {
+    hart_id * 2 + 1
+}
+
+/// Sets the priority of the source `irq`. A source with
+/// priority zero never interrupts.
+pub(crate) fn set_priority(irq: usize, priority: u32) {
+    write_reg(PRIORITY + 4 * irq, priority);
+}
+
+/// Enables the source `irq` for the hart in supervisor mode.
+pub(crate) fn enable(hart_id: usize, irq: usize) {
+    let offset = ENABLE + ENABLE_STRIDE * get_context(hart_id) + 4 * (irq / 32);
+    write_reg(offset, read_reg(offset) | (1 << (irq % 32)));
+}
+
+/// Sets the threshold of the hart in supervisor mode, which
+/// only takes the sources with higher priorities.
+pub(crate) fn set_threshold(hart_id: usize, threshold: u32) {
+    write_reg(THRESHOLD + CONTEXT_STRIDE * get_context(hart_id), threshold);
+}
+
+/// Claims the highest-priority pending source for the hart,
+/// or returns [None] if nothing is pending.
+pub(crate) fn claim(hart_id: usize) -> Option<usize> {
+    match read_reg(CLAIM_COMPLETE + CONTEXT_STRIDE * get_context(hart_id)) {
+        0 => None,
+        irq => Some(irq as usize),
+    }
+}
+
+/// Completes the source `irq` claimed by the hart, so that
+/// it can interrupt again.
+pub(crate) fn complete(hart_id: usize, irq: usize) {
+    write_reg(
+        CLAIM_COMPLETE + CONTEXT_STRIDE * get_context(hart_id),
+        irq as u32,
+    );
+}
+
+fn read_reg(offset: usize) -> u32 {
+    let addr = BASE_VA.load(Ordering::Acquire) + offset;
+    unsafe { (addr as *const u32).read_volatile() }
+}
+
+fn write_reg(offset: usize, value: u32) {
+    let addr = BASE_VA.load(Ordering::Acquire) + offset;
+    unsafe { (addr as *mut u32).write_volatile(value) }
+}
@@ -0,0 +1,232 @@
+pub(crate) mod blk;
+mod queue;
+
+use crate::mm::prelude::get_va_from_pa;
+
+// Registers of the virtio-mmio transport, see [here].
+//
+// [here]: https://docs.oasis-open.org/virtio/virtio/v1.2/virtio-v1.2.html
+const MAGIC_VALUE: usize = 0x000;
+const VERSION: usize = 0x004;
+const DEVICE_ID: usize = 0x008;
+const DEVICE_FEATURES: usize = 0x010;
+const DEVICE_FEATURES_SEL: usize = 0x014;
+const DRIVER_FEATURES: usize = 0x020;
+const DRIVER_FEATURES_SEL: usize = 0x024;
+/// Legacy only.
+const GUEST_PAGE_SIZE: usize = 0x028;
+const QUEUE_SEL: usize = 0x030;
+const QUEUE_NUM_MAX: usize = 0x034;
+const QUEUE_NUM: usize = 0x038;
+/// Legacy only.
+const QUEUE_ALIGN: usize = 0x03c;
+/// Legacy only.
+const QUEUE_PFN: usize = 0x040;
+const QUEUE_READY: usize = 0x044;
+const QUEUE_NOTIFY: usize = 0x050;
+const INTERRUPT_STATUS: usize = 0x060;
+const INTERRUPT_ACK: usize = 0x064;
+const STATUS: usize = 0x070;
+const QUEUE_DESC_LOW: usize = 0x080;
+const QUEUE_DESC_HIGH: usize = 0x084;
+const QUEUE_DRIVER_LOW: usize = 0x090;
+const QUEUE_DRIVER_HIGH: usize = 0x094;
+const QUEUE_DEVICE_LOW: usize = 0x0a0;
+const QUEUE_DEVICE_HIGH: usize = 0x0a4;
+const CONFIG: usize = 0x100;
+
+const MAGIC: u32 = 0x7472_6976; // "virt"
+const LEGACY_VERSION: u32 = 1;
+const MODERN_VERSION: u32 = 2;
+
+// Bits of the device status.
+const STATUS_ACKNOWLEDGE: u32 = 1;
+const STATUS_DRIVER: u32 = 2;
+const STATUS_DRIVER_OK: u32 = 4;
+const STATUS_FEATURES_OK: u32 = 8;
+const STATUS_FAILED: u32 = 128;
+
+/// The feature bit that a modern device requires the
+/// driver to accept.
+const FEATURE_VERSION_1: u64 = 1 << 32;
+
+const PAGE_SIZE_BYTES: u32 = 4096;
+
+pub(crate) const DEVICE_ID_BLOCK: u32 = 2;
+
+#[allow(dead_code)]
+#[derive(Debug)]
+pub(crate) enum VirtioError {
+    /// (magic value).
+    BadMagic(u32),
+    /// (version).
+    UnsupportedVersion(u32),
+    /// (expected device ID, found device ID).
+    UnexpectedDevice(u32, u32),
+    /// (features offered by the device).
+    FeaturesRejected(u64),
+    /// (queue index).
+    QueueUnavailable(u32),
+    /// No memory for the virtqueue.
+    OutOfMemory,
+}
+
+/// The virtio-mmio transport of a device, which supports
+/// both the legacy and the modern interface.
+#[derive(Debug)]
+pub(crate) struct VirtioMmio {
+    base_va: usize,
+    version: u32,
+}
+
+impl VirtioMmio {
+    /// Returns the transport at the `base_pa`, or [None] if
+    /// no device is plugged into it.
+    pub(crate) fn probe(base_pa: usize) -> Result<Option<Self>, VirtioError> {
+        let mut transport = Self {
+            base_va: get_va_from_pa(base_pa),
+            version: 0,
+        };
+        let magic = transport.read_reg(MAGIC_VALUE);
+        if magic != MAGIC {
+            return Err(VirtioError::BadMagic(magic));
+        }
+        transport.version = transport.read_reg(VERSION);
+        if transport.version != LEGACY_VERSION && transport.version != MODERN_VERSION {
+            return Err(VirtioError::UnsupportedVersion(transport.version));
+        }
+
+        // An empty slot has a zero device ID.
+        match transport.get_device_id() {
+            0 => Ok(None),
+            _ => Ok(Some(transport)),
+        }
+    }
+
+    pub(crate) fn get_device_id(&self) -> u32 {
+        self.read_reg(DEVICE_ID)
+    }
+
+    fn is_legacy(&self) -> bool {
+        self.version == LEGACY_VERSION
+    }
+
+    /// Resets the device and negotiates the features, of
+    /// which the driver accepts only those `supported`.
+    pub(crate) fn begin_init(&self, supported: u64) -> Result<(), VirtioError> {
+        self.write_reg(STATUS, 0);
+        self.write_reg(STATUS, STATUS_ACKNOWLEDGE);
+        self.write_reg(STATUS, STATUS_ACKNOWLEDGE | STATUS_DRIVER);
+
+        let offered = self.read_features();
+        let mut accepted = offered & supported;
+        if !self.is_legacy() {
+            accepted |= offered & FEATURE_VERSION_1;
+        }
+        self.write_features(accepted);
+
+        if self.is_legacy() {
+            self.write_reg(GUEST_PAGE_SIZE, PAGE_SIZE_BYTES);
+            return Ok(());
+        }
+
+        let status = STATUS_ACKNOWLEDGE | STATUS_DRIVER | STATUS_FEATURES_OK;
+        self.write_reg(STATUS, status);
+        if self.read_reg(STATUS) & STATUS_FEATURES_OK == 0 {
+            self.write_reg(STATUS, status | STATUS_FAILED);
+            return Err(VirtioError::FeaturesRejected(offered));
+        }
+        Ok(())
+    }
+
+    /// Tells the device that the driver is ready, after the
+    /// queues are set up.
+    pub(crate) fn finish_init(&self) {
+        let status = self.read_reg(STATUS);
+        self.write_reg(STATUS, status | STATUS_DRIVER_OK);
+    }
+
+    fn read_features(&self) -> u64 {
+        self.write_reg(DEVICE_FEATURES_SEL, 0);
+        let low = self.read_reg(DEVICE_FEATURES) as u64;
+        if self.is_legacy() {
+            return low;
+        }
+        self.write_reg(DEVICE_FEATURES_SEL, 1);
+        let high = self.read_reg(DEVICE_FEATURES) as u64;
+        (high << 32) | low
+    }
+
+    fn write_features(&self, features: u64) {
+        self.write_reg(DRIVER_FEATURES_SEL, 0);
+        self.write_reg(DRIVER_FEATURES, features as u32);
+        if !self.is_legacy() {
+            self.write_reg(DRIVER_FEATURES_SEL, 1);
+            self.write_reg(DRIVER_FEATURES, (features >> 32) as u32);
+        }
+    }
+
+    /// Returns the maximum size of the queue `queue_index`,
+    /// or zero if it is unavailable.
+    fn get_queue_max_size(&self, queue_index: u32) -> u32 {
+        self.write_reg(QUEUE_SEL, queue_index);
+        self.read_reg(QUEUE_NUM_MAX)
+    }
+
+    /// Hands the queue `queue_index` of `size` entries to the
+    /// device, whose rings are at the physical addresses
+    /// `desc_pa`, `avail_pa` and `used_pa`.
+    ///
+    /// The legacy interface takes the rings from one page,
+    /// where the used ring follows the available ring at the
+    /// `used_align`.
+    fn set_queue(
+        &self,
+        queue_index: u32,
+        size: u32,
+        (desc_pa, avail_pa, used_pa): (usize, usize, usize),
+        used_align: u32,
+    ) {
+        self.write_reg(QUEUE_SEL, queue_index);
+        self.write_reg(QUEUE_NUM, size);
+
+        if self.is_legacy() {
+            self.write_reg(QUEUE_ALIGN, used_align);
+            self.write_reg(QUEUE_PFN, (desc_pa / PAGE_SIZE_BYTES as usize) as u32);
+            return;
+        }
+
+        self.write_reg(QUEUE_DESC_LOW, desc_pa as u32);
+        self.write_reg(QUEUE_DESC_HIGH, (desc_pa >> 32) as u32);
+        self.write_reg(QUEUE_DRIVER_LOW, avail_pa as u32);
+        self.write_reg(QUEUE_DRIVER_HIGH, (avail_pa >> 32) as u32);
+        self.write_reg(QUEUE_DEVICE_LOW, used_pa as u32);
+        self.write_reg(QUEUE_DEVICE_HIGH, (used_pa >> 32) as u32);
+        self.write_reg(QUEUE_READY, 1);
+    }
+
+    fn notify_queue(&self, queue_index: u32) {
+        self.write_reg(QUEUE_NOTIFY, queue_index);
+    }
+
+    /// Acknowledges the pending interrupts, so that the
+    /// device can raise new ones.
+    pub(crate) fn ack_interrupts(&self) {
+        let status = self.read_reg(INTERRUPT_STATUS);
+        self.write_reg(INTERRUPT_ACK, status);
+    }
+
+    /// Reads the 32-bit word at `offset` of the device-specific
+    /// configuration.
+    fn read_config(&self, offset: usize) -> u32 {
+        self.read_reg(CONFIG + offset)
+    }
+
+    fn read_reg(&self, offset: usize) -> u32 {
+        unsafe { ((self.base_va + offset) as *const u32).read_volatile() }
+    }
+
+    fn write_reg(&self, offset: usize, value: u32) {
+        unsafe { ((self.base_va + offset) as *mut u32).write_volatile(value) }
+    }
+}
@@ -0,0 +1,228 @@
+extern crate alloc;
+
+use alloc::sync::Arc;
+use core::hint;
+
+use crate::drivers::block::{BLOCK_SIZE, BlockDevice, BlockError};
+use crate::drivers::virtio::queue::{Buffer, QUEUE_SIZE, VirtQueue};
+use crate::drivers::virtio::{DEVICE_ID_BLOCK, VirtioError, VirtioMmio};
+use crate::mm::prelude::{Page, alloc_zeroed_page, get_va_from_pa};
+use crate::sync::spin::SpinLock;
+use crate::sync::wait_queue::WaitQueue;
+use crate::task::prelude::try_get_current_task_id;
+use crate::trap;
+
+// Types of the requests.
+const REQUEST_IN: u32 = 0;
+const REQUEST_OUT: u32 = 1;
+
+const STATUS_OK: u8 = 0;
+
+/// The offset of the capacity in sectors in the device
+/// configuration.
+const CONFIG_CAPACITY: usize = 0;
+
+// The layout of the DMA page of a request, i.e., the header
+// of the type, a reserved word and the sector, followed by
+// the data and the status written by the device.
+const HEADER_OFFSET: usize = 0;
+const HEADER_SIZE: usize = 16;
+const DATA_OFFSET: usize = HEADER_OFFSET + HEADER_SIZE;
+const STATUS_OFFSET: usize = DATA_OFFSET + BLOCK_SIZE;
+
+/// The virtio-blk device that the interrupt handler serves.
+static VIRTIO_BLK: SpinLock<Option<Arc<VirtioBlk>>> = SpinLock::new(None);
+
+/// Sets up the block device of the `transport`, whose
+/// completions are signaled by the interrupt source `irq`.
+/// Returns the number of blocks of the device.
+pub(crate) fn init(transport: VirtioMmio, irq: usize) -> Result<usize, VirtioError> {
+    let blk = VirtioBlk::new(transport)?;
+    let num_blocks = blk.num_blocks;
+    *VIRTIO_BLK.lock() = Some(Arc::new(blk));
+    trap::register_irq_handler(irq, handle_virtio_blk_interrupt);
+    Ok(num_blocks)
+}
+
+/// Returns the block device set up by [init], if any.
+pub(crate) fn get_virtio_blk() -> Option<Arc<VirtioBlk>> {
+    VIRTIO_BLK.lock().clone()
+}
+
+fn handle_virtio_blk_interrupt() {
+    if let Some(blk) = get_virtio_blk() {
+        blk.handle_interrupt();
+    }
+}
+
+/// A virtio-blk device with one request queue. A request
+/// goes through a bounce buffer in a DMA page, and the task
+/// blocks until the device completes it.
+pub(crate) struct VirtioBlk {
+    transport: VirtioMmio,
+    queue: SpinLock<RequestQueue>,
+    /// The tasks waiting for their requests to complete, or
+    /// for descriptors to submit them.
+    waiters: WaitQueue,
+    num_blocks: usize,
+}
+
+struct RequestQueue {
+    virtqueue: VirtQueue,
+    /// Whether the request headed by each descriptor has
+    /// completed but not been taken by its submitter.
+    completed: [bool; QUEUE_SIZE as usize],
+}
+
+impl VirtioBlk {
+    fn new(transport: VirtioMmio) -> Result<Self, VirtioError> {
+        let device_id = transport.get_device_id();
+        if device_id != DEVICE_ID_BLOCK {
+            return Err(VirtioError::UnexpectedDevice(DEVICE_ID_BLOCK, device_id));
+        }
+
+        transport.begin_init(0)?;
+        let virtqueue = VirtQueue::new(&transport, 0)?;
+        let capacity_low = transport.read_config(CONFIG_CAPACITY) as usize;
+        let capacity_high = transport.read_config(CONFIG_CAPACITY + 4) as usize;
+        transport.finish_init();
+
+        Ok(Self {
+            transport,
+            queue: SpinLock::new(RequestQueue {
+                virtqueue,
+                completed: [false; QUEUE_SIZE as usize],
+            }),
+            waiters: WaitQueue::new(),
+            num_blocks: (capacity_high << 32) | capacity_low,
+        })
+    }
+
+    /// Takes the completed requests and wakes their tasks.
+    fn handle_interrupt(&self) {
+        self.transport.ack_interrupts();
+        self.collect_completed();
+        self.waiters.wake_all();
+    }
+
+    fn collect_completed(&self) {
+        let mut queue = self.queue.lock();
+        while let Some(head) = queue.virtqueue.pop_used() {
+            queue.completed[head as usize] = true;
+        }
+    }
+
+    /// Submits the request in the `page` for the block
+    /// `block_id` and waits until it completes. Returns the
+    /// status written by the device.
+    fn do_request(&self, page: &Page, is_write: bool, block_id: usize) -> u8 {
+        let va = get_va_from_pa(page.get_pa());
+        let request_type = if is_write { REQUEST_OUT } else { REQUEST_IN };
+        unsafe {
+            ((va + HEADER_OFFSET) as *mut u32).write_volatile(request_type);
+            ((va + HEADER_OFFSET + 8) as *mut u64).write_volatile(block_id as u64);
+        }
+
+        let pa = page.get_pa();
+        let buffers: [Buffer; 3] = [
+            (pa + HEADER_OFFSET, HEADER_SIZE, false),
+            (pa + DATA_OFFSET, BLOCK_SIZE, !is_write),
+            (pa + STATUS_OFFSET, 1, true),
+        ];
+        let head = self.submit(&buffers);
+        self.wait_for(head);
+
+        unsafe { ((va + STATUS_OFFSET) as *const u8).read_volatile() }
+    }
+
+    /// Adds the chain of `buffers` to the queue, waiting for
+    /// free descriptors if needed. Returns its head.
+    fn submit(&self, buffers: &[Buffer]) -> u16 {
+        loop {
+            if let Some(head) = self
+                .queue
+                .lock()
+                .virtqueue
+                .push_chain(&self.transport, buffers)
+            {
+                return head;
+            }
+            self.wait_until(|queue| queue.virtqueue.get_num_free() >= buffers.len());
+        }
+    }
+
+    /// Waits until the request headed by `head` completes and
+    /// frees its descriptors.
+    fn wait_for(&self, head: u16) {
+        let head = head as usize;
+        self.wait_until(|queue| queue.completed[head]);
+
+        let mut queue = self.queue.lock();
+        queue.completed[head] = false;
+        queue.virtqueue.free_chain(head as u16);
+        drop(queue);
+
+        // Some tasks may be waiting for the descriptors.
+        self.waiters.wake_all();
+    }
+
+    /// Waits until the `condition` on the queue holds. A task
+    /// blocks for the interrupts, while the boot code, which
+    /// runs without them, polls the device.
+    fn wait_until(&self, condition: impl Fn(&RequestQueue) -> bool) {
+        if try_get_current_task_id().is_none() {
+            while !condition(&self.queue.lock()) {
+                self.collect_completed();
+                hint::spin_loop();
+            }
+            return;
+        }
+
+        while self.waiters.wait_if(|| !condition(&self.queue.lock())) {}
+    }
+
+    fn check_request(&self, block_id: usize, buf_len: usize) -> Result<(), BlockError> {
+        if block_id >= self.num_blocks {
+            return Err(BlockError::OutOfRange(block_id, self.num_blocks));
+        }
+        if buf_len != BLOCK_SIZE {
+            return Err(BlockError::InvalidBuffer(buf_len));
+        }
+        Ok(())
+    }
+}
+
+impl BlockDevice for VirtioBlk {
+    fn get_num_blocks(&self) -> usize {
+        self.num_blocks
+    }
+
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) -> Result<(), BlockError> {
+        self.check_request(block_id, buf.len())?;
+        let page = alloc_zeroed_page().ok_or(BlockError::OutOfMemory)?;
+
+        match self.do_request(&page, false, block_id) {
+            STATUS_OK => {
+                let va = get_va_from_pa(page.get_pa()) + DATA_OFFSET;
+                let data = unsafe { core::slice::from_raw_parts(va as *const u8, BLOCK_SIZE) };
+                buf.copy_from_slice(data);
+                Ok(())
+            }
+            status => Err(BlockError::DeviceError(status)),
+        }
+    }
+
+    fn write_block(&self, block_id: usize, buf: &[u8]) -> Result<(), BlockError> {
+        self.check_request(block_id, buf.len())?;
+        let page = alloc_zeroed_page().ok_or(BlockError::OutOfMemory)?;
+
+        let va = get_va_from_pa(page.get_pa()) + DATA_OFFSET;
+        let data = unsafe { core::slice::from_raw_parts_mut(va as *mut u8, BLOCK_SIZE) };
+        data.copy_from_slice(buf);
+
+        match self.do_request(&page, true, block_id) {
+            STATUS_OK => Ok(()),
+            status => Err(BlockError::DeviceError(status)),
+        }
+    }
+}
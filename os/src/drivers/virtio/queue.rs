@@ -0,0 +1,168 @@
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::sync::atomic::{Ordering, fence};
+
+use crate::drivers::virtio::{VirtioError, VirtioMmio};
+use crate::mm::prelude::{Page, alloc_zeroed_page, get_va_from_pa};
+
+/// The number of descriptors of a queue, which keeps its
+/// rings within one page.
+pub(super) const QUEUE_SIZE: u16 = 16;
+
+const DESC_FLAG_NEXT: u16 = 1;
+const DESC_FLAG_WRITE: u16 = 2;
+
+// The layout of the rings in the page. The used ring is
+// aligned to USED_ALIGN, which is chosen so that the legacy
+// interface of QEMU puts it at the same offset.
+const DESC_SIZE: usize = 16;
+const USED_ELEM_SIZE: usize = 8;
+const USED_ALIGN: usize = 16;
+const DESC_OFFSET: usize = 0;
+const AVAIL_OFFSET: usize = DESC_OFFSET + DESC_SIZE * QUEUE_SIZE as usize;
+const AVAIL_IDX_OFFSET: usize = AVAIL_OFFSET + 2;
+const AVAIL_RING_OFFSET: usize = AVAIL_OFFSET + 4;
+/// The available ring has the flags, the index, the ring
+/// and the used event.
+const USED_OFFSET: usize =
+    (AVAIL_RING_OFFSET + 2 * QUEUE_SIZE as usize + 2).next_multiple_of(USED_ALIGN);
+const USED_IDX_OFFSET: usize = USED_OFFSET + 2;
+const USED_RING_OFFSET: usize = USED_OFFSET + 4;
+
+/// A buffer of a descriptor chain, as its (pa, len) and
+/// whether the device writes to it.
+pub(super) type Buffer = (usize, usize, bool);
+
+/// A split virtqueue, whose descriptor table, available ring
+/// and used ring share one DMA page.
+pub(super) struct VirtQueue {
+    queue_index: u32,
+    page: Page,
+    free_descs: Vec<u16>,
+    /// The index of the next entry of the available ring.
+    avail_idx: u16,
+    /// The index of the next entry of the used ring to take.
+    last_used_idx: u16,
+}
+
+impl VirtQueue {
+    /// Creates the queue `queue_index` and hands it to the
+    /// device of the `transport`.
+    pub(super) fn new(transport: &VirtioMmio, queue_index: u32) -> Result<Self, VirtioError> {
+        if transport.get_queue_max_size(queue_index) < QUEUE_SIZE as u32 {
+            return Err(VirtioError::QueueUnavailable(queue_index));
+        }
+
+        let page = alloc_zeroed_page().ok_or(VirtioError::OutOfMemory)?;
+        let pa = page.get_pa();
+        transport.set_queue(
+            queue_index,
+            QUEUE_SIZE as u32,
+            (pa + DESC_OFFSET, pa + AVAIL_OFFSET, pa + USED_OFFSET),
+            USED_ALIGN as u32,
+        );
+
+        Ok(Self {
+            queue_index,
+            page,
+            free_descs: (0..QUEUE_SIZE).rev().collect(),
+            avail_idx: 0,
+            last_used_idx: 0,
+        })
+    }
+
+    pub(super) fn get_num_free(&self) -> usize {
+        self.free_descs.len()
+    }
+
+    /// Adds the chain of `buffers` to the available ring and
+    /// notifies the device. Returns the head descriptor, or
+    /// [None] if too few descriptors are free.
+    pub(super) fn push_chain(&mut self, transport: &VirtioMmio, buffers: &[Buffer]) -> Option<u16> {
+        if buffers.is_empty() || buffers.len() > self.free_descs.len() {
+            return None;
+        }
+
+        let descs = self
+            .free_descs
+            .split_off(self.free_descs.len() - buffers.len());
+        for (i, &(pa, len, is_device_writable)) in buffers.iter().enumerate() {
+            let mut flags = 0;
+            if is_device_writable {
+                flags |= DESC_FLAG_WRITE;
+            }
+            let next = descs.get(i + 1).copied();
+            if next.is_some() {
+                flags |= DESC_FLAG_NEXT;
+            }
+            self.write_desc(descs[i], pa, len, flags, next.unwrap_or(0));
+        }
+
+        let head = descs[0];
+        let slot = (self.avail_idx % QUEUE_SIZE) as usize;
+        self.write(AVAIL_RING_OFFSET + 2 * slot, head);
+        // The device must see the entry before the index, and
+        // the index before the notification.
+        fence(Ordering::SeqCst);
+        self.avail_idx = self.avail_idx.wrapping_add(1);
+        self.write(AVAIL_IDX_OFFSET, self.avail_idx);
+        fence(Ordering::SeqCst);
+
+        transport.notify_queue(self.queue_index);
+        Some(head)
+    }
+
+    /// Takes the next chain completed by the device and
+    /// returns its head descriptor, or [None] if there is
+    /// none.
+    pub(super) fn pop_used(&mut self) -> Option<u16> {
+        let used_idx: u16 = self.read(USED_IDX_OFFSET);
+        if used_idx == self.last_used_idx {
+            return None;
+        }
+        // Read the entry only after seeing the index.
+        fence(Ordering::SeqCst);
+
+        let slot = (self.last_used_idx % QUEUE_SIZE) as usize;
+        let head: u32 = self.read(USED_RING_OFFSET + USED_ELEM_SIZE * slot);
+        self.last_used_idx = self.last_used_idx.wrapping_add(1);
+        Some(head as u16)
+    }
+
+    /// Returns the descriptors of the chain at `head` to the
+    /// free ones.
+    pub(super) fn free_chain(&mut self, head: u16) {
+        let mut desc = head;
+        loop {
+            self.free_descs.push(desc);
+            let flags: u16 = self.read(Self::get_desc_offset(desc) + 12);
+            if flags & DESC_FLAG_NEXT == 0 {
+                break;
+            }
+            desc = self.read(Self::get_desc_offset(desc) + 14);
+        }
+    }
+
+    fn get_desc_offset(desc: u16) -> usize {
+        DESC_OFFSET + DESC_SIZE * desc as usize
+    }
+
+    fn write_desc(&mut self, desc: u16, pa: usize, len: usize, flags: u16, next: u16) {
+        let offset = Self::get_desc_offset(desc);
+        self.write(offset, pa as u64);
+        self.write(offset + 8, len as u32);
+        self.write(offset + 12, flags);
+        self.write(offset + 14, next);
+    }
+
+    fn read<T: Copy>(&self, offset: usize) -> T {
+        let va = get_va_from_pa(self.page.get_pa()) + offset;
+        unsafe { (va as *const T).read_volatile() }
+    }
+
+    fn write<T: Copy>(&mut self, offset: usize, value: T) {
+        let va = get_va_from_pa(self.page.get_pa()) + offset;
+        unsafe { (va as *mut T).write_volatile(value) }
+    }
+}
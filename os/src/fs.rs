@@ -0,0 +1,260 @@
+extern crate alloc;
+
+mod dev;
+mod easy_fs;
+mod fd_table;
+mod initramfs;
+mod pipe;
+pub(crate) mod prelude;
+mod stdio;
+mod tmpfs;
+mod vfs;
+
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::drivers::block::BlockError;
+use crate::fs::stdio::{enable_console_polling, poll_console_input};
+use crate::fs::vfs::{Dentry, DirEntry, InodeFile, InodeType, MAX_NAME_LEN};
+use crate::machine;
+use crate::trap;
+
+// Flags of [open], see [here].
+//
+// [here]: https://github.com/torvalds/linux/blob/master/include/uapi/asm-generic/fcntl.h
+const O_RDONLY: usize = 0;
+const O_WRONLY: usize = 1;
+const O_RDWR: usize = 2;
+const O_ACCMODE: usize = 3;
+const O_CREAT: usize = 0o100;
+const O_TRUNC: usize = 0o1000;
+
+// File types in [Stat::mode].
+const S_IFMT: u32 = 0o170000;
+const S_IFIFO: u32 = 0o010000;
+const S_IFCHR: u32 = 0o020000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+
+/// Where the file system on the block device is mounted.
+const DISK_MOUNT_POINT: &str = "/mnt";
+
+/// A file that a task accesses through a file descriptor.
+/// It is shared by the descriptors duplicated from the
+/// same one, including those inherited by the children.
+pub(crate) trait File: Send + Sync {
+    fn readable(&self) -> bool;
+
+    fn writable(&self) -> bool;
+
+    /// Reads into the `buf` and returns the number of bytes
+    /// read, which is zero at the end of the file.
+    fn read(&self, buf: &mut [u8]) -> Result<usize, FsError>;
+
+    /// Writes the `buf` and returns the number of bytes
+    /// written.
+    fn write(&self, buf: &[u8]) -> Result<usize, FsError>;
+
+    fn stat(&self) -> Stat;
+
+    /// Returns the next at most `count` entries of the
+    /// directory, which are none at its end.
+    fn read_dir(&self, _count: usize) -> Result<Vec<DirEntry>, FsError> {
+        Err(FsError::NotDirectory)
+    }
+}
+
+/// The status of a [File], which is mirrored in user space.
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub(crate) struct Stat {
+    /// The ID of the device containing the file.
+    pub(crate) dev: u64,
+    /// The inode number of the file.
+    pub(crate) ino: u64,
+    /// The file type and permission bits.
+    pub(crate) mode: u32,
+    /// The number of hard links to the file.
+    pub(crate) nlink: u32,
+    /// The size of the file in bytes.
+    pub(crate) size: u64,
+}
+
+/// An entry of a directory read by getdents, which is
+/// mirrored in user space.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub(crate) struct Dirent {
+    /// The inode number of the entry.
+    pub(crate) ino: u64,
+    /// The file type bits, as in [Stat::mode].
+    pub(crate) file_type: u32,
+    /// The length of the name in bytes.
+    pub(crate) name_len: u32,
+    /// The name followed by NULs.
+    pub(crate) name: [u8; MAX_NAME_LEN + 1],
+}
+
+impl From<&DirEntry> for Dirent {
+    fn from(entry: &DirEntry) -> Self {
+        let mut name = [0; MAX_NAME_LEN + 1];
+        let len = entry.name.len().min(MAX_NAME_LEN);
+        name[..len].copy_from_slice(&entry.name.as_bytes()[..len]);
+        Self {
+            ino: entry.ino,
+            file_type: entry.file_type,
+            name_len: len as u32,
+            name,
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug)]
+pub(crate) enum FsError {
+    NotFound,
+    AlreadyExists,
+    IsDirectory,
+    NotDirectory,
+    /// The directory to remove has entries.
+    NotEmpty,
+    /// The directory to remove is a mount point.
+    Busy,
+    /// The file system cannot be modified.
+    ReadOnly,
+    /// (requested flags).
+    InvalidFlags(usize),
+    /// The name is empty or too long.
+    InvalidName,
+    /// No free inode or data block.
+    NoSpace,
+    /// (requested size).
+    FileTooLarge(usize),
+    /// (magic of the super block).
+    BadMagic(u32),
+    /// (error of the block device).
+    Io(BlockError),
+    /// The file is not opened for the access.
+    BadAccess,
+    /// The pipe has no read end open.
+    BrokenPipe,
+    /// A signal arrived while blocked.
+    Interrupted,
+}
+
+/// Registers the handler of the console input, or makes
+/// the scheduling loop poll it without one, and sets up
+/// the tree of the file systems: the initramfs as the root,
+/// the devices, tmpfs and the file system on the block
+/// device. It should be called after the drivers are set up
+/// and before the harts initialize traps.
+pub(crate) fn init() {
+    match machine::get_uart().and_then(|uart| uart.irq) {
+        Some(irq) => trap::register_irq_handler(irq, poll_console_input),
+        None => enable_console_polling(),
+    }
+    initramfs::init();
+    dev::init();
+    tmpfs::init();
+    easy_fs::init();
+}
+
+/// Opens the file at the `path` relative to the `cwd` with
+/// the `flags`. A directory can only be opened read-only.
+pub(crate) fn open(cwd: &Arc<Dentry>, path: &str, flags: usize) -> Result<Arc<dyn File>, FsError> {
+    let (readable, writable) = match flags & O_ACCMODE {
+        O_RDONLY => (true, false),
+        O_WRONLY => (false, true),
+        O_RDWR => (true, true),
+        _ => return Err(FsError::InvalidFlags(flags)),
+    };
+    if flags & !(O_ACCMODE | O_CREAT | O_TRUNC) != 0 {
+        return Err(FsError::InvalidFlags(flags));
+    }
+    let truncate = flags & O_TRUNC != 0;
+
+    let dentry = if flags & O_CREAT != 0 {
+        let (parent, name) = vfs::lookup_parent(cwd, path)?;
+        match vfs::lookup_child(&parent, name) {
+            Err(FsError::NotFound) => {
+                // Another task may have created it meanwhile.
+                match parent.get_inode().create(name, InodeType::File) {
+                    Ok(_) | Err(FsError::AlreadyExists) => vfs::lookup_child(&parent, name)?,
+                    Err(err) => return Err(err),
+                }
+            }
+            result => result?,
+        }
+    } else {
+        vfs::lookup(cwd, path)?
+    };
+
+    if dentry.is_dir()? && (writable || truncate) {
+        return Err(FsError::IsDirectory);
+    }
+    if truncate {
+        dentry.get_inode().truncate()?;
+    }
+    Ok(Arc::new(InodeFile::new(
+        dentry.get_inode().clone(),
+        readable,
+        writable,
+    )))
+}
+
+/// Creates an empty directory at the `path` relative to the
+/// `cwd`.
+pub(crate) fn mkdir(cwd: &Arc<Dentry>, path: &str) -> Result<(), FsError> {
+    let (parent, name) = vfs::lookup_parent(cwd, path)?;
+    parent.get_inode().create(name, InodeType::Directory)?;
+    Ok(())
+}
+
+/// Removes the file at the `path` relative to the `cwd`,
+/// or the empty directory if `is_dir`.
+pub(crate) fn unlink(cwd: &Arc<Dentry>, path: &str, is_dir: bool) -> Result<(), FsError> {
+    let (parent, name) = vfs::lookup_parent(cwd, path)?;
+    let dentry = vfs::lookup_child(&parent, name)?;
+    match dentry.is_dir()? {
+        true if !is_dir => return Err(FsError::IsDirectory),
+        false if is_dir => return Err(FsError::NotDirectory),
+        _ => {}
+    }
+    if vfs::is_mount_point(&dentry) {
+        return Err(FsError::Busy);
+    }
+    parent.get_inode().unlink(name)
+}
+
+/// Returns the directory at the `path` relative to the
+/// `cwd`, e.g., to become the new cwd.
+pub(crate) fn lookup_dir(cwd: &Arc<Dentry>, path: &str) -> Result<Arc<Dentry>, FsError> {
+    let dentry = vfs::lookup(cwd, path)?;
+    if !dentry.is_dir()? {
+        return Err(FsError::NotDirectory);
+    }
+    Ok(dentry)
+}
+
+/// Returns the content of the file at the absolute `path`.
+pub(crate) fn read_file(path: &str) -> Result<Vec<u8>, FsError> {
+    let inode = vfs::lookup(&vfs::get_root(), path)?.get_inode().clone();
+    let mut content = vec![0; inode.stat()?.size as usize];
+    let len = inode.read_at(0, &mut content)?;
+    content.truncate(len);
+    Ok(content)
+}
+
+/// Returns the names in the directory at the absolute
+/// `path`.
+pub(crate) fn read_dir(path: &str) -> Result<Vec<String>, FsError> {
+    let dentry = vfs::lookup(&vfs::get_root(), path)?;
+    Ok(dentry
+        .get_inode()
+        .read_dir()?
+        .into_iter()
+        .map(|entry| entry.name)
+        .collect())
+}
@@ -0,0 +1,129 @@
+extern crate alloc;
+
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::fs::vfs::{self, DirEntry, Inode, InodeType};
+use crate::fs::{FsError, S_IFCHR, S_IFDIR, Stat};
+use crate::{log, warn};
+
+/// The device number of the character devices below.
+const MEM_DEV: u64 = 1;
+/// Where the devices are mounted.
+const DEV_MOUNT_POINT: &str = "/dev";
+
+// Inode numbers of the devices.
+const DEV_DIR_INO: u64 = 1;
+const NULL_INO: u64 = 3;
+const ZERO_INO: u64 = 5;
+
+/// The devices in [DEV_MOUNT_POINT] by name.
+const DEVICES: [(&str, u64); 2] = [("null", NULL_INO), ("zero", ZERO_INO)];
+
+/// Mounts the directory of the devices.
+pub(super) fn init() {
+    if let Err(err) = vfs::mount(DEV_MOUNT_POINT, Arc::new(DevDir)) {
+        warn!("dev: Failed to mount, err={:?}", err);
+    }
+}
+
+/// The directory holding the devices, which cannot be
+/// modified.
+struct DevDir;
+
+impl Inode for DevDir {
+    fn stat(&self) -> Result<Stat, FsError> {
+        Ok(Stat {
+            dev: MEM_DEV,
+            ino: DEV_DIR_INO,
+            mode: S_IFDIR | 0o755,
+            nlink: 1,
+            size: 0,
+        })
+    }
+
+    fn lookup(&self, name: &str) -> Result<Arc<dyn Inode>, FsError> {
+        match name {
+            "null" => Ok(Arc::new(Null)),
+            "zero" => Ok(Arc::new(Zero)),
+            _ => Err(FsError::NotFound),
+        }
+    }
+
+    fn create(&self, _name: &str, _inode_type: InodeType) -> Result<Arc<dyn Inode>, FsError> {
+        Err(FsError::ReadOnly)
+    }
+
+    fn unlink(&self, _name: &str) -> Result<(), FsError> {
+        Err(FsError::ReadOnly)
+    }
+
+    fn read_dir(&self) -> Result<Vec<DirEntry>, FsError> {
+        Ok(DEVICES
+            .iter()
+            .map(|&(name, ino)| DirEntry {
+                name: name.to_string(),
+                ino,
+                file_type: S_IFCHR,
+            })
+            .collect())
+    }
+}
+
+/// Discards the writes and reads as an empty file, as
+/// `/dev/null`.
+struct Null;
+
+impl Inode for Null {
+    fn read_at(&self, _offset: usize, _buf: &mut [u8]) -> Result<usize, FsError> {
+        Ok(0)
+    }
+
+    fn write_at(&self, _offset: usize, buf: &[u8]) -> Result<usize, FsError> {
+        Ok(buf.len())
+    }
+
+    fn truncate(&self) -> Result<(), FsError> {
+        Ok(())
+    }
+
+    fn stat(&self) -> Result<Stat, FsError> {
+        Ok(Stat {
+            dev: MEM_DEV,
+            ino: NULL_INO,
+            mode: S_IFCHR | 0o666,
+            nlink: 1,
+            size: 0,
+        })
+    }
+}
+
+/// Discards the writes and reads as endless zeros, as
+/// `/dev/zero`.
+struct Zero;
+
+impl Inode for Zero {
+    fn read_at(&self, _offset: usize, buf: &mut [u8]) -> Result<usize, FsError> {
+        buf.fill(0);
+        Ok(buf.len())
+    }
+
+    fn write_at(&self, _offset: usize, buf: &[u8]) -> Result<usize, FsError> {
+        Ok(buf.len())
+    }
+
+    fn truncate(&self) -> Result<(), FsError> {
+        Ok(())
+    }
+
+    fn stat(&self) -> Result<Stat, FsError> {
+        Ok(Stat {
+            dev: MEM_DEV,
+            ino: ZERO_INO,
+            mode: S_IFCHR | 0o666,
+            nlink: 1,
+            size: 0,
+        })
+    }
+}
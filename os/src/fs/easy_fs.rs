@@ -0,0 +1,239 @@
+extern crate alloc;
+
+mod bitmap;
+mod block_cache;
+mod inode;
+mod layout;
+
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::drivers;
+use crate::drivers::block::{self, BlockError};
+use crate::fs::easy_fs::bitmap::Bitmap;
+use crate::fs::easy_fs::block_cache::BlockCache;
+use crate::fs::easy_fs::layout::{BITS_PER_BLOCK, BLOCK_SIZE, ROOT_INODE_ID, SuperBlock};
+use crate::fs::vfs::{self, DirEntry, Inode, InodeType};
+use crate::fs::{DISK_MOUNT_POINT, FsError, S_IFDIR, S_IFREG, Stat};
+use crate::sync::mutex::Mutex;
+use crate::{debug, info, log, warn};
+
+const _: () = assert!(BLOCK_SIZE == block::BLOCK_SIZE);
+
+/// The device number of easy-fs.
+const EASY_FS_DEV: u64 = 2;
+
+/// The file system on the block device, if it is mounted.
+static EASY_FS: Mutex<Option<EasyFs>> = Mutex::new(None);
+
+/// An inode-based file system, whose layout is described in
+/// [layout].
+///
+/// The blocks modified by an operation are written back at
+/// its end, so the disk stays consistent when the machine
+/// powers off.
+struct EasyFs {
+    cache: BlockCache,
+    super_block: SuperBlock,
+    inode_bitmap: Bitmap,
+    data_bitmap: Bitmap,
+}
+
+impl EasyFs {
+    fn mount(mut cache: BlockCache) -> Result<Self, FsError> {
+        let block = cache.get(0)?;
+        let super_block =
+            SuperBlock::decode(block).ok_or(FsError::BadMagic(layout::read_u32(block, 0)))?;
+        let num_blocks = cache.get_num_blocks();
+        if super_block.total_blocks as usize > num_blocks {
+            let last_block = super_block.total_blocks as usize - 1;
+            return Err(FsError::Io(BlockError::OutOfRange(last_block, num_blocks)));
+        }
+
+        let inode_bitmap = Bitmap::new(
+            super_block.get_inode_bitmap_start(),
+            super_block.inode_bitmap_blocks,
+            super_block.inode_bitmap_blocks * BITS_PER_BLOCK as u32,
+        );
+        let data_bitmap = Bitmap::new(
+            super_block.get_data_bitmap_start(),
+            super_block.data_bitmap_blocks,
+            super_block.data_area_blocks,
+        );
+        Ok(Self {
+            cache,
+            super_block,
+            inode_bitmap,
+            data_bitmap,
+        })
+    }
+
+    /// Fails with [FsError::NotDirectory] unless the inode
+    /// `inode_id` is a directory.
+    fn check_dir(&mut self, inode_id: u32) -> Result<(), FsError> {
+        if !self.read_inode(inode_id)?.is_dir() {
+            return Err(FsError::NotDirectory);
+        }
+        Ok(())
+    }
+}
+
+/// Runs the `f` on the mounted file system, and writes the
+/// blocks it has modified back.
+fn with_easy_fs<T>(f: impl FnOnce(&mut EasyFs) -> Result<T, FsError>) -> Result<T, FsError> {
+    let mut easy_fs = EASY_FS.lock();
+    let easy_fs = easy_fs.as_mut().ok_or(FsError::NotFound)?;
+    let result = f(easy_fs);
+    easy_fs.cache.sync()?;
+    result
+}
+
+/// Mounts the file system on the block device at
+/// [DISK_MOUNT_POINT], if any.
+pub(super) fn init() {
+    let Some(device) = drivers::get_block_device() else {
+        warn!("easy-fs: No block device");
+        return;
+    };
+
+    match EasyFs::mount(BlockCache::new(device)) {
+        Ok(easy_fs) => {
+            info!("easy-fs: Mounted {:?}", easy_fs.super_block);
+            *EASY_FS.lock() = Some(easy_fs);
+            log_root_dir();
+        }
+        Err(err) => {
+            warn!("easy-fs: Failed to mount, err={:?}", err);
+            return;
+        }
+    }
+
+    let root = Arc::new(EasyFsInode {
+        inode_id: ROOT_INODE_ID,
+    });
+    if let Err(err) = vfs::mount(DISK_MOUNT_POINT, root) {
+        warn!(
+            "easy-fs: Failed to mount at {}, err={:?}",
+            DISK_MOUNT_POINT, err
+        );
+    }
+}
+
+fn log_root_dir() {
+    let result = with_easy_fs(|easy_fs| {
+        for entry in easy_fs.read_dir(ROOT_INODE_ID)? {
+            let size = easy_fs.read_inode(entry.get_inode_id())?.size;
+            debug!(
+                "easy-fs: inode {} size={}, name={}",
+                entry.get_inode_id(),
+                size,
+                entry.get_name()
+            );
+        }
+        Ok(())
+    });
+    if let Err(err) = result {
+        warn!("easy-fs: Failed to read the root directory, err={:?}", err);
+    }
+}
+
+/// A file or a directory of easy-fs, whose operations go
+/// through the block cache.
+struct EasyFsInode {
+    inode_id: u32,
+}
+
+impl Inode for EasyFsInode {
+    fn stat(&self) -> Result<Stat, FsError> {
+        let inode = with_easy_fs(|easy_fs| easy_fs.read_inode(self.inode_id))?;
+        let mode = if inode.is_dir() {
+            S_IFDIR | 0o755
+        } else {
+            S_IFREG | 0o644
+        };
+        Ok(Stat {
+            dev: EASY_FS_DEV,
+            ino: self.inode_id as u64,
+            mode,
+            nlink: 1,
+            size: inode.size as u64,
+        })
+    }
+
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize, FsError> {
+        with_easy_fs(|easy_fs| {
+            if easy_fs.read_inode(self.inode_id)?.is_dir() {
+                return Err(FsError::IsDirectory);
+            }
+            easy_fs.read_at(self.inode_id, offset, buf)
+        })
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> Result<usize, FsError> {
+        with_easy_fs(|easy_fs| {
+            if easy_fs.read_inode(self.inode_id)?.is_dir() {
+                return Err(FsError::IsDirectory);
+            }
+            easy_fs.write_at(self.inode_id, offset, buf)
+        })
+    }
+
+    fn truncate(&self) -> Result<(), FsError> {
+        with_easy_fs(|easy_fs| {
+            if easy_fs.read_inode(self.inode_id)?.is_dir() {
+                return Err(FsError::IsDirectory);
+            }
+            easy_fs.truncate(self.inode_id)
+        })
+    }
+
+    fn lookup(&self, name: &str) -> Result<Arc<dyn Inode>, FsError> {
+        let inode_id = with_easy_fs(|easy_fs| {
+            easy_fs.check_dir(self.inode_id)?;
+            easy_fs.find(self.inode_id, name)?.ok_or(FsError::NotFound)
+        })?;
+        Ok(Arc::new(EasyFsInode { inode_id }))
+    }
+
+    fn create(&self, name: &str, inode_type: InodeType) -> Result<Arc<dyn Inode>, FsError> {
+        let inode_type = match inode_type {
+            InodeType::File => layout::InodeType::File,
+            InodeType::Directory => layout::InodeType::Directory,
+        };
+        let inode_id = with_easy_fs(|easy_fs| {
+            easy_fs.check_dir(self.inode_id)?;
+            easy_fs.create(self.inode_id, name, inode_type)
+        })?;
+        Ok(Arc::new(EasyFsInode { inode_id }))
+    }
+
+    fn unlink(&self, name: &str) -> Result<(), FsError> {
+        with_easy_fs(|easy_fs| {
+            easy_fs.check_dir(self.inode_id)?;
+            easy_fs.unlink(self.inode_id, name)
+        })
+    }
+
+    fn read_dir(&self) -> Result<Vec<DirEntry>, FsError> {
+        with_easy_fs(|easy_fs| {
+            easy_fs.check_dir(self.inode_id)?;
+            easy_fs
+                .read_dir(self.inode_id)?
+                .iter()
+                .map(|entry| {
+                    let file_type = if easy_fs.read_inode(entry.get_inode_id())?.is_dir() {
+                        S_IFDIR
+                    } else {
+                        S_IFREG
+                    };
+                    Ok(DirEntry {
+                        name: entry.get_name().to_string(),
+                        ino: entry.get_inode_id() as u64,
+                        file_type,
+                    })
+                })
+                .collect()
+        })
+    }
+}
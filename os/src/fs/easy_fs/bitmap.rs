@@ -0,0 +1,60 @@
+use crate::fs::FsError;
+use crate::fs::easy_fs::block_cache::BlockCache;
+use crate::fs::easy_fs::layout::BITS_PER_BLOCK;
+
+/// A bitmap of `blocks` blocks from `start_block`, whose
+/// set bits mark the allocated inodes or data blocks.
+#[derive(Debug)]
+pub(super) struct Bitmap {
+    start_block: u32,
+    blocks: u32,
+    /// The number of bits in use, which may be less than
+    /// the bits of the blocks.
+    len: u32,
+}
+
+impl Bitmap {
+    pub(super) fn new(start_block: u32, blocks: u32, len: u32) -> Self {
+        Self {
+            start_block,
+            blocks,
+            len,
+        }
+    }
+
+    /// Allocates the lowest clear bit and returns it, or
+    /// [None] if all bits are set.
+    pub(super) fn alloc(&self, cache: &mut BlockCache) -> Result<Option<u32>, FsError> {
+        for i in 0..self.blocks {
+            let block = cache.get(self.start_block + i)?;
+            let Some(byte) = block.iter().position(|&byte| byte != u8::MAX) else {
+                continue;
+            };
+            let bit = block[byte].trailing_ones() as usize;
+            let result = i * BITS_PER_BLOCK as u32 + (byte * 8 + bit) as u32;
+            if result >= self.len {
+                return Ok(None);
+            }
+
+            cache.get_mut(self.start_block + i)?[byte] |= 1 << bit;
+            return Ok(Some(result));
+        }
+        Ok(None)
+    }
+
+    /// Clears the `bit`, which should be set.
+    pub(super) fn dealloc(&self, cache: &mut BlockCache, bit: u32) -> Result<(), FsError> {
+        let bit = bit as usize;
+        let block_id = self.start_block + (bit / BITS_PER_BLOCK) as u32;
+        let byte = (bit % BITS_PER_BLOCK) / 8;
+
+        let block = cache.get_mut(block_id)?;
+        assert!(
+            block[byte] & (1 << (bit % 8)) != 0,
+            "Bit {} is not allocated",
+            bit
+        );
+        block[byte] &= !(1 << (bit % 8));
+        Ok(())
+    }
+}
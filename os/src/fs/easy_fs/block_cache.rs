@@ -0,0 +1,117 @@
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+
+use crate::drivers::block::BlockDevice;
+use crate::fs::FsError;
+use crate::fs::easy_fs::layout::BLOCK_SIZE;
+
+/// The number of blocks kept in memory.
+const CACHE_CAPACITY: usize = 64;
+
+type Block = [u8; BLOCK_SIZE];
+
+struct CachedBlock {
+    block_id: u32,
+    data: Box<Block>,
+    is_dirty: bool,
+}
+
+/// The recently used blocks of a device, where the least
+/// recently used one is evicted first. A dirty block is
+/// written back when it is evicted or synced.
+pub(super) struct BlockCache {
+    device: Arc<dyn BlockDevice>,
+    /// The blocks from the least to the most recently used.
+    blocks: VecDeque<CachedBlock>,
+}
+
+impl BlockCache {
+    pub(super) fn new(device: Arc<dyn BlockDevice>) -> Self {
+        Self {
+            device,
+            blocks: VecDeque::new(),
+        }
+    }
+
+    pub(super) fn get_num_blocks(&self) -> usize {
+        self.device.get_num_blocks()
+    }
+
+    /// Returns the content of the block `block_id`.
+    pub(super) fn get(&mut self, block_id: u32) -> Result<&Block, FsError> {
+        let index = self.load(block_id, true)?;
+        Ok(&self.blocks[index].data)
+    }
+
+    /// Returns the content of the block `block_id` to be
+    /// modified, which marks the block dirty.
+    pub(super) fn get_mut(&mut self, block_id: u32) -> Result<&mut Block, FsError> {
+        let index = self.load(block_id, true)?;
+        let block = &mut self.blocks[index];
+        block.is_dirty = true;
+        Ok(&mut block.data)
+    }
+
+    /// Returns the content of the block `block_id` to be
+    /// overwritten, which is zeroed instead of being read
+    /// from the device.
+    pub(super) fn get_zeroed(&mut self, block_id: u32) -> Result<&mut Block, FsError> {
+        let index = self.load(block_id, false)?;
+        let block = &mut self.blocks[index];
+        block.data.fill(0);
+        block.is_dirty = true;
+        Ok(&mut block.data)
+    }
+
+    /// Writes all dirty blocks back to the device.
+    pub(super) fn sync(&mut self) -> Result<(), FsError> {
+        for block in self.blocks.iter_mut().filter(|block| block.is_dirty) {
+            self.device
+                .write_block(block.block_id as usize, &block.data[..])
+                .map_err(FsError::Io)?;
+            block.is_dirty = false;
+        }
+        Ok(())
+    }
+
+    /// Moves the block `block_id` to the most recently used
+    /// end, reading it from the device if it is not cached
+    /// and `should_read`. Returns its index.
+    fn load(&mut self, block_id: u32, should_read: bool) -> Result<usize, FsError> {
+        if let Some(index) = self.blocks.iter().position(|b| b.block_id == block_id) {
+            let block = self.blocks.remove(index).unwrap();
+            self.blocks.push_back(block);
+            return Ok(self.blocks.len() - 1);
+        }
+
+        if self.blocks.len() == CACHE_CAPACITY {
+            self.evict()?;
+        }
+        let mut data = Box::new([0; BLOCK_SIZE]);
+        if should_read {
+            self.device
+                .read_block(block_id as usize, &mut data[..])
+                .map_err(FsError::Io)?;
+        }
+        self.blocks.push_back(CachedBlock {
+            block_id,
+            data,
+            is_dirty: false,
+        });
+        Ok(self.blocks.len() - 1)
+    }
+
+    fn evict(&mut self) -> Result<(), FsError> {
+        let block = self.blocks.front().unwrap();
+        if block.is_dirty {
+            self.device
+                .write_block(block.block_id as usize, &block.data[..])
+                .map_err(FsError::Io)?;
+        }
+        self.blocks.pop_front();
+        Ok(())
+    }
+}
@@ -0,0 +1,278 @@
+extern crate alloc;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::fs::FsError;
+use crate::fs::easy_fs::EasyFs;
+use crate::fs::easy_fs::layout::{
+    BLOCK_SIZE, DIR_ENTRY_SIZE, DIRECT_COUNT, DISK_INODE_SIZE, DirEntry, DiskInode, INDIRECT_COUNT,
+    InodeType, MAX_DATA_BLOCKS, get_data_blocks, read_u32, write_u32,
+};
+
+impl EasyFs {
+    pub(super) fn read_inode(&mut self, inode_id: u32) -> Result<DiskInode, FsError> {
+        let (block_id, offset) = self.super_block.get_inode_pos(inode_id);
+        let block = self.cache.get(block_id)?;
+        Ok(DiskInode::decode(&block[offset..offset + DISK_INODE_SIZE]))
+    }
+
+    fn write_inode(&mut self, inode_id: u32, inode: &DiskInode) -> Result<(), FsError> {
+        let (block_id, offset) = self.super_block.get_inode_pos(inode_id);
+        let block = self.cache.get_mut(block_id)?;
+        inode.encode(&mut block[offset..offset + DISK_INODE_SIZE]);
+        Ok(())
+    }
+
+    /// Reads the data of the inode `inode_id` from `offset`
+    /// into the `buf`, and returns the number of bytes read,
+    /// which is zero at the end of the data.
+    pub(super) fn read_at(
+        &mut self,
+        inode_id: u32,
+        offset: usize,
+        buf: &mut [u8],
+    ) -> Result<usize, FsError> {
+        let inode = self.read_inode(inode_id)?;
+        let end = (offset + buf.len()).min(inode.size as usize);
+        if offset >= end {
+            return Ok(0);
+        }
+
+        let mut pos = offset;
+        while pos < end {
+            let block_id = self.get_data_block_id(&inode, pos / BLOCK_SIZE)?;
+            let start = pos % BLOCK_SIZE;
+            let len = (BLOCK_SIZE - start).min(end - pos);
+            let block = self.cache.get(block_id)?;
+            buf[pos - offset..pos - offset + len].copy_from_slice(&block[start..start + len]);
+            pos += len;
+        }
+        Ok(end - offset)
+    }
+
+    /// Writes the `buf` to the data of the inode `inode_id`
+    /// at `offset`, growing the data if needed. Returns the
+    /// number of bytes written.
+    pub(super) fn write_at(
+        &mut self,
+        inode_id: u32,
+        offset: usize,
+        buf: &[u8],
+    ) -> Result<usize, FsError> {
+        let mut inode = self.read_inode(inode_id)?;
+        let end = offset + buf.len();
+        if end > inode.size as usize {
+            // The inode keeps the blocks allocated before a
+            // failure.
+            let result = self.grow(&mut inode, end);
+            self.write_inode(inode_id, &inode)?;
+            result?;
+        }
+
+        let mut pos = offset;
+        while pos < end {
+            let block_id = self.get_data_block_id(&inode, pos / BLOCK_SIZE)?;
+            let start = pos % BLOCK_SIZE;
+            let len = (BLOCK_SIZE - start).min(end - pos);
+            let block = if len == BLOCK_SIZE {
+                self.cache.get_zeroed(block_id)?
+            } else {
+                self.cache.get_mut(block_id)?
+            };
+            block[start..start + len].copy_from_slice(&buf[pos - offset..pos - offset + len]);
+            pos += len;
+        }
+        Ok(buf.len())
+    }
+
+    /// Frees all data blocks of the inode `inode_id`, and
+    /// sets its size to zero.
+    pub(super) fn truncate(&mut self, inode_id: u32) -> Result<(), FsError> {
+        let inode = self.read_inode(inode_id)?;
+        for index in 0..get_data_blocks(inode.size) {
+            let block_id = self.get_data_block_id(&inode, index)?;
+            self.dealloc_data_block(block_id)?;
+        }
+
+        if inode.doubly_indirect != 0 {
+            for index in 0..INDIRECT_COUNT {
+                let block_id = self.read_index(inode.doubly_indirect, index)?;
+                if block_id != 0 {
+                    self.dealloc_data_block(block_id)?;
+                }
+            }
+            self.dealloc_data_block(inode.doubly_indirect)?;
+        }
+        if inode.indirect != 0 {
+            self.dealloc_data_block(inode.indirect)?;
+        }
+
+        self.write_inode(inode_id, &DiskInode::new(inode.inode_type))
+    }
+
+    /// Returns the entries of the directory `dir_id`.
+    pub(super) fn read_dir(&mut self, dir_id: u32) -> Result<Vec<DirEntry>, FsError> {
+        let size = self.read_inode(dir_id)?.size as usize;
+        let mut bytes = vec![0; size];
+        self.read_at(dir_id, 0, &mut bytes)?;
+        Ok(bytes
+            .chunks_exact(DIR_ENTRY_SIZE)
+            .map(DirEntry::decode)
+            .collect())
+    }
+
+    /// Returns the inode ID of the entry `name` of the
+    /// directory `dir_id`, or [None] if there is none.
+    pub(super) fn find(&mut self, dir_id: u32, name: &str) -> Result<Option<u32>, FsError> {
+        Ok(self
+            .read_dir(dir_id)?
+            .iter()
+            .find(|entry| entry.get_name() == name)
+            .map(DirEntry::get_inode_id))
+    }
+
+    /// Creates an empty inode of the `inode_type` as the
+    /// entry `name` of the directory `dir_id`, and returns
+    /// the ID of the inode.
+    pub(super) fn create(
+        &mut self,
+        dir_id: u32,
+        name: &str,
+        inode_type: InodeType,
+    ) -> Result<u32, FsError> {
+        DirEntry::new(name, 0).ok_or(FsError::InvalidName)?;
+        if self.find(dir_id, name)?.is_some() {
+            return Err(FsError::AlreadyExists);
+        }
+        let inode_id = self
+            .inode_bitmap
+            .alloc(&mut self.cache)?
+            .ok_or(FsError::NoSpace)?;
+        self.write_inode(inode_id, &DiskInode::new(inode_type))?;
+
+        let mut bytes = [0; DIR_ENTRY_SIZE];
+        DirEntry::new(name, inode_id).unwrap().encode(&mut bytes);
+        let dir_size = self.read_inode(dir_id)?.size as usize;
+        self.write_at(dir_id, dir_size, &bytes)?;
+        Ok(inode_id)
+    }
+
+    /// Removes the entry `name` of the directory `dir_id`
+    /// and frees its inode, which should not be a non-empty
+    /// directory.
+    pub(super) fn unlink(&mut self, dir_id: u32, name: &str) -> Result<(), FsError> {
+        let mut entries = self.read_dir(dir_id)?;
+        let pos = entries
+            .iter()
+            .position(|entry| entry.get_name() == name)
+            .ok_or(FsError::NotFound)?;
+        let inode_id = entries[pos].get_inode_id();
+        let inode = self.read_inode(inode_id)?;
+        if inode.is_dir() && inode.size != 0 {
+            return Err(FsError::NotEmpty);
+        }
+        self.truncate(inode_id)?;
+        self.inode_bitmap.dealloc(&mut self.cache, inode_id)?;
+
+        // The last entry takes the place of the removed one.
+        entries.swap_remove(pos);
+        let mut bytes = vec![0; entries.len() * DIR_ENTRY_SIZE];
+        for (entry, chunk) in entries.iter().zip(bytes.chunks_exact_mut(DIR_ENTRY_SIZE)) {
+            entry.encode(chunk);
+        }
+        self.truncate(dir_id)?;
+        self.write_at(dir_id, 0, &bytes)?;
+        Ok(())
+    }
+
+    /// Grows the data of the `inode` to `new_size` bytes with
+    /// zeroed blocks.
+    fn grow(&mut self, inode: &mut DiskInode, new_size: usize) -> Result<(), FsError> {
+        if new_size > MAX_DATA_BLOCKS * BLOCK_SIZE {
+            return Err(FsError::FileTooLarge(new_size));
+        }
+
+        let first_new = get_data_blocks(inode.size);
+        for index in first_new..get_data_blocks(new_size as u32) {
+            let block_id = self.alloc_data_block()?;
+            self.set_data_block_id(inode, index, block_id)?;
+            inode.size = ((index + 1) * BLOCK_SIZE).min(new_size) as u32;
+        }
+        inode.size = new_size as u32;
+        Ok(())
+    }
+
+    /// Returns the block ID of the data block `index` of the
+    /// `inode`.
+    fn get_data_block_id(&mut self, inode: &DiskInode, index: usize) -> Result<u32, FsError> {
+        if index < DIRECT_COUNT {
+            return Ok(inode.direct[index]);
+        }
+        let index = index - DIRECT_COUNT;
+        if index < INDIRECT_COUNT {
+            return self.read_index(inode.indirect, index);
+        }
+        let index = index - INDIRECT_COUNT;
+        let indirect = self.read_index(inode.doubly_indirect, index / INDIRECT_COUNT)?;
+        self.read_index(indirect, index % INDIRECT_COUNT)
+    }
+
+    /// Sets the data block `index` of the `inode` to the
+    /// `block_id`, allocating the index blocks on the way.
+    fn set_data_block_id(
+        &mut self,
+        inode: &mut DiskInode,
+        index: usize,
+        block_id: u32,
+    ) -> Result<(), FsError> {
+        if index < DIRECT_COUNT {
+            inode.direct[index] = block_id;
+            return Ok(());
+        }
+        let index = index - DIRECT_COUNT;
+        if index < INDIRECT_COUNT {
+            if inode.indirect == 0 {
+                inode.indirect = self.alloc_data_block()?;
+            }
+            return self.write_index(inode.indirect, index, block_id);
+        }
+
+        let index = index - INDIRECT_COUNT;
+        if inode.doubly_indirect == 0 {
+            inode.doubly_indirect = self.alloc_data_block()?;
+        }
+        let mut indirect = self.read_index(inode.doubly_indirect, index / INDIRECT_COUNT)?;
+        if indirect == 0 {
+            indirect = self.alloc_data_block()?;
+            self.write_index(inode.doubly_indirect, index / INDIRECT_COUNT, indirect)?;
+        }
+        self.write_index(indirect, index % INDIRECT_COUNT, block_id)
+    }
+
+    /// Returns the entry `index` of the index block.
+    fn read_index(&mut self, block_id: u32, index: usize) -> Result<u32, FsError> {
+        Ok(read_u32(self.cache.get(block_id)?, index * 4))
+    }
+
+    fn write_index(&mut self, block_id: u32, index: usize, value: u32) -> Result<(), FsError> {
+        write_u32(self.cache.get_mut(block_id)?, index * 4, value);
+        Ok(())
+    }
+
+    /// Allocates a zeroed data block and returns its ID.
+    fn alloc_data_block(&mut self) -> Result<u32, FsError> {
+        let bit = self
+            .data_bitmap
+            .alloc(&mut self.cache)?
+            .ok_or(FsError::NoSpace)?;
+        let block_id = self.super_block.get_data_area_start() + bit;
+        self.cache.get_zeroed(block_id)?;
+        Ok(block_id)
+    }
+
+    fn dealloc_data_block(&mut self, block_id: u32) -> Result<(), FsError> {
+        let bit = block_id - self.super_block.get_data_area_start();
+        self.data_bitmap.dealloc(&mut self.cache, bit)
+    }
+}
@@ -0,0 +1,254 @@
+// The on-disk layout of easy-fs, which the host tool mkfs
+// also includes, so it depends only on `core`.
+//
+// The blocks are laid out as follows, where the sizes of
+// the areas are kept in the super block, and the numbers
+// are stored in little endian.
+//
+// | super block | inode bitmap | inodes | data bitmap | data |
+//
+// The items only the kernel or only mkfs uses allow dead
+// code.
+
+/// The size of a block in bytes.
+pub(super) const BLOCK_SIZE: usize = 512;
+pub(super) const MAGIC: u32 = 0x3b80_0001;
+
+/// The inode of the root directory.
+pub(super) const ROOT_INODE_ID: u32 = 0;
+
+/// The number of data blocks an inode refers to directly.
+pub(super) const DIRECT_COUNT: usize = 28;
+/// The number of block IDs in an index block.
+pub(super) const INDIRECT_COUNT: usize = BLOCK_SIZE / 4;
+/// The largest number of data blocks of a file, which are
+/// referred to directly, through the indirect block or
+/// through the doubly indirect block.
+pub(super) const MAX_DATA_BLOCKS: usize =
+    DIRECT_COUNT + INDIRECT_COUNT + INDIRECT_COUNT * INDIRECT_COUNT;
+
+pub(super) const DISK_INODE_SIZE: usize = 128;
+pub(super) const INODES_PER_BLOCK: usize = BLOCK_SIZE / DISK_INODE_SIZE;
+pub(super) const BITS_PER_BLOCK: usize = BLOCK_SIZE * 8;
+
+/// The longest file name in bytes, which leaves room for
+/// the trailing NUL.
+pub(super) const MAX_NAME_LEN: usize = 27;
+pub(super) const DIR_ENTRY_SIZE: usize = 32;
+
+/// The first block, which describes the sizes of the areas.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct SuperBlock {
+    pub(super) total_blocks: u32,
+    pub(super) inode_bitmap_blocks: u32,
+    pub(super) inode_area_blocks: u32,
+    pub(super) data_bitmap_blocks: u32,
+    pub(super) data_area_blocks: u32,
+}
+
+impl SuperBlock {
+    /// Returns the layout of `total_blocks` blocks with an
+    /// inode bitmap of `inode_bitmap_blocks` blocks, where
+    /// the data area takes the rest.
+    #[allow(dead_code)]
+    pub(super) fn new(total_blocks: u32, inode_bitmap_blocks: u32) -> Self {
+        let inodes = inode_bitmap_blocks * BITS_PER_BLOCK as u32;
+        let inode_area_blocks = inodes.div_ceil(INODES_PER_BLOCK as u32);
+        let rest_blocks = total_blocks - 1 - inode_bitmap_blocks - inode_area_blocks;
+        // Each bit of the data bitmap takes one data block.
+        let data_bitmap_blocks = rest_blocks.div_ceil(BITS_PER_BLOCK as u32 + 1);
+        Self {
+            total_blocks,
+            inode_bitmap_blocks,
+            inode_area_blocks,
+            data_bitmap_blocks,
+            data_area_blocks: rest_blocks - data_bitmap_blocks,
+        }
+    }
+
+    /// Returns the [SuperBlock] in the `block`, or [None] if
+    /// the block has no valid magic.
+    #[allow(dead_code)]
+    pub(super) fn decode(block: &[u8]) -> Option<Self> {
+        if read_u32(block, 0) != MAGIC {
+            return None;
+        }
+        Some(Self {
+            total_blocks: read_u32(block, 4),
+            inode_bitmap_blocks: read_u32(block, 8),
+            inode_area_blocks: read_u32(block, 12),
+            data_bitmap_blocks: read_u32(block, 16),
+            data_area_blocks: read_u32(block, 20),
+        })
+    }
+
+    #[allow(dead_code)]
+    pub(super) fn encode(&self, block: &mut [u8]) {
+        write_u32(block, 0, MAGIC);
+        write_u32(block, 4, self.total_blocks);
+        write_u32(block, 8, self.inode_bitmap_blocks);
+        write_u32(block, 12, self.inode_area_blocks);
+        write_u32(block, 16, self.data_bitmap_blocks);
+        write_u32(block, 20, self.data_area_blocks);
+    }
+
+    pub(super) fn get_inode_bitmap_start(&self) -> u32 {
+        1
+    }
+
+    pub(super) fn get_inode_area_start(&self) -> u32 {
+        self.get_inode_bitmap_start() + self.inode_bitmap_blocks
+    }
+
+    pub(super) fn get_data_bitmap_start(&self) -> u32 {
+        self.get_inode_area_start() + self.inode_area_blocks
+    }
+
+    pub(super) fn get_data_area_start(&self) -> u32 {
+        self.get_data_bitmap_start() + self.data_bitmap_blocks
+    }
+
+    /// Returns the (block ID, offset in the block) of the
+    /// inode `inode_id`.
+    pub(super) fn get_inode_pos(&self, inode_id: u32) -> (u32, usize) {
+        let block_id = self.get_inode_area_start() + inode_id / INODES_PER_BLOCK as u32;
+        let offset = (inode_id as usize % INODES_PER_BLOCK) * DISK_INODE_SIZE;
+        (block_id, offset)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum InodeType {
+    File = 0,
+    Directory = 1,
+}
+
+/// An inode, whose data blocks are referred to by their
+/// block IDs, with zero for none.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct DiskInode {
+    /// The size of the data in bytes.
+    pub(super) size: u32,
+    pub(super) direct: [u32; DIRECT_COUNT],
+    /// The block of the IDs of the next [INDIRECT_COUNT]
+    /// data blocks.
+    pub(super) indirect: u32,
+    /// The block of the IDs of the index blocks of the rest
+    /// data blocks.
+    pub(super) doubly_indirect: u32,
+    pub(super) inode_type: InodeType,
+}
+
+impl DiskInode {
+    pub(super) fn new(inode_type: InodeType) -> Self {
+        Self {
+            size: 0,
+            direct: [0; DIRECT_COUNT],
+            indirect: 0,
+            doubly_indirect: 0,
+            inode_type,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub(super) fn decode(bytes: &[u8]) -> Self {
+        let offset = 4 + 4 * DIRECT_COUNT;
+        Self {
+            size: read_u32(bytes, 0),
+            direct: core::array::from_fn(|i| read_u32(bytes, 4 + 4 * i)),
+            indirect: read_u32(bytes, offset),
+            doubly_indirect: read_u32(bytes, offset + 4),
+            inode_type: match read_u32(bytes, offset + 8) {
+                1 => InodeType::Directory,
+                _ => InodeType::File,
+            },
+        }
+    }
+
+    pub(super) fn encode(&self, bytes: &mut [u8]) {
+        let offset = 4 + 4 * DIRECT_COUNT;
+        write_u32(bytes, 0, self.size);
+        for (i, &block_id) in self.direct.iter().enumerate() {
+            write_u32(bytes, 4 + 4 * i, block_id);
+        }
+        write_u32(bytes, offset, self.indirect);
+        write_u32(bytes, offset + 4, self.doubly_indirect);
+        write_u32(bytes, offset + 8, self.inode_type as u32);
+    }
+
+    #[allow(dead_code)]
+    pub(super) fn is_dir(&self) -> bool {
+        self.inode_type == InodeType::Directory
+    }
+}
+
+/// Returns the number of data blocks of `size` bytes.
+#[allow(dead_code)]
+pub(super) fn get_data_blocks(size: u32) -> usize {
+    (size as usize).div_ceil(BLOCK_SIZE)
+}
+
+/// An entry of a directory, which names an inode.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct DirEntry {
+    /// The name padded with NULs.
+    name: [u8; MAX_NAME_LEN + 1],
+    inode_id: u32,
+}
+
+impl DirEntry {
+    /// Returns the entry of the `name`, or [None] if it is
+    /// empty, too long or contains a NUL.
+    pub(super) fn new(name: &str, inode_id: u32) -> Option<Self> {
+        if name.is_empty() || name.len() > MAX_NAME_LEN || name.contains('\0') {
+            return None;
+        }
+        let mut bytes = [0; MAX_NAME_LEN + 1];
+        bytes[..name.len()].copy_from_slice(name.as_bytes());
+        Some(Self {
+            name: bytes,
+            inode_id,
+        })
+    }
+
+    #[allow(dead_code)]
+    pub(super) fn decode(bytes: &[u8]) -> Self {
+        let mut name = [0; MAX_NAME_LEN + 1];
+        name.copy_from_slice(&bytes[..MAX_NAME_LEN + 1]);
+        Self {
+            name,
+            inode_id: read_u32(bytes, MAX_NAME_LEN + 1),
+        }
+    }
+
+    pub(super) fn encode(&self, bytes: &mut [u8]) {
+        bytes[..MAX_NAME_LEN + 1].copy_from_slice(&self.name);
+        write_u32(bytes, MAX_NAME_LEN + 1, self.inode_id);
+    }
+
+    /// Returns the name, or an empty string if it is not
+    /// valid UTF-8.
+    #[allow(dead_code)]
+    pub(super) fn get_name(&self) -> &str {
+        let len = self
+            .name
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(self.name.len());
+        core::str::from_utf8(&self.name[..len]).unwrap_or("")
+    }
+
+    #[allow(dead_code)]
+    pub(super) fn get_inode_id(&self) -> u32 {
+        self.inode_id
+    }
+}
+
+#[allow(dead_code)]
+pub(super) fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+pub(super) fn write_u32(bytes: &mut [u8], offset: usize, value: u32) {
+    bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
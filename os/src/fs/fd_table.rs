@@ -0,0 +1,90 @@
+extern crate alloc;
+
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::fs::File;
+use crate::fs::stdio::{Stdin, Stdout};
+
+/// The maximum number of file descriptors a task can have.
+const MAX_FDS: usize = 64;
+
+/// The open files of a task indexed by file descriptor.
+/// A forked task gets a copy sharing the same [File]s.
+#[derive(Clone)]
+pub(crate) struct FdTable {
+    files: Vec<Option<Arc<dyn File>>>,
+}
+
+impl FdTable {
+    /// Returns a table with stdin, stdout and stderr opened
+    /// as file descriptors 0, 1 and 2.
+    pub(crate) fn new_stdio() -> Self {
+        let stdout: Arc<dyn File> = Arc::new(Stdout);
+        Self {
+            files: vec![Some(Arc::new(Stdin)), Some(stdout.clone()), Some(stdout)],
+        }
+    }
+
+    /// Returns the [File] of the `fd`, or [None] if it is
+    /// not open.
+    pub(crate) fn get(&self, fd: usize) -> Option<Arc<dyn File>> {
+        self.files.get(fd).cloned().flatten()
+    }
+
+    /// Opens the `file` as the lowest free file descriptor
+    /// and returns it, or returns [None] if all are in use.
+    pub(crate) fn alloc(&mut self, file: Arc<dyn File>) -> Option<usize> {
+        if let Some(fd) = self.files.iter().position(Option::is_none) {
+            self.files[fd] = Some(file);
+            return Some(fd);
+        }
+        if self.files.len() == MAX_FDS {
+            return None;
+        }
+        self.files.push(Some(file));
+        Some(self.files.len() - 1)
+    }
+
+    /// Opens the `file` as the `fd`, closing the one it
+    /// replaces. Returns whether the `fd` is valid, i.e.,
+    /// less than [MAX_FDS].
+    pub(crate) fn insert(&mut self, fd: usize, file: Arc<dyn File>) -> bool {
+        if fd >= MAX_FDS {
+            return false;
+        }
+        if fd >= self.files.len() {
+            self.files.resize(fd + 1, None);
+        }
+        self.files[fd] = Some(file);
+        true
+    }
+
+    /// Closes the `fd` and returns its [File], or returns
+    /// [None] if it is not open.
+    pub(crate) fn remove(&mut self, fd: usize) -> Option<Arc<dyn File>> {
+        self.files.get_mut(fd)?.take()
+    }
+
+    /// Closes all the file descriptors and returns their
+    /// [File]s.
+    pub(crate) fn close_all(&mut self) -> Vec<Arc<dyn File>> {
+        core::mem::take(&mut self.files)
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+}
+
+impl fmt::Debug for FdTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let open_fds = self
+            .files
+            .iter()
+            .enumerate()
+            .filter(|(_, file)| file.is_some());
+        f.debug_list().entries(open_fds.map(|(fd, _)| fd)).finish()
+    }
+}
@@ -0,0 +1,207 @@
+extern crate alloc;
+
+mod cpio;
+
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::fs::initramfs::cpio::CpioError;
+use crate::fs::vfs::{self, DirEntry, Inode, InodeType};
+use crate::fs::{FsError, S_IFDIR, S_IFMT, Stat};
+use crate::sync::spin::SpinLock;
+use crate::{debug, log};
+
+/// The cpio archive generated by the build.rs, which holds
+/// the apps in /bin and the files of user/initramfs.
+static ARCHIVE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/initramfs.cpio"));
+
+/// The device number of the initramfs.
+const INITRAMFS_DEV: u64 = 3;
+
+/// The index of the root directory among the nodes.
+const ROOT_INDEX: usize = 0;
+
+/// The file system unpacked from the [ARCHIVE] at boot.
+static INITRAMFS: SpinLock<Initramfs> = SpinLock::new(Initramfs { nodes: Vec::new() });
+
+/// A read-only file system in memory, whose files refer to
+/// their content in the archive.
+struct Initramfs {
+    /// The files and directories, where the root directory
+    /// comes first.
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug)]
+struct Node {
+    name: &'static str,
+    /// The file type and permission bits.
+    mode: u32,
+    data: &'static [u8],
+    /// The indices of the children of a directory.
+    children: Vec<usize>,
+}
+
+impl Node {
+    fn new_dir(name: &'static str) -> Self {
+        Self {
+            name,
+            mode: S_IFDIR | 0o755,
+            data: &[],
+            children: Vec::new(),
+        }
+    }
+
+    fn is_dir(&self) -> bool {
+        self.mode & S_IFMT == S_IFDIR
+    }
+}
+
+impl Initramfs {
+    fn unpack(archive: &'static [u8]) -> Result<Self, CpioError> {
+        let mut initramfs = Self {
+            nodes: vec![Node::new_dir("")],
+        };
+
+        for entry in cpio::parse(archive)? {
+            let mut components = entry
+                .name
+                .split('/')
+                .filter(|&name| !name.is_empty() && name != ".")
+                .peekable();
+            let mut parent = ROOT_INDEX;
+            while let Some(name) = components.next() {
+                let index = initramfs.get_or_add_child(parent, name);
+                // The directories on the way may not have their
+                // own entries.
+                if components.peek().is_none() {
+                    let node = &mut initramfs.nodes[index];
+                    node.mode = entry.mode;
+                    node.data = entry.data;
+                }
+                parent = index;
+            }
+        }
+        Ok(initramfs)
+    }
+
+    fn get_or_add_child(&mut self, parent: usize, name: &'static str) -> usize {
+        if let Some(index) = self.find_child(parent, name) {
+            return index;
+        }
+        self.nodes.push(Node::new_dir(name));
+        let index = self.nodes.len() - 1;
+        self.nodes[parent].children.push(index);
+        index
+    }
+
+    fn find_child(&self, parent: usize, name: &str) -> Option<usize> {
+        self.nodes[parent]
+            .children
+            .iter()
+            .copied()
+            .find(|&child| self.nodes[child].name == name)
+    }
+
+    fn log_nodes(&self, index: usize, path: &str) {
+        for &child in self.nodes[index].children.iter() {
+            let node = &self.nodes[child];
+            let child_path = path.to_string() + "/" + node.name;
+            debug!(
+                "initramfs: mode={:o} size={}, path={}",
+                node.mode,
+                node.data.len(),
+                child_path
+            );
+            self.log_nodes(child, &child_path);
+        }
+    }
+}
+
+/// Unpacks the archive linked into the kernel, and sets
+/// it up as the root of the tree.
+pub(super) fn init() {
+    let initramfs = Initramfs::unpack(ARCHIVE)
+        .unwrap_or_else(|err| panic!("Failed to unpack the initramfs, err={:?}", err));
+    initramfs.log_nodes(ROOT_INDEX, "");
+    *INITRAMFS.lock() = initramfs;
+    vfs::mount_root(Arc::new(InitramfsInode { index: ROOT_INDEX }));
+}
+
+/// A file or a directory of the initramfs, which cannot be
+/// modified.
+struct InitramfsInode {
+    index: usize,
+}
+
+impl Inode for InitramfsInode {
+    fn stat(&self) -> Result<Stat, FsError> {
+        let initramfs = INITRAMFS.lock();
+        let node = &initramfs.nodes[self.index];
+        Ok(Stat {
+            dev: INITRAMFS_DEV,
+            ino: self.index as u64,
+            mode: node.mode,
+            nlink: 1,
+            size: node.data.len() as u64,
+        })
+    }
+
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize, FsError> {
+        let initramfs = INITRAMFS.lock();
+        let node = &initramfs.nodes[self.index];
+        if node.is_dir() {
+            return Err(FsError::IsDirectory);
+        }
+        let src = &node.data[offset.min(node.data.len())..];
+        let len = buf.len().min(src.len());
+        buf[..len].copy_from_slice(&src[..len]);
+        Ok(len)
+    }
+
+    fn write_at(&self, _offset: usize, _buf: &[u8]) -> Result<usize, FsError> {
+        Err(FsError::ReadOnly)
+    }
+
+    fn truncate(&self) -> Result<(), FsError> {
+        Err(FsError::ReadOnly)
+    }
+
+    fn lookup(&self, name: &str) -> Result<Arc<dyn Inode>, FsError> {
+        let initramfs = INITRAMFS.lock();
+        if !initramfs.nodes[self.index].is_dir() {
+            return Err(FsError::NotDirectory);
+        }
+        let index = initramfs
+            .find_child(self.index, name)
+            .ok_or(FsError::NotFound)?;
+        Ok(Arc::new(InitramfsInode { index }))
+    }
+
+    fn create(&self, _name: &str, _inode_type: InodeType) -> Result<Arc<dyn Inode>, FsError> {
+        Err(FsError::ReadOnly)
+    }
+
+    fn unlink(&self, _name: &str) -> Result<(), FsError> {
+        Err(FsError::ReadOnly)
+    }
+
+    fn read_dir(&self) -> Result<Vec<DirEntry>, FsError> {
+        let initramfs = INITRAMFS.lock();
+        let node = &initramfs.nodes[self.index];
+        if !node.is_dir() {
+            return Err(FsError::NotDirectory);
+        }
+        Ok(node
+            .children
+            .iter()
+            .map(|&child| DirEntry {
+                name: initramfs.nodes[child].name.to_string(),
+                ino: child as u64,
+                file_type: initramfs.nodes[child].mode & S_IFMT,
+            })
+            .collect())
+    }
+}
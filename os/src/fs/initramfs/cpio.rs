@@ -0,0 +1,91 @@
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::str;
+
+// The layout of a cpio archive in the "new ASCII" format,
+// see [here].
+//
+// [here]: https://man7.org/linux/man-pages/man5/cpio.5.html
+const MAGIC: &[u8] = b"070701";
+/// The magic followed by 13 fields of 8 hex digits.
+const HEADER_SIZE: usize = 110;
+const FIELD_SIZE: usize = 8;
+
+// Indices of the header fields used here.
+const FIELD_MODE: usize = 1;
+const FIELD_FILE_SIZE: usize = 6;
+const FIELD_NAME_SIZE: usize = 11;
+
+/// The name of the entry that ends the archive.
+const TRAILER_NAME: &str = "TRAILER!!!";
+
+#[allow(dead_code)]
+#[derive(Debug)]
+pub(crate) enum CpioError {
+    /// (offset of the header).
+    BadMagic(usize),
+    /// (offset).
+    Truncated(usize),
+    /// (offset of the field).
+    InvalidNumber(usize),
+    /// (offset of the name).
+    InvalidName(usize),
+}
+
+/// An entry of the archive, i.e., a file or a directory.
+#[derive(Debug)]
+pub(super) struct Entry<'a> {
+    /// The path relative to the root of the archive.
+    pub(super) name: &'a str,
+    /// The file type and permission bits.
+    pub(super) mode: u32,
+    pub(super) data: &'a [u8],
+}
+
+/// Returns the entries of the `archive` in the order they
+/// appear, without the trailer.
+pub(super) fn parse(archive: &[u8]) -> Result<Vec<Entry<'_>>, CpioError> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+
+    loop {
+        let header = archive
+            .get(offset..offset + HEADER_SIZE)
+            .ok_or(CpioError::Truncated(offset + HEADER_SIZE))?;
+        if !header.starts_with(MAGIC) {
+            return Err(CpioError::BadMagic(offset));
+        }
+        let read_field = |index: usize| {
+            let start = MAGIC.len() + index * FIELD_SIZE;
+            str::from_utf8(&header[start..start + FIELD_SIZE])
+                .ok()
+                .and_then(|field| usize::from_str_radix(field, 16).ok())
+                .ok_or(CpioError::InvalidNumber(offset + start))
+        };
+        let mode = read_field(FIELD_MODE)? as u32;
+        let file_size = read_field(FIELD_FILE_SIZE)?;
+        let name_size = read_field(FIELD_NAME_SIZE)?;
+
+        // The name ends with a NUL, and both the name and
+        // the data are padded to 4 bytes.
+        let name_offset = offset + HEADER_SIZE;
+        let name = archive
+            .get(name_offset..name_offset + name_size)
+            .ok_or(CpioError::Truncated(name_offset + name_size))?;
+        let name = name
+            .strip_suffix(b"\0")
+            .and_then(|name| str::from_utf8(name).ok())
+            .ok_or(CpioError::InvalidName(name_offset))?;
+        if name == TRAILER_NAME {
+            return Ok(entries);
+        }
+
+        let data_offset = (name_offset + name_size).next_multiple_of(4);
+        let data = archive
+            .get(data_offset..data_offset + file_size)
+            .ok_or(CpioError::Truncated(data_offset + file_size))?;
+        entries.push(Entry { name, mode, data });
+        offset = (data_offset + file_size).next_multiple_of(4);
+    }
+}
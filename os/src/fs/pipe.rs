@@ -0,0 +1,254 @@
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec;
+
+use crate::fs::{File, FsError, S_IFIFO, Stat};
+use crate::sync::spin::SpinLock;
+use crate::sync::wait_queue::WaitQueue;
+use crate::task::prelude::has_current_pending_signal;
+
+/// The number of bytes a pipe holds before its writers
+/// block.
+const PIPE_CAPACITY: usize = 4096;
+/// The device number of the pipes.
+const PIPE_DEV: u64 = 6;
+
+/// Returns the read end and the write end of a new pipe.
+pub(crate) fn pipe() -> (Arc<dyn File>, Arc<dyn File>) {
+    let pipe = Arc::new(Pipe {
+        state: SpinLock::new(PipeState {
+            buf: RingBuffer::new(PIPE_CAPACITY),
+            readers: 1,
+            writers: 1,
+        }),
+        read_waiters: WaitQueue::new(),
+        write_waiters: WaitQueue::new(),
+    });
+    (
+        Arc::new(PipeReadEnd { pipe: pipe.clone() }),
+        Arc::new(PipeWriteEnd { pipe }),
+    )
+}
+
+/// The buffer shared by the two ends of a pipe.
+struct Pipe {
+    state: SpinLock<PipeState>,
+    /// The readers waiting for data or the last writer to
+    /// close.
+    read_waiters: WaitQueue,
+    /// The writers waiting for space or the last reader to
+    /// close.
+    write_waiters: WaitQueue,
+}
+
+struct PipeState {
+    buf: RingBuffer,
+    /// The number of open read ends.
+    readers: usize,
+    /// The number of open write ends.
+    writers: usize,
+}
+
+impl Pipe {
+    fn stat(&self) -> Stat {
+        Stat {
+            dev: PIPE_DEV,
+            ino: self as *const Self as u64,
+            mode: S_IFIFO | 0o600,
+            nlink: 1,
+            size: self.state.lock().buf.len() as u64,
+        }
+    }
+}
+
+/// Reads from a pipe. A read blocks the task until at least
+/// one byte is in the pipe, and then returns the bytes
+/// available without waiting for more. It returns zero once
+/// the pipe is empty and all the write ends are closed, or
+/// fails with [FsError::Interrupted] on a signal.
+struct PipeReadEnd {
+    pipe: Arc<Pipe>,
+}
+
+impl File for PipeReadEnd {
+    fn readable(&self) -> bool {
+        true
+    }
+
+    fn writable(&self) -> bool {
+        false
+    }
+
+    fn read(&self, buf: &mut [u8]) -> Result<usize, FsError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let mut state = self.pipe.state.lock();
+            let count = state.buf.pop(buf);
+            let has_writers = state.writers != 0;
+            drop(state);
+
+            if count != 0 {
+                self.pipe.write_waiters.wake_all();
+                return Ok(count);
+            }
+            if !has_writers {
+                return Ok(0);
+            }
+            if has_current_pending_signal() {
+                return Err(FsError::Interrupted);
+            }
+            self.pipe.read_waiters.wait_if(|| {
+                let state = self.pipe.state.lock();
+                state.buf.is_empty() && state.writers != 0
+            });
+        }
+    }
+
+    fn write(&self, _buf: &[u8]) -> Result<usize, FsError> {
+        Err(FsError::BadAccess)
+    }
+
+    fn stat(&self) -> Stat {
+        self.pipe.stat()
+    }
+}
+
+impl Drop for PipeReadEnd {
+    fn drop(&mut self) {
+        self.pipe.state.lock().readers -= 1;
+        self.pipe.write_waiters.wake_all();
+    }
+}
+
+/// Writes to a pipe. A write blocks the task until all the
+/// bytes are in the pipe, unless all the read ends are
+/// closed, in which case it returns the bytes written so
+/// far, or fails with [FsError::BrokenPipe] if there are
+/// none. A signal stops it the same way, but fails with
+/// [FsError::Interrupted].
+struct PipeWriteEnd {
+    pipe: Arc<Pipe>,
+}
+
+impl File for PipeWriteEnd {
+    fn readable(&self) -> bool {
+        false
+    }
+
+    fn writable(&self) -> bool {
+        true
+    }
+
+    fn read(&self, _buf: &mut [u8]) -> Result<usize, FsError> {
+        Err(FsError::BadAccess)
+    }
+
+    fn write(&self, buf: &[u8]) -> Result<usize, FsError> {
+        let mut written_len = 0;
+        while written_len < buf.len() {
+            let mut state = self.pipe.state.lock();
+            if state.readers == 0 {
+                return stop_write(written_len, FsError::BrokenPipe);
+            }
+            let count = state.buf.push(&buf[written_len..]);
+            drop(state);
+
+            if count != 0 {
+                written_len += count;
+                self.pipe.read_waiters.wake_all();
+                continue;
+            }
+            if has_current_pending_signal() {
+                return stop_write(written_len, FsError::Interrupted);
+            }
+            self.pipe.write_waiters.wait_if(|| {
+                let state = self.pipe.state.lock();
+                state.buf.is_full() && state.readers != 0
+            });
+        }
+        Ok(written_len)
+    }
+
+    fn stat(&self) -> Stat {
+        self.pipe.stat()
+    }
+}
+
+impl Drop for PipeWriteEnd {
+    fn drop(&mut self) {
+        self.pipe.state.lock().writers -= 1;
+        self.pipe.read_waiters.wake_all();
+    }
+}
+
+/// Returns the `written_len` of a write stopped early, or
+/// the `err` if nothing has been written.
+fn stop_write(written_len: usize, err: FsError) -> Result<usize, FsError> {
+    if written_len == 0 {
+        Err(err)
+    } else {
+        Ok(written_len)
+    }
+}
+
+/// A fixed-size FIFO of bytes on the kernel heap, whose
+/// data wraps around the end of the storage.
+struct RingBuffer {
+    data: Box<[u8]>,
+    /// The index of the first byte.
+    head: usize,
+    len: usize,
+}
+
+impl RingBuffer {
+    fn new(capacity: usize) -> Self {
+        Self {
+            data: vec![0; capacity].into_boxed_slice(),
+            head: 0,
+            len: 0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn is_full(&self) -> bool {
+        self.len == self.data.len()
+    }
+
+    /// Appends as many bytes of the `buf` as fit, and
+    /// returns the number of bytes appended.
+    fn push(&mut self, buf: &[u8]) -> usize {
+        let capacity = self.data.len();
+        let count = buf.len().min(capacity - self.len);
+        let tail = (self.head + self.len) % capacity;
+        let first_len = count.min(capacity - tail);
+        self.data[tail..tail + first_len].copy_from_slice(&buf[..first_len]);
+        self.data[..count - first_len].copy_from_slice(&buf[first_len..count]);
+        self.len += count;
+        count
+    }
+
+    /// Moves as many bytes as fit into the `buf`, and
+    /// returns the number of bytes moved.
+    fn pop(&mut self, buf: &mut [u8]) -> usize {
+        let capacity = self.data.len();
+        let count = buf.len().min(self.len);
+        let first_len = count.min(capacity - self.head);
+        buf[..first_len].copy_from_slice(&self.data[self.head..self.head + first_len]);
+        buf[first_len..count].copy_from_slice(&self.data[..count - first_len]);
+        self.head = (self.head + count) % capacity;
+        self.len -= count;
+        count
+    }
+}
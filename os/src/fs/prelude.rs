@@ -0,0 +1,20 @@
+pub(crate) use super::Dirent;
+pub(crate) use super::File;
+pub(crate) use super::FsError;
+pub(crate) use super::Stat;
+pub(crate) use super::init;
+pub(crate) use super::lookup_dir;
+pub(crate) use super::mkdir;
+pub(crate) use super::open;
+pub(crate) use super::read_dir;
+pub(crate) use super::read_file;
+pub(crate) use super::unlink;
+
+pub(crate) use super::fd_table::FdTable;
+
+pub(crate) use super::pipe::pipe;
+
+pub(crate) use super::stdio::poll_console_without_irq;
+
+pub(crate) use super::vfs::Dentry;
+pub(crate) use super::vfs::get_root;
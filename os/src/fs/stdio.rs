@@ -0,0 +1,141 @@
+extern crate alloc;
+
+use alloc::collections::VecDeque;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::console;
+use crate::fs::{File, FsError, S_IFCHR, Stat};
+use crate::sync::spin::SpinLock;
+use crate::sync::wait_queue::WaitQueue;
+use crate::task::prelude::has_current_pending_signal;
+
+/// The device number of the console.
+const CONSOLE_DEV: u64 = 5;
+
+/// The bytes received from the console but not yet read.
+static CONSOLE_INPUT: SpinLock<VecDeque<u8>> = SpinLock::new(VecDeque::new());
+/// The tasks waiting for the console input.
+static CONSOLE_WAITERS: WaitQueue = WaitQueue::new();
+/// Whether no interrupt announces the console input, so
+/// that the scheduling loop polls for it instead.
+static IS_POLLING: AtomicBool = AtomicBool::new(false);
+
+/// Moves the bytes that have arrived at the console into
+/// the input buffer, and wakes the tasks waiting for them.
+///
+/// It handles the receive interrupts of the console, if
+/// any.
+pub(super) fn poll_console_input() {
+    // The input stays locked while polling, so that the
+    // harts do not race for the same byte.
+    let mut input = CONSOLE_INPUT.lock();
+    let prev_len = input.len();
+    while let Some(c) = console::getchar() {
+        input.push_back(c);
+    }
+    let has_input = input.len() != prev_len;
+    drop(input);
+
+    if has_input {
+        CONSOLE_WAITERS.wake_all();
+    }
+}
+
+/// Makes the scheduling loop poll the console input, when
+/// the console has no interrupt, e.g., on the SBI console.
+pub(super) fn enable_console_polling() {
+    IS_POLLING.store(true, Ordering::Relaxed);
+}
+
+/// Polls the console input if no interrupt announces it,
+/// see [enable_console_polling]. The scheduling loop of
+/// each hart calls it between the tasks and while idle.
+pub(crate) fn poll_console_without_irq() {
+    if IS_POLLING.load(Ordering::Relaxed) {
+        poll_console_input();
+    }
+}
+
+/// Reads from the console. A read blocks the task until at
+/// least one byte has arrived, and then returns the bytes
+/// available without waiting for more. A signal makes it
+/// fail with [FsError::Interrupted].
+pub(super) struct Stdin;
+
+impl File for Stdin {
+    fn readable(&self) -> bool {
+        true
+    }
+
+    fn writable(&self) -> bool {
+        false
+    }
+
+    fn read(&self, buf: &mut [u8]) -> Result<usize, FsError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let mut input = CONSOLE_INPUT.lock();
+            let count = buf.len().min(input.len());
+            buf.iter_mut()
+                .zip(input.drain(..count))
+                .for_each(|(dst, c)| *dst = c);
+            drop(input);
+
+            if count != 0 {
+                return Ok(count);
+            }
+            if has_current_pending_signal() {
+                return Err(FsError::Interrupted);
+            }
+            CONSOLE_WAITERS.wait_if(|| CONSOLE_INPUT.lock().is_empty());
+        }
+    }
+
+    fn write(&self, _buf: &[u8]) -> Result<usize, FsError> {
+        Err(FsError::BadAccess)
+    }
+
+    fn stat(&self) -> Stat {
+        console_stat()
+    }
+}
+
+/// Writes to the console, which serves as both stdout and
+/// stderr.
+pub(super) struct Stdout;
+
+impl File for Stdout {
+    fn readable(&self) -> bool {
+        false
+    }
+
+    fn writable(&self) -> bool {
+        true
+    }
+
+    fn read(&self, _buf: &mut [u8]) -> Result<usize, FsError> {
+        Err(FsError::BadAccess)
+    }
+
+    fn write(&self, buf: &[u8]) -> Result<usize, FsError> {
+        console::write_bytes(buf);
+        Ok(buf.len())
+    }
+
+    fn stat(&self) -> Stat {
+        console_stat()
+    }
+}
+
+fn console_stat() -> Stat {
+    Stat {
+        dev: CONSOLE_DEV,
+        ino: 1,
+        mode: S_IFCHR | 0o620,
+        nlink: 1,
+        size: 0,
+    }
+}
@@ -0,0 +1,205 @@
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::fs::vfs::{self, DirEntry, Inode, InodeType};
+use crate::fs::{FsError, S_IFDIR, S_IFMT, S_IFREG, Stat};
+use crate::mm::prelude::{PAGE_SIZE_BYTES, Page, alloc_zeroed_page};
+use crate::sync::spin::SpinLock;
+use crate::{info, log, warn};
+
+/// The device number of tmpfs.
+const TMPFS_DEV: u64 = 4;
+/// Where tmpfs is mounted.
+const TMPFS_MOUNT_POINT: &str = "/tmp";
+
+static NEXT_INO: AtomicU64 = AtomicU64::new(1);
+
+/// Mounts an empty tmpfs.
+pub(super) fn init() {
+    let root: Arc<dyn Inode> = TmpfsInode::new(InodeType::Directory);
+    match vfs::mount(TMPFS_MOUNT_POINT, root) {
+        Ok(()) => {
+            info!("tmpfs: Mounted at {}", TMPFS_MOUNT_POINT);
+        }
+        Err(err) => {
+            warn!("tmpfs: Failed to mount, err={:?}", err);
+        }
+    }
+}
+
+/// A file or a directory in memory, whose data is kept in
+/// pages from the page allocator. A file lives as long as
+/// it is in a directory or open.
+struct TmpfsInode {
+    ino: u64,
+    content: SpinLock<Content>,
+}
+
+enum Content {
+    File {
+        pages: Vec<Page>,
+        size: usize,
+    },
+    Directory {
+        entries: BTreeMap<String, Arc<TmpfsInode>>,
+    },
+}
+
+impl TmpfsInode {
+    fn new(inode_type: InodeType) -> Arc<Self> {
+        let content = match inode_type {
+            InodeType::File => Content::File {
+                pages: Vec::new(),
+                size: 0,
+            },
+            InodeType::Directory => Content::Directory {
+                entries: BTreeMap::new(),
+            },
+        };
+        Arc::new(Self {
+            ino: NEXT_INO.fetch_add(1, Ordering::Relaxed),
+            content: SpinLock::new(content),
+        })
+    }
+
+    fn get_mode(&self) -> u32 {
+        match *self.content.lock() {
+            Content::File { .. } => S_IFREG | 0o644,
+            Content::Directory { .. } => S_IFDIR | 0o755,
+        }
+    }
+}
+
+impl Inode for TmpfsInode {
+    fn stat(&self) -> Result<Stat, FsError> {
+        let (mode, size) = match &*self.content.lock() {
+            Content::File { size, .. } => (S_IFREG | 0o644, *size),
+            Content::Directory { entries } => (S_IFDIR | 0o755, entries.len()),
+        };
+        Ok(Stat {
+            dev: TMPFS_DEV,
+            ino: self.ino,
+            mode,
+            nlink: 1,
+            size: size as u64,
+        })
+    }
+
+    fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize, FsError> {
+        let content = self.content.lock();
+        let Content::File { pages, size } = &*content else {
+            return Err(FsError::IsDirectory);
+        };
+        let end = (offset + buf.len()).min(*size);
+        if offset >= end {
+            return Ok(0);
+        }
+
+        let mut pos = offset;
+        while pos < end {
+            let start = pos % PAGE_SIZE_BYTES;
+            let len = (PAGE_SIZE_BYTES - start).min(end - pos);
+            let page = pages[pos / PAGE_SIZE_BYTES].as_bytes();
+            buf[pos - offset..pos - offset + len].copy_from_slice(&page[start..start + len]);
+            pos += len;
+        }
+        Ok(end - offset)
+    }
+
+    fn write_at(&self, offset: usize, buf: &[u8]) -> Result<usize, FsError> {
+        let mut content = self.content.lock();
+        let Content::File { pages, size } = &mut *content else {
+            return Err(FsError::IsDirectory);
+        };
+        let end = offset
+            .checked_add(buf.len())
+            .ok_or(FsError::FileTooLarge(usize::MAX))?;
+        while pages.len() < end.div_ceil(PAGE_SIZE_BYTES) {
+            pages.push(alloc_zeroed_page().ok_or(FsError::NoSpace)?);
+        }
+
+        let mut pos = offset;
+        while pos < end {
+            let start = pos % PAGE_SIZE_BYTES;
+            let len = (PAGE_SIZE_BYTES - start).min(end - pos);
+            let page = pages[pos / PAGE_SIZE_BYTES].as_bytes_mut();
+            page[start..start + len].copy_from_slice(&buf[pos - offset..pos - offset + len]);
+            pos += len;
+        }
+        *size = (*size).max(end);
+        Ok(buf.len())
+    }
+
+    fn truncate(&self) -> Result<(), FsError> {
+        let mut content = self.content.lock();
+        let Content::File { pages, size } = &mut *content else {
+            return Err(FsError::IsDirectory);
+        };
+        let pages = core::mem::take(pages);
+        *size = 0;
+        drop(content);
+
+        // Recycle the pages after the inode is unlocked.
+        drop(pages);
+        Ok(())
+    }
+
+    fn lookup(&self, name: &str) -> Result<Arc<dyn Inode>, FsError> {
+        let Content::Directory { entries } = &*self.content.lock() else {
+            return Err(FsError::NotDirectory);
+        };
+        let inode = entries.get(name).ok_or(FsError::NotFound)?;
+        Ok(inode.clone())
+    }
+
+    fn create(&self, name: &str, inode_type: InodeType) -> Result<Arc<dyn Inode>, FsError> {
+        let Content::Directory { entries } = &mut *self.content.lock() else {
+            return Err(FsError::NotDirectory);
+        };
+        if entries.contains_key(name) {
+            return Err(FsError::AlreadyExists);
+        }
+        let inode = TmpfsInode::new(inode_type);
+        entries.insert(name.to_string(), inode.clone());
+        Ok(inode)
+    }
+
+    fn unlink(&self, name: &str) -> Result<(), FsError> {
+        let mut content = self.content.lock();
+        let Content::Directory { entries } = &mut *content else {
+            return Err(FsError::NotDirectory);
+        };
+        let inode = entries.get(name).ok_or(FsError::NotFound)?;
+        if let Content::Directory { entries } = &*inode.content.lock() {
+            if !entries.is_empty() {
+                return Err(FsError::NotEmpty);
+            }
+        }
+        let inode = entries.remove(name);
+        drop(content);
+
+        // The data is freed after the directory is unlocked,
+        // unless the inode is still open.
+        drop(inode);
+        Ok(())
+    }
+
+    fn read_dir(&self) -> Result<Vec<DirEntry>, FsError> {
+        let Content::Directory { entries } = &*self.content.lock() else {
+            return Err(FsError::NotDirectory);
+        };
+        Ok(entries
+            .iter()
+            .map(|(name, inode)| DirEntry {
+                name: name.clone(),
+                ino: inode.ino,
+                file_type: inode.get_mode() & S_IFMT,
+            })
+            .collect())
+    }
+}
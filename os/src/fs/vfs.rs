@@ -0,0 +1,315 @@
+extern crate alloc;
+
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::fs::{File, FsError, S_IFDIR, S_IFMT, Stat};
+use crate::sync::mutex::Mutex;
+use crate::sync::spin::SpinLock;
+use crate::{log, warn};
+
+/// The longest name of a directory entry in bytes.
+pub(crate) const MAX_NAME_LEN: usize = 255;
+
+/// The root of the tree, which is set up once at boot.
+static ROOT: SpinLock<Option<Arc<Dentry>>> = SpinLock::new(None);
+
+/// The file systems mounted on the directories of others.
+static MOUNTS: SpinLock<Vec<Mount>> = SpinLock::new(Vec::new());
+
+/// A file or a directory of a file system, which is shared
+/// by the [Dentry]s and the [File]s referring to it.
+///
+/// The operations that a kind of inode does not support
+/// fail by default, e.g., a regular file has no entries.
+pub(super) trait Inode: Send + Sync {
+    fn stat(&self) -> Result<Stat, FsError>;
+
+    /// Reads the data from `offset` into the `buf`, and
+    /// returns the number of bytes read, which is zero at
+    /// the end of the data.
+    fn read_at(&self, _offset: usize, _buf: &mut [u8]) -> Result<usize, FsError> {
+        Err(FsError::IsDirectory)
+    }
+
+    /// Writes the `buf` to the data at `offset`, growing the
+    /// data if needed. Returns the number of bytes written.
+    fn write_at(&self, _offset: usize, _buf: &[u8]) -> Result<usize, FsError> {
+        Err(FsError::IsDirectory)
+    }
+
+    /// Discards the data.
+    fn truncate(&self) -> Result<(), FsError> {
+        Err(FsError::IsDirectory)
+    }
+
+    /// Returns the inode of the entry `name` of the
+    /// directory.
+    fn lookup(&self, _name: &str) -> Result<Arc<dyn Inode>, FsError> {
+        Err(FsError::NotDirectory)
+    }
+
+    /// Creates an empty inode of the `inode_type` as the
+    /// entry `name` of the directory and returns it.
+    fn create(&self, _name: &str, _inode_type: InodeType) -> Result<Arc<dyn Inode>, FsError> {
+        Err(FsError::NotDirectory)
+    }
+
+    /// Removes the entry `name` of the directory, which is
+    /// not a directory or is an empty one.
+    fn unlink(&self, _name: &str) -> Result<(), FsError> {
+        Err(FsError::NotDirectory)
+    }
+
+    /// Returns the entries of the directory, without `.`
+    /// and `..`.
+    fn read_dir(&self) -> Result<Vec<DirEntry>, FsError> {
+        Err(FsError::NotDirectory)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum InodeType {
+    File,
+    Directory,
+}
+
+/// An entry of a directory.
+#[derive(Debug, Clone)]
+pub(crate) struct DirEntry {
+    pub(crate) name: String,
+    pub(crate) ino: u64,
+    /// The file type bits, as in [Stat::mode].
+    pub(crate) file_type: u32,
+}
+
+/// An inode reached by a path, which remembers its parent
+/// so that `..` leads back the same way, even across mount
+/// points.
+pub(crate) struct Dentry {
+    name: String,
+    inode: Arc<dyn Inode>,
+    /// The parent directory, or [None] for the root.
+    parent: Option<Arc<Dentry>>,
+}
+
+impl Dentry {
+    pub(super) fn get_inode(&self) -> &Arc<dyn Inode> {
+        &self.inode
+    }
+
+    /// Returns the absolute path by which the dentry was
+    /// reached.
+    pub(crate) fn get_path(&self) -> String {
+        let Some(parent) = &self.parent else {
+            return "/".to_string();
+        };
+        let mut path = parent.get_path();
+        if !path.ends_with('/') {
+            path.push('/');
+        }
+        path + &self.name
+    }
+
+    pub(crate) fn is_dir(&self) -> Result<bool, FsError> {
+        Ok(self.inode.stat()?.mode & S_IFMT == S_IFDIR)
+    }
+}
+
+impl fmt::Debug for Dentry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Dentry({})", self.get_path())
+    }
+}
+
+/// A file system mounted on a directory, which is known by
+/// its device and inode numbers.
+struct Mount {
+    dev: u64,
+    ino: u64,
+    root: Arc<dyn Inode>,
+}
+
+/// Sets the `root` inode as the root of the tree.
+pub(super) fn mount_root(root: Arc<dyn Inode>) {
+    *ROOT.lock() = Some(Arc::new(Dentry {
+        name: String::new(),
+        inode: root,
+        parent: None,
+    }));
+}
+
+/// Mounts the file system of the `root` inode on the
+/// directory at the absolute `path`, hiding its entries.
+pub(super) fn mount(path: &str, root: Arc<dyn Inode>) -> Result<(), FsError> {
+    let dentry = lookup(&get_root(), path)?;
+    if !dentry.is_dir()? {
+        return Err(FsError::NotDirectory);
+    }
+
+    let stat = dentry.inode.stat()?;
+    MOUNTS.lock().push(Mount {
+        dev: stat.dev,
+        ino: stat.ino,
+        root,
+    });
+    Ok(())
+}
+
+/// Returns the root of the tree.
+pub(crate) fn get_root() -> Arc<Dentry> {
+    ROOT.lock()
+        .clone()
+        .expect("The root file system is not mounted")
+}
+
+/// Returns the root of the file system mounted on the
+/// `inode`, if any.
+fn get_mounted_root(inode: &Arc<dyn Inode>) -> Result<Option<Arc<dyn Inode>>, FsError> {
+    let stat = inode.stat()?;
+    Ok(MOUNTS
+        .lock()
+        .iter()
+        .find(|mount| mount.dev == stat.dev && mount.ino == stat.ino)
+        .map(|mount| mount.root.clone()))
+}
+
+/// Returns the dentry at the `path`, which is relative to
+/// the `cwd` unless it is absolute.
+pub(super) fn lookup(cwd: &Arc<Dentry>, path: &str) -> Result<Arc<Dentry>, FsError> {
+    if path.is_empty() {
+        return Err(FsError::NotFound);
+    }
+
+    let mut dentry = if path.starts_with('/') {
+        get_root()
+    } else {
+        cwd.clone()
+    };
+    for name in path.split('/') {
+        dentry = lookup_child(&dentry, name)?;
+    }
+    Ok(dentry)
+}
+
+/// Returns the parent directory of the `path` relative to
+/// the `cwd`, and the last name in the `path`, which is
+/// neither `.` nor `..`.
+pub(super) fn lookup_parent<'a>(
+    cwd: &Arc<Dentry>,
+    path: &'a str,
+) -> Result<(Arc<Dentry>, &'a str), FsError> {
+    let path = path.trim_end_matches('/');
+    let (parent_path, name) = match path.rsplit_once('/') {
+        Some(("", name)) => ("/", name),
+        Some((parent_path, name)) => (parent_path, name),
+        None => (".", path),
+    };
+    if name.is_empty() || name == "." || name == ".." || name.len() > MAX_NAME_LEN {
+        return Err(FsError::InvalidName);
+    }
+
+    let parent = lookup(cwd, parent_path)?;
+    if !parent.is_dir()? {
+        return Err(FsError::NotDirectory);
+    }
+    Ok((parent, name))
+}
+
+/// Returns the entry `name` of the directory `dentry`,
+/// where `.` and `..` are resolved and the mount points
+/// lead to the roots mounted on them.
+pub(super) fn lookup_child(dentry: &Arc<Dentry>, name: &str) -> Result<Arc<Dentry>, FsError> {
+    match name {
+        "" | "." | ".." if !dentry.is_dir()? => Err(FsError::NotDirectory),
+        "" | "." => Ok(dentry.clone()),
+        ".." => Ok(dentry.parent.clone().unwrap_or_else(|| dentry.clone())),
+        _ => {
+            let mut inode = dentry.inode.lookup(name)?;
+            if let Some(root) = get_mounted_root(&inode)? {
+                inode = root;
+            }
+            Ok(Arc::new(Dentry {
+                name: name.to_string(),
+                inode,
+                parent: Some(dentry.clone()),
+            }))
+        }
+    }
+}
+
+/// Returns whether a file system is mounted on the
+/// `dentry`, i.e., it refers to the root of that one.
+pub(super) fn is_mount_point(dentry: &Dentry) -> bool {
+    MOUNTS
+        .lock()
+        .iter()
+        .any(|mount| Arc::ptr_eq(&mount.root, &dentry.inode))
+}
+
+/// A [File] opened from an inode, whose reads and writes
+/// start at its offset. The offset of a directory counts
+/// the entries read.
+pub(super) struct InodeFile {
+    inode: Arc<dyn Inode>,
+    readable: bool,
+    writable: bool,
+    offset: Mutex<usize>,
+}
+
+impl InodeFile {
+    pub(super) fn new(inode: Arc<dyn Inode>, readable: bool, writable: bool) -> Self {
+        Self {
+            inode,
+            readable,
+            writable,
+            offset: Mutex::new(0),
+        }
+    }
+}
+
+impl File for InodeFile {
+    fn readable(&self) -> bool {
+        self.readable
+    }
+
+    fn writable(&self) -> bool {
+        self.writable
+    }
+
+    fn read(&self, buf: &mut [u8]) -> Result<usize, FsError> {
+        let mut offset = self.offset.lock();
+        let len = self.inode.read_at(*offset, buf)?;
+        *offset += len;
+        Ok(len)
+    }
+
+    fn write(&self, buf: &[u8]) -> Result<usize, FsError> {
+        let mut offset = self.offset.lock();
+        let len = self.inode.write_at(*offset, buf)?;
+        *offset += len;
+        Ok(len)
+    }
+
+    fn stat(&self) -> Stat {
+        self.inode.stat().unwrap_or_else(|err| {
+            warn!("vfs: Failed to stat, err={:?}", err);
+            Stat::default()
+        })
+    }
+
+    fn read_dir(&self, count: usize) -> Result<Vec<DirEntry>, FsError> {
+        let mut offset = self.offset.lock();
+        let entries: Vec<_> = self
+            .inode
+            .read_dir()?
+            .into_iter()
+            .skip(*offset)
+            .take(count)
+            .collect();
+        *offset += entries.len();
+        Ok(entries)
+    }
+}
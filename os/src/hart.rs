@@ -0,0 +1,35 @@
+use crate::mm::prelude::get_pa_from_va;
+use crate::sbi;
+use crate::{info, log, warn};
+
+/// The maximum number of harts, which should be kept in
+/// sync with the boot stacks in entry.S and linker.ld.
+pub(crate) const MAX_HARTS: usize = 8;
+
+/// Starts all harts other than the boot hart through the
+/// SBI HSM extension. They enter `_start_secondary` in
+/// entry.S with their own boot stacks.
+///
+/// In the deterministic mode, they are left stopped, since
+/// the interleaving of harts cannot be reproduced.
+pub(crate) fn start_secondary_harts(boot_hart_id: usize) {
+    unsafe extern "C" {
+        unsafe fn _start_secondary();
+    }
+
+    if cfg!(feature = "deterministic") {
+        for hart_id in (0..MAX_HARTS).filter(|&id| id != boot_hart_id) {
+            if sbi::has_hart(hart_id) {
+                warn!("Hart {}: Not started in the deterministic mode", hart_id);
+            }
+        }
+        return;
+    }
+
+    let start_pa = get_pa_from_va(_start_secondary as usize);
+    for hart_id in (0..MAX_HARTS).filter(|&id| id != boot_hart_id) {
+        if sbi::start_hart(hart_id, start_pa, 0) {
+            info!("Hart {}: Started", hart_id);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+extern crate alloc;
+
+mod fdt;
+
+use alloc::vec::Vec;
+
+use crate::machine::fdt::{Fdt, FdtError, Node};
+use crate::mm::prelude::get_va_from_pa;
+use crate::sync::spin::SpinLock;
+use crate::{debug, log};
+
+/// The machine described by the device tree, which is
+/// parsed once at boot.
+static MACHINE: SpinLock<Machine> = SpinLock::new(Machine::new());
+
+/// An MMIO device found in the device tree.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Device {
+    pub(crate) base_pa: usize,
+    pub(crate) size: usize,
+    /// The interrupt source at the PLIC, if any.
+    pub(crate) irq: Option<usize>,
+}
+
+impl Device {
+    /// Returns the [Device] of the `node` from its first
+    /// `reg` entry, or [None] if it has none.
+    fn from_node(node: &Node) -> Option<Self> {
+        let &(base_pa, size) = node.get_reg().first()?;
+        Some(Self {
+            base_pa,
+            size,
+            irq: node.get_u32("interrupts").map(|irq| irq as usize),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Machine {
+    /// The (start_pa, size) pairs of the physical memory.
+    memory_regions: Vec<(usize, usize)>,
+    /// The (start_pa, size) pairs of the physical memory
+    /// that the kernel should not allocate, including the
+    /// device tree blob.
+    reserved_regions: Vec<(usize, usize)>,
+    uart: Option<Device>,
+    plic: Option<Device>,
+    clint: Option<Device>,
+    rtc: Option<Device>,
+    /// The virtio-mmio devices, ordered by base address.
+    virtio_devices: Vec<Device>,
+    /// The frequency of the time counter in Hz.
+    timebase_frequency: usize,
+}
+
+impl Machine {
+    const fn new() -> Self {
+        Self {
+            memory_regions: Vec::new(),
+            reserved_regions: Vec::new(),
+            uart: None,
+            plic: None,
+            clint: None,
+            rtc: None,
+            virtio_devices: Vec::new(),
+            timebase_frequency: 0,
+        }
+    }
+
+    fn parse(fdt: &Fdt, dtb_pa: usize) -> Result<Self, FdtError> {
+        let mut machine = Self::new();
+        machine.reserved_regions = fdt.get_mem_reservations()?;
+        machine
+            .reserved_regions
+            .push((dtb_pa, fdt.get_total_size()));
+
+        let nodes = fdt.get_nodes()?;
+        for node in nodes.iter().filter(|node| node.is_enabled()) {
+            let parent_name = node.get_parent().map(|parent| nodes[parent].get_name());
+
+            if node.get_str("device_type") == Some("memory") {
+                machine.memory_regions.extend(node.get_reg());
+            } else if parent_name == Some("reserved-memory") {
+                machine.reserved_regions.extend(node.get_reg());
+            } else if node.is_compatible("ns16550a") {
+                machine.uart = machine.uart.or(Device::from_node(node));
+            } else if node.is_compatible("riscv,plic0") || node.is_compatible("sifive,plic-1.0.0") {
+                machine.plic = machine.plic.or(Device::from_node(node));
+            } else if node.is_compatible("riscv,clint0") || node.is_compatible("sifive,clint0") {
+                machine.clint = machine.clint.or(Device::from_node(node));
+            } else if node.is_compatible("google,goldfish-rtc") {
+                machine.rtc = machine.rtc.or(Device::from_node(node));
+            } else if node.is_compatible("virtio,mmio") {
+                machine.virtio_devices.extend(Device::from_node(node));
+            }
+
+            // It is usually in /cpus, but may be in each cpu.
+            if machine.timebase_frequency == 0 {
+                if let Some(frequency) = node.get_u32("timebase-frequency") {
+                    machine.timebase_frequency = frequency as usize;
+                }
+            }
+        }
+
+        machine.virtio_devices.sort_by_key(|device| device.base_pa);
+        Ok(machine)
+    }
+}
+
+/// Parses the device tree at the `dtb_pa`, which should be
+/// called after the kernel heap is ready and before the
+/// kernel space is built from it.
+pub(crate) fn init(dtb_pa: usize) {
+    // SAFETY:
+    // The bootloader passes a valid blob and the boot page
+    // table maps all physical memory.
+    let machine = unsafe { Fdt::from_ptr(get_va_from_pa(dtb_pa) as *const u8) }
+        .and_then(|fdt| Machine::parse(&fdt, dtb_pa))
+        .unwrap_or_else(|err| panic!("Failed to parse the device tree, err={:?}", err));
+
+    assert!(
+        !machine.memory_regions.is_empty(),
+        "No memory in the device tree"
+    );
+    assert_ne!(
+        machine.timebase_frequency, 0,
+        "No timebase frequency in the device tree"
+    );
+    *MACHINE.lock() = machine;
+}
+
+pub(crate) fn get_memory_regions() -> Vec<(usize, usize)> {
+    MACHINE.lock().memory_regions.clone()
+}
+
+pub(crate) fn get_reserved_regions() -> Vec<(usize, usize)> {
+    MACHINE.lock().reserved_regions.clone()
+}
+
+pub(crate) fn get_uart() -> Option<Device> {
+    MACHINE.lock().uart
+}
+
+pub(crate) fn get_plic() -> Option<Device> {
+    MACHINE.lock().plic
+}
+
+pub(crate) fn get_rtc() -> Option<Device> {
+    MACHINE.lock().rtc
+}
+
+pub(crate) fn get_virtio_devices() -> Vec<Device> {
+    MACHINE.lock().virtio_devices.clone()
+}
+
+pub(crate) fn get_timebase_frequency() -> usize {
+    MACHINE.lock().timebase_frequency
+}
+
+/// Returns the (base_pa, size) pairs of all devices found,
+/// which the kernel space maps.
+pub(crate) fn get_mmio_regions() -> Vec<(usize, usize)> {
+    let machine = MACHINE.lock();
+    [machine.uart, machine.plic, machine.clint, machine.rtc]
+        .into_iter()
+        .flatten()
+        .chain(machine.virtio_devices.iter().copied())
+        .map(|device| (device.base_pa, device.size))
+        .collect()
+}
+
+pub(crate) fn log_machine() {
+    let machine = MACHINE.lock();
+    for &(start, size) in machine.memory_regions.iter() {
+        debug!("memory [{:#x}, {:#x}) size={}", start, start + size, size);
+    }
+    for &(start, size) in machine.reserved_regions.iter() {
+        debug!("reserved [{:#x}, {:#x}) size={}", start, start + size, size);
+    }
+    debug!("uart {:x?}", machine.uart);
+    debug!("plic {:x?}", machine.plic);
+    debug!("clint {:x?}", machine.clint);
+    debug!("rtc {:x?}", machine.rtc);
+    for device in machine.virtio_devices.iter() {
+        debug!("virtio {:x?}", device);
+    }
+    debug!("timebase-frequency={}", machine.timebase_frequency);
+}
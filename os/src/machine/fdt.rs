@@ -0,0 +1,319 @@
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::{slice, str};
+
+// The layout of a flattened device tree, see [here].
+//
+// [here]: https://github.com/devicetree-org/devicetree-specification/releases
+const FDT_MAGIC: u32 = 0xd00d_feed;
+/// The version whose layout is parsed here, which is also
+/// understood by the later versions.
+const FDT_COMPATIBLE_VERSION: u32 = 16;
+
+// Offsets of the header fields.
+const HEADER_MAGIC: usize = 0;
+const HEADER_TOTAL_SIZE: usize = 4;
+const HEADER_OFF_DT_STRUCT: usize = 8;
+const HEADER_OFF_DT_STRINGS: usize = 12;
+const HEADER_OFF_MEM_RSVMAP: usize = 16;
+const HEADER_LAST_COMP_VERSION: usize = 24;
+const HEADER_SIZE_DT_STRINGS: usize = 32;
+const HEADER_SIZE_DT_STRUCT: usize = 36;
+const HEADER_SIZE: usize = 40;
+
+// Tokens of the structure block.
+const FDT_BEGIN_NODE: u32 = 0x1;
+const FDT_END_NODE: u32 = 0x2;
+const FDT_PROP: u32 = 0x3;
+const FDT_NOP: u32 = 0x4;
+const FDT_END: u32 = 0x9;
+
+/// The cells of the `reg` of a node whose parent does not
+/// specify them.
+const DEFAULT_ADDRESS_CELLS: usize = 2;
+const DEFAULT_SIZE_CELLS: usize = 1;
+
+#[allow(dead_code)]
+#[derive(Debug)]
+pub(crate) enum FdtError {
+    /// (found magic).
+    BadMagic(u32),
+    /// (last compatible version).
+    UnsupportedVersion(u32),
+    /// (offset).
+    Truncated(usize),
+    /// (token, offset in the structure block).
+    UnexpectedToken(u32, usize),
+    /// (offset).
+    InvalidString(usize),
+}
+
+/// A flattened device tree, which borrows the blob passed
+/// by the bootloader.
+pub(super) struct Fdt<'a> {
+    blob: &'a [u8],
+    structs: &'a [u8],
+    strings: &'a [u8],
+    mem_rsvmap: &'a [u8],
+}
+
+impl<'a> Fdt<'a> {
+    /// Returns the [Fdt] of the blob at `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to a readable blob that lives and
+    /// stays unchanged for `'a`, at least as large as its
+    /// header states if it starts with the magic.
+    pub(super) unsafe fn from_ptr(ptr: *const u8) -> Result<Self, FdtError> {
+        let header = unsafe { slice::from_raw_parts(ptr, HEADER_SIZE) };
+        let magic = read_u32(header, HEADER_MAGIC)?;
+        if magic != FDT_MAGIC {
+            return Err(FdtError::BadMagic(magic));
+        }
+
+        let total_size = read_u32(header, HEADER_TOTAL_SIZE)? as usize;
+        Self::from_bytes(unsafe { slice::from_raw_parts(ptr, total_size) })
+    }
+
+    pub(super) fn from_bytes(blob: &'a [u8]) -> Result<Self, FdtError> {
+        let magic = read_u32(blob, HEADER_MAGIC)?;
+        if magic != FDT_MAGIC {
+            return Err(FdtError::BadMagic(magic));
+        }
+        let version = read_u32(blob, HEADER_LAST_COMP_VERSION)?;
+        if version > FDT_COMPATIBLE_VERSION {
+            return Err(FdtError::UnsupportedVersion(version));
+        }
+
+        let get_block = |off_field, size_field| -> Result<&'a [u8], FdtError> {
+            let offset = read_u32(blob, off_field)? as usize;
+            let size = read_u32(blob, size_field)? as usize;
+            blob.get(offset..offset + size)
+                .ok_or(FdtError::Truncated(offset + size))
+        };
+        let rsvmap_offset = read_u32(blob, HEADER_OFF_MEM_RSVMAP)? as usize;
+        Ok(Self {
+            blob,
+            structs: get_block(HEADER_OFF_DT_STRUCT, HEADER_SIZE_DT_STRUCT)?,
+            strings: get_block(HEADER_OFF_DT_STRINGS, HEADER_SIZE_DT_STRINGS)?,
+            mem_rsvmap: blob
+                .get(rsvmap_offset..)
+                .ok_or(FdtError::Truncated(rsvmap_offset))?,
+        })
+    }
+
+    pub(super) fn get_total_size(&self) -> usize {
+        self.blob.len()
+    }
+
+    /// Returns the (start, size) pairs of the memory reserve
+    /// block, which precede the reserved-memory nodes.
+    pub(super) fn get_mem_reservations(&self) -> Result<Vec<(usize, usize)>, FdtError> {
+        let mut result = Vec::new();
+        for offset in (0..).step_by(16) {
+            let start = read_u64(self.mem_rsvmap, offset)? as usize;
+            let size = read_u64(self.mem_rsvmap, offset + 8)? as usize;
+            if start == 0 && size == 0 {
+                break;
+            }
+            result.push((start, size));
+        }
+        Ok(result)
+    }
+
+    /// Returns all nodes in the order they appear, where a
+    /// parent precedes its children.
+    pub(super) fn get_nodes(&self) -> Result<Vec<Node<'a>>, FdtError> {
+        let mut nodes: Vec<Node<'a>> = Vec::new();
+        // The indices of the nodes being parsed, from the root.
+        let mut open_nodes = Vec::new();
+        let mut offset = 0;
+
+        loop {
+            let token_offset = offset;
+            let token = read_u32(self.structs, offset)?;
+            offset += 4;
+
+            match token {
+                FDT_BEGIN_NODE => {
+                    let name = read_c_str(self.structs, offset)?;
+                    offset = (offset + name.len() + 1).next_multiple_of(4);
+
+                    // The properties of the parent precede its
+                    // children, so its cells are known.
+                    let parent = open_nodes.last().copied();
+                    let (address_cells, size_cells) = parent.map_or(
+                        (DEFAULT_ADDRESS_CELLS, DEFAULT_SIZE_CELLS),
+                        |parent: usize| {
+                            let parent = &nodes[parent];
+                            (
+                                parent
+                                    .get_u32("#address-cells")
+                                    .map_or(DEFAULT_ADDRESS_CELLS, |c| c as usize),
+                                parent
+                                    .get_u32("#size-cells")
+                                    .map_or(DEFAULT_SIZE_CELLS, |c| c as usize),
+                            )
+                        },
+                    );
+                    open_nodes.push(nodes.len());
+                    nodes.push(Node {
+                        name,
+                        parent,
+                        address_cells,
+                        size_cells,
+                        props: Vec::new(),
+                    });
+                }
+
+                FDT_END_NODE => {
+                    open_nodes
+                        .pop()
+                        .ok_or(FdtError::UnexpectedToken(token, token_offset))?;
+                }
+
+                FDT_PROP => {
+                    let len = read_u32(self.structs, offset)? as usize;
+                    let name_offset = read_u32(self.structs, offset + 4)? as usize;
+                    offset += 8;
+                    let value = self
+                        .structs
+                        .get(offset..offset + len)
+                        .ok_or(FdtError::Truncated(offset + len))?;
+                    offset = (offset + len).next_multiple_of(4);
+
+                    let &node = open_nodes
+                        .last()
+                        .ok_or(FdtError::UnexpectedToken(token, token_offset))?;
+                    nodes[node].props.push(Property {
+                        name: read_c_str(self.strings, name_offset)?,
+                        value,
+                    });
+                }
+
+                FDT_NOP => {}
+
+                FDT_END if open_nodes.is_empty() => return Ok(nodes),
+
+                _ => return Err(FdtError::UnexpectedToken(token, token_offset)),
+            }
+        }
+    }
+}
+
+/// A node of the device tree with its properties.
+#[derive(Debug)]
+pub(super) struct Node<'a> {
+    /// The node name with the unit address, e.g., uart@1000.
+    name: &'a str,
+    /// The index of the parent among all nodes.
+    parent: Option<usize>,
+    /// The cells of an address and a size in the `reg`,
+    /// which the parent specifies.
+    address_cells: usize,
+    size_cells: usize,
+    props: Vec<Property<'a>>,
+}
+
+impl<'a> Node<'a> {
+    pub(super) fn get_name(&self) -> &'a str {
+        self.name
+    }
+
+    pub(super) fn get_parent(&self) -> Option<usize> {
+        self.parent
+    }
+
+    pub(super) fn get_prop(&self, name: &str) -> Option<&'a [u8]> {
+        self.props
+            .iter()
+            .find(|prop| prop.name == name)
+            .map(|prop| prop.value)
+    }
+
+    pub(super) fn get_u32(&self, name: &str) -> Option<u32> {
+        read_u32(self.get_prop(name)?, 0).ok()
+    }
+
+    /// Returns the property `name` as a string, without the
+    /// trailing NUL.
+    pub(super) fn get_str(&self, name: &str) -> Option<&'a str> {
+        read_c_str(self.get_prop(name)?, 0).ok()
+    }
+
+    /// Returns whether the `compatible` property lists the
+    /// `compatible`.
+    pub(super) fn is_compatible(&self, compatible: &str) -> bool {
+        self.get_prop("compatible").is_some_and(|value| {
+            value
+                .split(|&c| c == 0)
+                .any(|entry| entry == compatible.as_bytes())
+        })
+    }
+
+    /// Returns whether the device is usable, i.e., its status
+    /// is absent or "okay".
+    pub(super) fn is_enabled(&self) -> bool {
+        self.get_str("status")
+            .is_none_or(|status| status == "okay" || status == "ok")
+    }
+
+    /// Returns the (address, size) pairs of the `reg`.
+    pub(super) fn get_reg(&self) -> Vec<(usize, usize)> {
+        let Some(value) = self.get_prop("reg") else {
+            return Vec::new();
+        };
+        let entry_size = (self.address_cells + self.size_cells) * 4;
+        if entry_size == 0 {
+            return Vec::new();
+        }
+
+        value
+            .chunks_exact(entry_size)
+            .map(|entry| {
+                let (address, size) = entry.split_at(self.address_cells * 4);
+                (read_cells(address), read_cells(size))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+struct Property<'a> {
+    name: &'a str,
+    value: &'a [u8],
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, FdtError> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_be_bytes(b.try_into().unwrap()))
+        .ok_or(FdtError::Truncated(offset + 4))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Result<u64, FdtError> {
+    bytes
+        .get(offset..offset + 8)
+        .map(|b| u64::from_be_bytes(b.try_into().unwrap()))
+        .ok_or(FdtError::Truncated(offset + 8))
+}
+
+/// Reads the big-endian number made of the 32-bit `cells`,
+/// keeping the lower 64 bits.
+fn read_cells(cells: &[u8]) -> usize {
+    cells.chunks_exact(4).fold(0, |acc, cell| {
+        (acc << 32) | u32::from_be_bytes(cell.try_into().unwrap()) as usize
+    })
+}
+
+/// Reads the NUL-terminated string at `offset`.
+fn read_c_str(bytes: &[u8], offset: usize) -> Result<&str, FdtError> {
+    let bytes = bytes.get(offset..).ok_or(FdtError::Truncated(offset))?;
+    let len = bytes
+        .iter()
+        .position(|&c| c == 0)
+        .ok_or(FdtError::InvalidString(offset))?;
+    str::from_utf8(&bytes[..len]).map_err(|_| FdtError::InvalidString(offset))
+}
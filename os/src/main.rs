@@ -2,8 +2,13 @@
 #![no_main]
 
 mod console;
+mod drivers;
+mod fs;
+mod hart;
 mod lang_items;
+mod machine;
 mod mm;
+mod random;
 mod sbi;
 mod sync;
 mod syscall;
@@ -14,21 +19,41 @@ mod trap;
 use core::arch::global_asm;
 
 use console::log;
+use fs::prelude as fs_p;
 use mm::prelude as mm_p;
 use task::prelude as task_p;
 
 global_asm!(include_str!("entry.S"));
-global_asm!(include_str!("link_apps.S"));
 
+/// The entry of the boot hart, which is the only hart
+/// running until it starts the others.
 #[unsafe(no_mangle)]
-pub fn rust_main() -> ! {
+pub fn rust_main(hart_id: usize, dtb_pa: usize) -> ! {
+    mm_p::init_heap();
+    machine::init(dtb_pa);
     mm_p::init();
+    console::init();
 
     log::init();
+    drivers::init();
+    timer::init();
+    random::init();
+    machine::log_machine();
     mm_p::log_kernel_layout();
-    task_p::log_app_elfs();
+    fs_p::init();
 
-    trap::init();
+    trap::init(hart_id);
 
-    task_p::start();
+    task_p::add_initial_tasks(hart_id);
+    hart::start_secondary_harts(hart_id);
+    task_p::run_tasks(hart_id);
+}
+
+/// The entry of the secondary harts, which start after the
+/// boot hart has initialized the kernel.
+#[unsafe(no_mangle)]
+pub fn rust_main_secondary(hart_id: usize) -> ! {
+    mm_p::init_secondary();
+    trap::init(hart_id);
+    task_p::run_tasks(hart_id);
 }
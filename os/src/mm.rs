@@ -1,7 +1,9 @@
+mod asid;
 mod heap_alloc;
 mod page_alloc;
 pub mod prelude;
 mod sv39;
+mod tlb;
 mod uaccess;
 mod vm;
 
@@ -25,22 +27,6 @@ unsafe extern "C" {
     fn kernel_end();
 }
 
-/// The (base_pa, size_bytes) pairs of the QEMU virt machine
-/// MMIO scheme. For more details, see [here].
-///
-/// [here]: https://github.com/qemu/qemu/blob/master/hw/riscv/virt.c#L82
-const QEMU_VIRT_MMIO: &[(usize, usize)] = &[
-    (0x0010_0000, 0x0000_1000), // VIRT_TEST
-    (0x0010_1000, 0x0000_1000), // VIRT_RTC
-    (0x0200_0000, 0x0001_0000), // VIRT_CLINT
-    (0x0c00_0000, 0x0060_0000), // VIRT_PLIC
-    (0x1000_0000, 0x0000_0100), // VIRT_UART0
-    (0x1001_0000, 0x0000_1000), // VIRT_VIRTIO
-];
-
-const MEM_START_PA: usize = 0x8000_0000;
-const MEM_SIZE_BYTES: usize = 128 << 20; // 128 MiB
-
 const PAGE_SIZE_ORDER: usize = 12;
 pub(crate) const PAGE_SIZE_BYTES: usize = 1 << PAGE_SIZE_ORDER; // 4 KiB
 const LARGE_PAGE_SIZE_ORDER: usize = 21;
@@ -54,11 +40,25 @@ const KERNEL_HEAP_SIZE_BYTES: usize = 4 << 20; // 4 MiB
 const USER_SPACE_END: usize = 0x40_0000_0000 - PAGE_SIZE_BYTES;
 const USER_STACK_MAX_SIZE_BYTES: usize = 8 << 20; // 8 MiB
 
-pub(crate) fn init() {
+/// Clears the .bss and sets up the kernel heap, which
+/// should precede everything else.
+pub(crate) fn init_heap() {
     clear_bss();
     heap_alloc::init();
+}
+
+/// Builds and activates the kernel space from the memory
+/// and devices found in the device tree.
+pub(crate) fn init() {
     vm::init_kernel_satp().expect("Failed to activate kernel satp");
     vm::activate_kernel_space();
+    asid::init();
+}
+
+/// Switches a secondary hart from the boot page table to
+/// the kernel space prepared by [init].
+pub(crate) fn init_secondary() {
+    vm::activate_kernel_space();
 }
 
 fn clear_bss() {
@@ -75,13 +75,13 @@ fn get_pa_mut_ptr(pa: usize) -> *mut u8 {
 
 /// Returns the virtual address of the given `pa` under
 /// the kernel's satp.
-fn get_va_from_pa(pa: usize) -> usize {
+pub(crate) fn get_va_from_pa(pa: usize) -> usize {
     pa.checked_add(KERNEL_VA_OFFSET).expect("address overflow")
 }
 
 /// Returns the physical address of the given `va` under
 /// the kernel's satp.
-fn get_pa_from_va(va: usize) -> usize {
+pub(crate) fn get_pa_from_va(va: usize) -> usize {
     va.checked_sub(KERNEL_VA_OFFSET).expect("address underflow")
 }
 
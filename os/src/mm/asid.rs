@@ -0,0 +1,114 @@
+use core::arch::asm;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use riscv::regs::satp;
+
+use crate::hart::MAX_HARTS;
+use crate::sync::spin::SpinLock;
+use crate::{info, log};
+
+/// The ASID of the kernel space, which is never assigned
+/// to a [VMSpace].
+///
+/// [VMSpace]: super::vm::VMSpace
+pub(super) const KERNEL_ASID: usize = 0;
+
+static ASID_ALLOCATOR: SpinLock<AsidAllocator> = SpinLock::new(AsidAllocator::new());
+
+/// The generation up to which each hart has flushed its
+/// TLB. A hart must flush the translations of the previous
+/// generations before it runs an ASID of a newer one.
+static HART_GENERATIONS: [AtomicUsize; MAX_HARTS] = [const { AtomicUsize::new(0) }; MAX_HARTS];
+
+/// An ASID and the generation it is allocated in. It gets
+/// stale once the pool rolls over to a new generation, and
+/// should be reallocated before its next use.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Asid {
+    value: usize,
+    generation: usize,
+}
+
+impl Asid {
+    pub(super) fn get_value(&self) -> usize {
+        self.value
+    }
+}
+
+struct AsidAllocator {
+    /// The largest ASID the harts implement, or zero if they
+    /// do not support ASIDs.
+    max_asid: usize,
+    next_asid: usize,
+    generation: usize,
+}
+
+impl AsidAllocator {
+    const fn new() -> Self {
+        Self {
+            max_asid: 0,
+            next_asid: KERNEL_ASID + 1,
+            generation: 1,
+        }
+    }
+}
+
+/// Probes the number of ASID bits, which should be called
+/// once the kernel space is active.
+pub(super) fn init() {
+    let asid_bits = satp::probe_asid_bits();
+    ASID_ALLOCATOR.lock().max_asid = (1 << asid_bits) - 1;
+    info!("ASID bits: {}", asid_bits);
+}
+
+/// Allocates an ASID of the current generation. Once the
+/// pool is exhausted, it rolls over to a new generation in
+/// which all ASIDs are free again, see [sync_hart].
+///
+/// Without ASID support, all [VMSpace]s share the
+/// [KERNEL_ASID].
+///
+/// [VMSpace]: super::vm::VMSpace
+pub(super) fn alloc_asid() -> Asid {
+    let mut allocator = ASID_ALLOCATOR.lock();
+    if allocator.max_asid == 0 {
+        return Asid {
+            value: KERNEL_ASID,
+            generation: allocator.generation,
+        };
+    }
+
+    if allocator.next_asid > allocator.max_asid {
+        allocator.generation += 1;
+        allocator.next_asid = KERNEL_ASID + 1;
+        info!("ASID rolled over to generation {}", allocator.generation);
+    }
+
+    let value = allocator.next_asid;
+    allocator.next_asid += 1;
+    Asid {
+        value,
+        generation: allocator.generation,
+    }
+}
+
+/// Returns whether the `asid` is of the current generation.
+pub(super) fn is_current(asid: Asid) -> bool {
+    asid.generation == ASID_ALLOCATOR.lock().generation
+}
+
+/// Flushes the whole TLB of the current hart `hart_id` if
+/// it has not done so since the last rollover, so that the
+/// reused ASIDs do not hit the stale translations. Without
+/// ASID support, it always flushes.
+pub(super) fn sync_hart(hart_id: usize) {
+    let allocator = ASID_ALLOCATOR.lock();
+    let generation = allocator.generation;
+    let is_supported = allocator.max_asid != 0;
+    drop(allocator);
+
+    let prev = HART_GENERATIONS[hart_id].swap(generation, Ordering::Relaxed);
+    if prev != generation || !is_supported {
+        unsafe { asm!("sfence.vma") };
+    }
+}
@@ -1,18 +1,43 @@
 extern crate alloc;
 
+use core::alloc::{GlobalAlloc, Layout};
+use core::ptr::{self, NonNull};
+
 use alloc::vec::Vec;
-use buddy_system_allocator::LockedHeap;
+use buddy_system_allocator::Heap;
 
 use crate::mm::{KERNEL_HEAP_SIZE_BYTES, bss_end, bss_start};
+use crate::sync::spin::SpinLock;
 
 static mut KERNEL_HEAP: [u8; KERNEL_HEAP_SIZE_BYTES] = [0; KERNEL_HEAP_SIZE_BYTES];
 
 #[global_allocator]
-static HEAP_ALLOCATOR: LockedHeap<23> = LockedHeap::empty();
+static HEAP_ALLOCATOR: KernelHeap = KernelHeap(SpinLock::new(Heap::empty()));
+
+/// The buddy heap behind a [SpinLock], which keeps the
+/// interrupts off while it is held, since the interrupt
+/// handlers may allocate.
+struct KernelHeap(SpinLock<Heap<23>>);
+
+unsafe impl GlobalAlloc for KernelHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.0
+            .lock()
+            .alloc(layout)
+            .map_or(ptr::null_mut(), NonNull::as_ptr)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.0
+            .lock()
+            .dealloc(unsafe { NonNull::new_unchecked(ptr) }, layout);
+    }
+}
 
 pub(super) fn init() {
     unsafe {
         HEAP_ALLOCATOR
+            .0
             .lock()
             .init((&raw const KERNEL_HEAP).addr(), KERNEL_HEAP_SIZE_BYTES);
     }
@@ -5,52 +5,67 @@ use core::mem::ManuallyDrop;
 use alloc::vec::Vec;
 use lazy_static::lazy_static;
 
-use crate::mm::{
-    MEM_SIZE_BYTES, MEM_START_PA, PAGE_SIZE_BYTES, PPN, get_pa_from_va, get_pa_mut_ptr, kernel_end,
-};
+use crate::machine;
+use crate::mm::{PAGE_SIZE_BYTES, PPN, get_pa_from_va, get_pa_mut_ptr, kernel_end};
 use crate::sync::spin::SpinLock;
 
 lazy_static! {
-    /// Global allocator for physical memory pages. Pages before
-    /// the end of the kernel are treated as persistently allocated
-    /// and will not be recycled.
+    /// Global allocator for physical memory pages, which hands
+    /// out the memory regions of the device tree. Pages before
+    /// the end of the kernel and the reserved regions are treated
+    /// as persistently allocated and will not be recycled.
     ///
     /// # Invariants
     /// * Access to pages that are not yet allocated or have been
     /// recycled should be forbidden, e.g., through virtual memory
     /// control.
     static ref PAGE_ALLOCATOR: SpinLock<PageAllocator> = {
-        let next_unused_ppn = compute_first_unused_ppn();
-        let max_ppn = compute_max_ppn();
-        let recycled_ppn = Vec::new();
-        SpinLock::new(PageAllocator { next_unused_ppn, max_ppn, recycled_ppn })
+        let mut unused_ranges = compute_unused_ranges();
+        unused_ranges.reverse();
+        SpinLock::new(PageAllocator {
+            next_unused_ppn: PPN(0),
+            max_ppn: PPN(0),
+            unused_ranges,
+            recycled_ppn: Vec::new(),
+        })
     };
 }
 
-fn compute_first_unused_ppn() -> PPN {
+/// Returns the [PPN] ranges of the memory regions after the
+/// end of the kernel and outside the reserved regions, in
+/// ascending order.
+fn compute_unused_ranges() -> Vec<(PPN, PPN)> {
     let kernel_end_pa = get_pa_from_va(kernel_end as usize);
 
-    if kernel_end_pa & (PAGE_SIZE_BYTES - 1) == 0 {
-        PPN::from_pa(kernel_end_pa)
-    } else {
-        PPN::from_pa(kernel_end_pa + PAGE_SIZE_BYTES)
+    let mut ranges: Vec<(usize, usize)> = machine::get_memory_regions()
+        .into_iter()
+        .map(|(start, size)| (start.max(kernel_end_pa), start + size))
+        .filter(|&(start, end)| start < end)
+        .collect();
+    for (reserved_start, size) in machine::get_reserved_regions() {
+        let reserved_end = reserved_start + size;
+        ranges = ranges
+            .into_iter()
+            .flat_map(|(start, end)| {
+                [
+                    (start, end.min(reserved_start)),
+                    (start.max(reserved_end), end),
+                ]
+            })
+            .filter(|&(start, end)| start < end)
+            .collect();
     }
-}
-
-/// Returns the exclusive upper bound of valid [PPN].
-fn compute_max_ppn() -> PPN {
-    assert_eq!(
-        MEM_START_PA & (PAGE_SIZE_BYTES - 1),
-        0,
-        "The algorithm assumes MEM_START_PA is page-aligned"
-    );
-    assert_eq!(
-        MEM_SIZE_BYTES & (PAGE_SIZE_BYTES - 1),
-        0,
-        "The algorithm assumes MEM_SIZE_BYTES is page-aligned"
-    );
 
-    PPN::from_pa(MEM_START_PA + MEM_SIZE_BYTES)
+    ranges.sort_unstable();
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let start = start.next_multiple_of(PAGE_SIZE_BYTES);
+            let end = end & !(PAGE_SIZE_BYTES - 1);
+            (PPN::from_pa(start), PPN::from_pa(end))
+        })
+        .filter(|(start, end)| start < end)
+        .collect()
 }
 
 /// Allocates and returns a [Page]. If no page is available,
@@ -65,7 +80,7 @@ pub(super) fn alloc_page() -> Option<Page> {
 /// returns [None].
 ///
 /// The allocated [Page] is zerod.
-pub(super) fn alloc_zeroed_page() -> Option<Page> {
+pub(crate) fn alloc_zeroed_page() -> Option<Page> {
     let result = alloc_page()?;
     let pa = result.get_ppn().get_pa();
     let pa_mut_ptr = get_pa_mut_ptr(pa);
@@ -85,8 +100,11 @@ fn dealloc_pages(pages: Vec<Page>) {
 
 struct PageAllocator {
     next_unused_ppn: PPN,
-    /// The exclusive upper bound of valid [PPN].
+    /// The exclusive upper bound of the range being used.
     max_ppn: PPN,
+    /// The ranges not used yet, in descending order so that
+    /// the next one is popped from the end.
+    unused_ranges: Vec<(PPN, PPN)>,
     recycled_ppn: Vec<PPN>,
 }
 
@@ -96,8 +114,8 @@ impl PageAllocator {
             return Some(Page { ppn });
         }
 
-        if self.next_unused_ppn >= self.max_ppn {
-            return None;
+        while self.next_unused_ppn >= self.max_ppn {
+            (self.next_unused_ppn, self.max_ppn) = self.unused_ranges.pop()?;
         }
 
         let result = Page {
@@ -115,10 +133,17 @@ impl PageAllocator {
 /// Abstraction of physical memory pages. It implements [Drop] to
 /// automate page deallocation.
 ///
+/// A page shared by multiple owners, e.g., a copy-on-write page
+/// shared by forked [VMSpace]s, is wrapped in an [Arc] so that
+/// the page is recycled only when the last sharer drops it.
+///
+/// [VMSpace]: crate::mm::vm::VMSpace
+/// [Arc]: alloc::sync::Arc
+///
 /// # Invariants
 /// * Instance of [Page] should only be created by [PAGE_ALLOCATOR].
 #[derive(Debug)]
-pub(super) struct Page {
+pub(crate) struct Page {
     ppn: PPN,
 }
 
@@ -126,6 +151,22 @@ impl Page {
     pub(super) fn get_ppn(&self) -> PPN {
         self.ppn
     }
+
+    /// Returns the physical address of the page, e.g., for a
+    /// device to access it through DMA.
+    pub(crate) fn get_pa(&self) -> usize {
+        self.ppn.get_pa()
+    }
+
+    /// Returns the content of the page, e.g., for a page
+    /// holding file data.
+    pub(crate) fn as_bytes(&self) -> &[u8; PAGE_SIZE_BYTES] {
+        unsafe { &*(get_pa_mut_ptr(self.get_pa()) as *const [u8; PAGE_SIZE_BYTES]) }
+    }
+
+    pub(crate) fn as_bytes_mut(&mut self) -> &mut [u8; PAGE_SIZE_BYTES] {
+        unsafe { &mut *(get_pa_mut_ptr(self.get_pa()) as *mut [u8; PAGE_SIZE_BYTES]) }
+    }
 }
 
 impl Drop for Page {
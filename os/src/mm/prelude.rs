@@ -2,9 +2,14 @@ pub(crate) use super::PAGE_SIZE_BYTES;
 pub(crate) use super::VPN;
 pub(crate) use super::check_u_va;
 pub(crate) use super::check_u_va_range;
+pub(crate) use super::get_pa_from_va;
+pub(crate) use super::get_va_from_pa;
 pub(crate) use super::init;
+pub(crate) use super::init_heap;
+pub(crate) use super::init_secondary;
 pub(crate) use super::log_kernel_layout;
 
+pub(crate) use super::vm::KernelStack;
 pub(crate) use super::vm::MapType;
 pub(crate) use super::vm::PERMISSION_R;
 pub(crate) use super::vm::PERMISSION_U;
@@ -12,6 +17,10 @@ pub(crate) use super::vm::PERMISSION_W;
 pub(crate) use super::vm::PERMISSION_X;
 pub(crate) use super::vm::VMError;
 pub(crate) use super::vm::VMSpace;
+pub(crate) use super::vm::get_kernel_satp;
+
+pub(crate) use super::page_alloc::Page;
+pub(crate) use super::page_alloc::alloc_zeroed_page;
 
 pub(crate) use super::uaccess::copy_from_user;
 pub(crate) use super::uaccess::copy_to_user;
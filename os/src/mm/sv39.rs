@@ -64,8 +64,10 @@ impl RootPgt {
         Ok(Self { ppn, pages })
     }
 
-    pub(super) fn get_satp(&self) -> usize {
-        satp::compute_value(self.ppn.get_raw(), Mode::Sv39)
+    /// Returns the satp value for this [RootPgt] tagged with
+    /// the `asid`.
+    pub(super) fn get_satp(&self, asid: usize) -> usize {
+        satp::compute_value(self.ppn.get_raw(), asid, Mode::Sv39)
     }
 
     /// Returns a [PTE] array view of the physical page
@@ -249,6 +251,66 @@ impl RootPgt {
 
         panic!("Code should not reach here.")
     }
+
+    /// Returns a mutable reference to the leaf [PTE] that
+    /// maps `vpn`, or [None] if `vpn` is not mapped.
+    ///
+    /// # Panic
+    /// This function expects page level mapping only. It panics
+    /// if a huge page mapping or large page mapping is encountered.
+    fn find_leaf_pte_mut(&mut self, vpn: VPN) -> Option<&mut PTE> {
+        let parsed_vpn = parse_vpn(vpn);
+        // SAFETY:
+        // The RootPgt instance itself must point to a physical
+        // page holding the corresponding page table.
+        let mut table = unsafe { Self::get_ptes_mut(self.ppn) };
+
+        for level in [2, 1] {
+            let pte = table[parsed_vpn[level]];
+            if !pte.is_valid() {
+                return None;
+            }
+            assert!(
+                !pte.is_leaf(),
+                "Unexpected huge or large page: vpn={vpn:?}, level={level}, pte={pte:?}."
+            );
+
+            // SAFETY:
+            // The page table entry is valid and is not a leaf;
+            // therefore, it points to a physical page holding a
+            // page table.
+            table = unsafe { RootPgt::get_ptes_mut(pte.get_ppn()) };
+        }
+
+        let pte = &mut table[parsed_vpn[0]];
+        if pte.is_valid() { Some(pte) } else { None }
+    }
+
+    /// Returns the flags of the leaf [PTE] that maps `vpn`,
+    /// or [None] if `vpn` is not mapped.
+    pub(super) fn get_pte_flags(&mut self, vpn: VPN) -> Option<usize> {
+        self.find_leaf_pte_mut(vpn).map(|pte| pte.get_flags())
+    }
+
+    /// Replaces the flags of the existing mapping of `vpn`
+    /// with `pte_flags`.
+    pub(super) fn update_flags(&mut self, vpn: VPN, pte_flags: usize) -> Result<(), PgtError> {
+        let pte = self
+            .find_leaf_pte_mut(vpn)
+            .ok_or(PgtError::NotMapped(vpn))?;
+        *pte = PTE::new(pte.get_ppn(), pte_flags)?;
+        Ok(())
+    }
+
+    /// Replaces the existing mapping of `vpn` with a mapping
+    /// to `ppn`.
+    pub(super) fn remap(&mut self, vpn: VPN, ppn: PPN, pte_flags: usize) -> Result<(), PgtError> {
+        let pte = self
+            .find_leaf_pte_mut(vpn)
+            .ok_or(PgtError::NotMapped(vpn))?;
+        *pte = PTE::new(ppn, pte_flags)?;
+        Ok(())
+    }
 }
 
 // Page Table Entry
@@ -265,6 +327,10 @@ impl PTE {
     pub(super) const FLAG_W: usize = 1 << 2;
     pub(super) const FLAG_X: usize = 1 << 3;
     pub(super) const FLAG_U: usize = 1 << 4;
+    /// A software-defined flag that marks a read-only mapping
+    /// of a writable page shared on copy-on-write. It occupies
+    /// the first bit of the RSW field.
+    pub(super) const FLAG_COW: usize = 1 << 8;
 
     /// Creates a [PTE] that points to the physical page
     /// containing the `pa`, or returns the corresponding
@@ -312,8 +378,12 @@ impl PTE {
         PPN::from_pa(ppn << PAGE_SIZE_ORDER)
     }
 
+    fn get_flags(&self) -> usize {
+        self.0 & Self::ALL_FLAGS
+    }
+
     fn is_leaf(&self) -> bool {
-        self.is_valid() && self.0 & (Self::FLAG_X | Self::FLAG_W) != 0
+        self.is_valid() && self.0 & (Self::FLAG_X | Self::FLAG_W | Self::FLAG_R) != 0
     }
 }
 
@@ -325,4 +395,8 @@ pub(crate) enum PgtError {
     #[allow(dead_code)]
     /// Attempts to map a [VPN] that is already mapped.
     DoubleMapping(VPN, PPN),
+    #[allow(dead_code)]
+    /// Attempts to modify the mapping of a [VPN] that is not
+    /// mapped.
+    NotMapped(VPN),
 }
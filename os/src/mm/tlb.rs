@@ -0,0 +1,52 @@
+use core::arch::asm;
+
+use crate::mm::{PAGE_SIZE_BYTES, VPN};
+use crate::sbi;
+use crate::{log, warn};
+
+/// Flushing a range of more pages than this flushes the
+/// whole ASID instead.
+const MAX_PAGES_FLUSHED_ONE_BY_ONE: usize = 64;
+
+/// Flushes the translations of the `vpn_range`, or all the
+/// translations if it is [None], in the `asid` on the
+/// current hart `curr_hart_id`. It then shoots down those
+/// on the other harts in the `hart_mask` through the SBI
+/// RFENCE extension.
+pub(super) fn shootdown(
+    curr_hart_id: usize,
+    hart_mask: usize,
+    asid: usize,
+    vpn_range: Option<(VPN, VPN)>,
+) {
+    let vpn_range = vpn_range.filter(|(start_vpn, end_vpn)| {
+        end_vpn.0.saturating_sub(start_vpn.0) <= MAX_PAGES_FLUSHED_ONE_BY_ONE
+    });
+
+    match vpn_range {
+        Some((start_vpn, end_vpn)) => (start_vpn.0..end_vpn.0)
+            .map(|vpn| VPN(vpn).get_va())
+            .for_each(|va| unsafe { asm!("sfence.vma {}, {}", in(reg) va, in(reg) asid) }),
+        None => unsafe { asm!("sfence.vma x0, {}", in(reg) asid) },
+    }
+
+    let remote_mask = hart_mask & !(1 << curr_hart_id);
+    if remote_mask == 0 {
+        return;
+    }
+
+    // A size of usize::MAX flushes the whole ASID.
+    let (start_va, size) = match vpn_range {
+        Some((start_vpn, end_vpn)) => (
+            start_vpn.get_va(),
+            (end_vpn.0 - start_vpn.0) * PAGE_SIZE_BYTES,
+        ),
+        None => (0, usize::MAX),
+    };
+    if !sbi::remote_sfence_vma_asid(remote_mask, start_va, size, asid) {
+        warn!(
+            "Failed to shoot down TLB, hart_mask={:#x}, asid={}",
+            remote_mask, asid
+        );
+    }
+}
@@ -1,20 +1,27 @@
 extern crate alloc;
 
 use alloc::collections::btree_map::BTreeMap;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::arch::asm;
+use core::ptr;
 use core::sync::atomic::{AtomicUsize, Ordering};
 
 use xmas_elf::{ElfFile, program};
 
+use crate::machine;
+use crate::mm::asid::{self, Asid, KERNEL_ASID};
 use crate::mm::page_alloc::{Page, alloc_page, alloc_zeroed_page};
 use crate::mm::sv39::{PTE, PgtError, RootPgt};
+use crate::mm::tlb;
 use crate::mm::{
-    KERNEL_VA_OFFSET, LARGE_PAGE_SIZE_BYTES, MEM_SIZE_BYTES, MEM_START_PA, PAGE_SIZE_BYTES,
-    PAGE_SIZE_ORDER, PPN, QEMU_VIRT_MMIO, USER_SPACE_END, USER_STACK_MAX_SIZE_BYTES, VPN, bss_end,
-    bss_start, data_end, data_start, get_pa_from_va, get_pa_mut_ptr, get_va_from_pa, kernel_end,
-    kernel_stack_end, kernel_stack_start, rodata_end, rodata_start, text_end, text_start,
+    KERNEL_VA_OFFSET, LARGE_PAGE_SIZE_BYTES, PAGE_SIZE_BYTES, PAGE_SIZE_ORDER, PPN, USER_SPACE_END,
+    USER_STACK_MAX_SIZE_BYTES, VPN, bss_end, bss_start, data_end, data_start, get_pa_from_va,
+    get_pa_mut_ptr, get_va_from_pa, kernel_end, kernel_stack_end, kernel_stack_start, rodata_end,
+    rodata_start, text_end, text_start,
 };
+use crate::task::prelude::get_current_hart_id;
+use crate::timer::get_timebase_frequency;
 
 const ALL_PERMISSION_FLAGS: usize = PERMISSION_R | PERMISSION_W | PERMISSION_X | PERMISSION_U;
 pub(crate) const PERMISSION_R: usize = PTE::FLAG_R;
@@ -29,9 +36,30 @@ fn to_pte_flags(permissions: usize) -> Result<usize, VMError> {
     Ok((permissions & ALL_PERMISSION_FLAGS) | PTE::FLAG_V)
 }
 
+// Keys of the auxiliary vector, see [here].
+//
+// [here]: https://github.com/torvalds/linux/blob/master/include/uapi/linux/auxvec.h
+const AT_NULL: usize = 0;
+const AT_PHDR: usize = 3;
+const AT_PHENT: usize = 4;
+const AT_PHNUM: usize = 5;
+const AT_PAGESZ: usize = 6;
+const AT_ENTRY: usize = 9;
+const AT_RANDOM: usize = 25;
+/// The frequency of the time counter in Hz, which is a key
+/// specific to this kernel.
+const AT_TIMEBASE_FREQ: usize = 0x1000;
+
+/// The alignment of the initial user sp required by the
+/// RISC-V calling convention.
+const USER_SP_ALIGN: usize = 16;
+/// The maximum number of user stacks in a [VMSpace], i.e.,
+/// of the threads sharing it.
+const MAX_USER_STACKS: usize = 64;
+
 static KERNEL_SATP: AtomicUsize = AtomicUsize::new(0);
 
-fn get_kernel_satp() -> usize {
+pub(crate) fn get_kernel_satp() -> usize {
     KERNEL_SATP.load(Ordering::Acquire)
 }
 
@@ -56,7 +84,7 @@ pub(super) fn init_kernel_satp() -> Result<(), VMError> {
     map_kernel_bss(&mut root_pgt)?;
     map_phys_mem(&mut root_pgt)?;
 
-    let satp = root_pgt.get_satp();
+    let satp = root_pgt.get_satp(KERNEL_ASID);
     KERNEL_SATP.store(satp, Ordering::Release);
 
     // We don't keep an instance of RootPgt for kernel space.
@@ -85,10 +113,16 @@ fn map_kernel_range(
         })
 }
 
+/// Maps the registers of the devices found in the device
+/// tree, each rounded out to whole pages.
 fn map_virt_mmio(root_pgt: &mut RootPgt) -> Result<(), VMError> {
-    QEMU_VIRT_MMIO
-        .iter()
-        .map(|&(start_pa, size)| (get_va_from_pa(start_pa), get_va_from_pa(start_pa + size)))
+    machine::get_mmio_regions()
+        .into_iter()
+        .map(|(start_pa, size)| {
+            let start_va = get_va_from_pa(start_pa & !(PAGE_SIZE_BYTES - 1));
+            let end_va = get_va_from_pa((start_pa + size).next_multiple_of(PAGE_SIZE_BYTES));
+            (start_va, end_va)
+        })
         .try_for_each(|(start_va, end_va)| {
             map_kernel_range(root_pgt, start_va, end_va, PERMISSION_R | PERMISSION_W)
         })
@@ -139,10 +173,26 @@ fn map_kernel_bss(root_pgt: &mut RootPgt) -> Result<(), VMError> {
     )
 }
 
+/// Maps the memory regions found in the device tree, except
+/// the part up to the end of the kernel image.
 fn map_phys_mem(root_pgt: &mut RootPgt) -> Result<(), VMError> {
+    machine::get_memory_regions()
+        .into_iter()
+        .map(|(start_pa, size)| (get_va_from_pa(start_pa), get_va_from_pa(start_pa + size)))
+        .filter(|&(_, end_va)| end_va > kernel_end as usize)
+        .try_for_each(|(start_va, end_va)| {
+            let start_va = compute_phy_mem_page_start(start_va.max(kernel_end as usize));
+            let end_va = end_va & !(PAGE_SIZE_BYTES - 1);
+            map_phys_mem_range(root_pgt, start_va, end_va)
+        })
+}
+
+fn map_phys_mem_range(
+    root_pgt: &mut RootPgt,
+    start_va: usize,
+    end_va: usize,
+) -> Result<(), VMError> {
     let permissions = PERMISSION_R | PERMISSION_W;
-    let start_va = compute_phy_mem_page_start(kernel_end as usize);
-    let end_va = get_va_from_pa(MEM_START_PA + MEM_SIZE_BYTES);
     let large_start_va = compute_phy_mem_large_page_start(start_va);
     let large_end_va = compute_phy_mem_large_page_end(end_va);
 
@@ -219,35 +269,105 @@ pub(crate) struct VMSpace {
     root_pgt: RootPgt,
     areas: Vec<VMArea>,
     entry_addr: usize,
-    /// The end of the task's user stack; i.e., the sp
+    /// The address of the program headers of the user ELF,
+    /// or zero if they are not loaded.
+    phdr_addr: usize,
+    phdr_count: usize,
+    phdr_entry_size: usize,
+    /// The end of the main thread's user stack; i.e., the sp
     /// value when the stack is empty.
     u_stack_end: usize,
-    /// The end of the task's kernel stack, i.e., the sp
-    /// value when the stack is empty.
-    k_stack_end: usize,
+    /// The ASID tagging the translations of this [VMSpace],
+    /// which is reallocated once it gets stale.
+    asid: Asid,
+    /// The previous value of the [asid], which the threads
+    /// still running since before its reallocation may use.
+    ///
+    /// [asid]: VMSpace::asid
+    prev_asid: Option<usize>,
+    /// The bit mask of the harts that have run this [VMSpace]
+    /// and thus may cache its translations.
+    hart_mask: usize,
 }
 
 impl VMSpace {
     /// Returns a new user [VMSpace] with the `elf_bytes`
     /// mapped. Additionally, it inherits entries from the
-    /// kernel's [RootPgt], and maps a user stack and a kernel
-    /// stack.
+    /// kernel's [RootPgt], and maps a user stack for the main
+    /// thread.
     pub(crate) fn new_user(elf_bytes: &[u8]) -> Result<Self, VMError> {
+        let mut result = Self::new_empty()?;
+        result.map_user_elf(elf_bytes)?;
+        result.add_user_stack_area()?;
+        Ok(result)
+    }
+
+    /// Returns a [VMSpace] that has no [VMArea] but inherits
+    /// entries from the kernel's [RootPgt].
+    fn new_empty() -> Result<Self, VMError> {
         let entries = unsafe { RootPgt::get_ptes_mut(get_kernel_satp_ppn()) };
         let root_pgt =
             unsafe { RootPgt::new_copy(entries) }.map_err(VMError::CreateRootPgtFailed)?;
         let areas = Vec::new();
 
-        let mut result = Self {
+        Ok(Self {
             root_pgt,
             areas,
             entry_addr: 0,
+            phdr_addr: 0,
+            phdr_count: 0,
+            phdr_entry_size: 0,
             u_stack_end: 0,
-            k_stack_end: 0,
-        };
-        result.map_user_elf(elf_bytes)?;
-        result.add_user_stack_area()?;
-        result.add_kernel_stack_area()?;
+            asid: asid::alloc_asid(),
+            prev_asid: None,
+            hart_mask: 0,
+        })
+    }
+
+    /// Returns a copy of the `parent` [VMSpace] for a forked
+    /// task. The pages of the [MapType::Anonymous] areas are
+    /// shared rather than copied; the writable ones are mapped
+    /// read-only in both [VMSpace]s and are copied on the first
+    /// write, including the stacks of the other threads.
+    pub(crate) fn new_forked(parent: &mut VMSpace) -> Result<Self, VMError> {
+        let mut result = Self::new_empty()?;
+        result.entry_addr = parent.entry_addr;
+        result.phdr_addr = parent.phdr_addr;
+        result.phdr_count = parent.phdr_count;
+        result.phdr_entry_size = parent.phdr_entry_size;
+        result.u_stack_end = parent.u_stack_end;
+
+        for area in parent.areas.iter() {
+            let mut pte_flags = to_pte_flags(area.permissions)?;
+            let is_writable = area.permissions & PERMISSION_W != 0;
+            if is_writable {
+                pte_flags = (pte_flags & !PTE::FLAG_W) | PTE::FLAG_COW;
+            }
+
+            let mut new_area = VMArea::new(
+                area.start_vpn,
+                area.end_vpn,
+                area.map_type,
+                area.permissions,
+            );
+            for (&vpn, page) in area.pages.iter() {
+                if is_writable {
+                    parent
+                        .root_pgt
+                        .update_flags(vpn, pte_flags)
+                        .map_err(|pgt_err| VMError::PgtError(vpn, pgt_err))?;
+                }
+                result
+                    .root_pgt
+                    .map_create(vpn, page.get_ppn(), pte_flags)
+                    .map_err(|pgt_err| VMError::PgtError(vpn, pgt_err))?;
+                new_area.pages.insert(vpn, page.clone());
+            }
+            result.areas.push(new_area);
+        }
+
+        // The parent may have cached the writable mappings.
+        parent.flush_tlb(None);
         Ok(result)
     }
 
@@ -265,9 +385,33 @@ impl VMSpace {
         }
 
         self.entry_addr = elf.header.pt2.entry_point() as usize;
+        self.phdr_addr = Self::find_phdr_addr(&elf)?;
+        self.phdr_count = elf.header.pt2.ph_count() as usize;
+        self.phdr_entry_size = elf.header.pt2.ph_entry_size() as usize;
         Ok(())
     }
 
+    /// Returns the virtual address of the program headers,
+    /// or zero if no loaded segment contains them.
+    fn find_phdr_addr(elf: &ElfFile) -> Result<usize, VMError> {
+        let ph_offset = elf.header.pt2.ph_offset();
+
+        for ph in elf.program_iter() {
+            let ph_type = ph.get_type().map_err(|msg| VMError::ElfError(msg))?;
+            if ph_type == program::Type::Phdr {
+                return Ok(ph.virtual_addr() as usize);
+            }
+            if ph_type == program::Type::Load
+                && ph.offset() <= ph_offset
+                && ph_offset < ph.offset() + ph.file_size()
+            {
+                return Ok((ph.virtual_addr() + ph_offset - ph.offset()) as usize);
+            }
+        }
+
+        Ok(0)
+    }
+
     fn map_user_elf_segment(
         self: &mut VMSpace,
         elf_bytes: &[u8],
@@ -301,7 +445,7 @@ impl VMSpace {
             self.root_pgt
                 .map_create(vpn, ppn, pte_flags)
                 .map_err(|pgt_err| VMError::PgtError(vpn, pgt_err))?;
-            area.pages.insert(vpn, page);
+            area.pages.insert(vpn, Arc::new(page));
         }
 
         self.areas.push(area);
@@ -377,32 +521,184 @@ impl VMSpace {
         let end_vpn = VPN::from_va(USER_SPACE_END);
         let start_vpn = VPN::from_va(USER_SPACE_END - USER_STACK_MAX_SIZE_BYTES);
         let mut area = VMArea::new(start_vpn, end_vpn, MapType::Anonymous, permissions);
-        area.pages.insert(vpn, page);
+        area.pages.insert(vpn, Arc::new(page));
         self.areas.push(area);
 
         self.u_stack_end = USER_SPACE_END;
         Ok(())
     }
 
-    /// Assigns a page in the kernel memory range to be the
-    /// kernel stack for this user space.
-    fn add_kernel_stack_area(&mut self) -> Result<(), VMError> {
-        let page = alloc_page().ok_or(VMError::AcquirePageFailed)?;
-        let ppn = page.get_ppn();
-        let pa = ppn.get_pa();
-        let start_vpn = VPN::from_va(pa + KERNEL_VA_OFFSET);
-        let end_vpn = VPN::from_va(pa + KERNEL_VA_OFFSET + PAGE_SIZE_BYTES);
-        let permissions = PERMISSION_R | PERMISSION_W;
-        let mut area = VMArea::new(start_vpn, end_vpn, MapType::KernelVaOffset, permissions);
-        area.pages.insert(start_vpn, page);
-        self.areas.push(area);
+    /// Adds an area of [USER_STACK_MAX_SIZE_BYTES] for the
+    /// user stack of another thread, below the stacks of the
+    /// main thread and the other threads with a guard page
+    /// between them. Lazily maps the pages. Returns the end
+    /// of the stack.
+    pub(crate) fn add_thread_stack_area(&mut self) -> Result<usize, VMError> {
+        let permissions = PERMISSION_R | PERMISSION_W | PERMISSION_U;
+        let stride = USER_STACK_MAX_SIZE_BYTES + PAGE_SIZE_BYTES;
+
+        // The slot 0 is the main thread's stack.
+        for slot in 1..MAX_USER_STACKS {
+            let end = USER_SPACE_END - slot * stride;
+            let start_vpn = VPN::from_va(end - USER_STACK_MAX_SIZE_BYTES);
+            let end_vpn = VPN::from_va(end);
+            match self.add_new_area(start_vpn, end_vpn, MapType::Anonymous, permissions) {
+                Ok(()) => return Ok(end),
+                Err(VMError::AreaOverlapping(..)) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Err(VMError::NoFreeStackArea)
+    }
+
+    /// Removes the user stack area of a thread that ends at
+    /// `u_stack_end`, which is added by [add_thread_stack_area].
+    ///
+    /// [add_thread_stack_area]: VMSpace::add_thread_stack_area
+    pub(crate) fn remove_thread_stack_area(&mut self, u_stack_end: usize) -> Result<(), VMError> {
+        let start_vpn = VPN::from_va(u_stack_end - USER_STACK_MAX_SIZE_BYTES);
+        self.unmap(start_vpn, VPN::from_va(u_stack_end))
+    }
+
+    /// Lays out the `args`, the `envs` and an auxiliary vector
+    /// at the top of the user stack in the Linux style, and
+    /// returns (initial sp, argv address, envp address).
+    ///
+    /// From the initial sp upwards, the stack holds argc, the
+    /// argv pointers, a null, the envp pointers, a null, and
+    /// the auxiliary vector ending with [AT_NULL]. The strings
+    /// and the `random_bytes` for [AT_RANDOM] are above them.
+    pub(crate) fn push_initial_stack(
+        &mut self,
+        args: &[&str],
+        envs: &[&str],
+        random_bytes: &[u8; 16],
+    ) -> Result<(usize, usize, usize), VMError> {
+        let mut sp = self.u_stack_end;
+
+        sp -= random_bytes.len();
+        let random_addr = sp;
+        self.write_user_bytes(random_addr, random_bytes)?;
+
+        let mut arg_addrs = Vec::with_capacity(args.len());
+        for arg in args {
+            sp = self.push_user_str(sp, arg)?;
+            arg_addrs.push(sp);
+        }
+
+        let mut env_addrs = Vec::with_capacity(envs.len());
+        for env in envs {
+            sp = self.push_user_str(sp, env)?;
+            env_addrs.push(sp);
+        }
+
+        let auxv = [
+            (AT_PHDR, self.phdr_addr),
+            (AT_PHENT, self.phdr_entry_size),
+            (AT_PHNUM, self.phdr_count),
+            (AT_PAGESZ, PAGE_SIZE_BYTES),
+            (AT_ENTRY, self.entry_addr),
+            (AT_RANDOM, random_addr),
+            (AT_TIMEBASE_FREQ, get_timebase_frequency()),
+            (AT_NULL, 0),
+        ];
+
+        let mut words = Vec::new();
+        words.push(args.len());
+        words.extend_from_slice(&arg_addrs);
+        words.push(0);
+        words.extend_from_slice(&env_addrs);
+        words.push(0);
+        auxv.iter().for_each(|&(key, value)| {
+            words.push(key);
+            words.push(value);
+        });
+
+        sp = (sp - words.len() * size_of::<usize>()) & !(USER_SP_ALIGN - 1);
+        for (i, word) in words.iter().enumerate() {
+            self.write_user_bytes(sp + i * size_of::<usize>(), &word.to_ne_bytes())?;
+        }
+
+        let argv_addr = sp + size_of::<usize>();
+        let envp_addr = argv_addr + (args.len() + 1) * size_of::<usize>();
+        Ok((sp, argv_addr, envp_addr))
+    }
+
+    /// Pushes the null-terminated `s` onto the user stack
+    /// whose top is `sp`, and returns the new top.
+    fn push_user_str(&mut self, sp: usize, s: &str) -> Result<usize, VMError> {
+        let sp = sp - s.len() - 1;
+        self.write_user_bytes(sp, s.as_bytes())?;
+        self.write_user_bytes(sp + s.len(), &[0])?;
+        Ok(sp)
+    }
+
+    /// Writes the `bytes` to the user memory starting at `va`
+    /// through the backing pages, mapping the missing pages
+    /// as needed.
+    fn write_user_bytes(&mut self, va: usize, bytes: &[u8]) -> Result<(), VMError> {
+        let mut offset = 0;
+
+        while offset < bytes.len() {
+            let curr_va = va + offset;
+            let vpn = VPN::from_va(curr_va);
+            if !self.find_area_mut(vpn)?.pages.contains_key(&vpn) {
+                self.map(vpn, PERMISSION_U | PERMISSION_W)?;
+            }
+
+            let page = &self.find_area_mut(vpn)?.pages[&vpn];
+            let page_offset = curr_va & (PAGE_SIZE_BYTES - 1);
+            let len = (PAGE_SIZE_BYTES - page_offset).min(bytes.len() - offset);
+            unsafe {
+                Self::copy_data(
+                    page.get_ppn().get_pa() + page_offset,
+                    &bytes[offset..offset + len],
+                )
+            };
+            offset += len;
+        }
 
-        self.k_stack_end = end_vpn.get_va();
         Ok(())
     }
 
     pub(crate) fn get_satp(&self) -> usize {
-        self.root_pgt.get_satp()
+        self.root_pgt.get_satp(self.asid.get_value())
+    }
+
+    /// Prepares this [VMSpace] to run on the current hart
+    /// `hart_id` and returns the satp value to switch to.
+    ///
+    /// A stale ASID is reallocated, and the hart flushes its
+    /// TLB if the ASIDs have rolled over since its last flush.
+    pub(crate) fn prepare_switch(&mut self, hart_id: usize) -> usize {
+        if !asid::is_current(self.asid) {
+            self.prev_asid = Some(self.asid.get_value());
+            self.asid = asid::alloc_asid();
+        }
+        asid::sync_hart(hart_id);
+
+        self.hart_mask |= 1 << hart_id;
+        self.get_satp()
+    }
+
+    /// Switches the current hart `hart_id` to this [VMSpace].
+    pub(crate) fn activate(&mut self, hart_id: usize) {
+        let satp = self.prepare_switch(hart_id);
+        unsafe { asm!("csrw satp, {}", in(reg) satp) }
+    }
+
+    /// Flushes the translations of the `vpn_range`, or all the
+    /// translations if it is [None], from the TLBs of the
+    /// harts that may have cached them.
+    ///
+    /// It should be called by a task running this [VMSpace]
+    /// after a mapping is removed or loses permissions.
+    fn flush_tlb(&self, vpn_range: Option<(VPN, VPN)>) {
+        let hart_id = get_current_hart_id();
+        tlb::shootdown(hart_id, self.hart_mask, self.asid.get_value(), vpn_range);
+        if let Some(prev_asid) = self.prev_asid {
+            tlb::shootdown(hart_id, self.hart_mask, prev_asid, vpn_range);
+        }
     }
 
     pub(crate) fn get_entry_addr(&self) -> usize {
@@ -413,10 +709,6 @@ impl VMSpace {
         self.u_stack_end
     }
 
-    pub(crate) fn get_k_stack_end(&self) -> usize {
-        self.k_stack_end
-    }
-
     /// Maps the `vpn`, requesting at least the `min_permissions`.
     fn map(&mut self, vpn: VPN, min_permissions: usize) -> Result<(), VMError> {
         let area = self.find_area_mut(vpn)?;
@@ -426,14 +718,9 @@ impl VMSpace {
             return Err(VMError::PermissionDenied(vpn, min_permissions));
         }
 
-        let map_type = area.map_type;
-        if !matches!(map_type, MapType::Anonymous) {
-            panic!("Unexpected MapType {:?}.", map_type)
-        }
-
         let page = alloc_zeroed_page().ok_or(VMError::AcquirePageFailed)?;
         let ppn = page.get_ppn();
-        area.pages.insert(vpn, page);
+        area.pages.insert(vpn, Arc::new(page));
 
         let pte_flags = to_pte_flags(area.permissions)?;
         let result = self
@@ -458,15 +745,92 @@ impl VMSpace {
             .ok_or(VMError::NoAreaContainVpn(vpn))
     }
 
+    /// Resolves the write to the copy-on-write `vpn`. The page
+    /// is copied unless this [VMSpace] is its last sharer, and
+    /// the mapping regains the permissions of its [VMArea].
+    fn copy_on_write(&mut self, vpn: VPN, min_permissions: usize) -> Result<(), VMError> {
+        let area = self
+            .areas
+            .iter_mut()
+            .find(|area| area.contain_vpn(vpn))
+            .ok_or(VMError::NoAreaContainVpn(vpn))?;
+
+        let permissions = area.permissions;
+        if min_permissions & permissions != min_permissions {
+            return Err(VMError::PermissionDenied(vpn, min_permissions));
+        }
+
+        let is_cow = self
+            .root_pgt
+            .get_pte_flags(vpn)
+            .is_some_and(|flags| flags & PTE::FLAG_COW != 0);
+        if !is_cow {
+            return Err(VMError::PermissionDenied(vpn, min_permissions));
+        }
+
+        let pte_flags = to_pte_flags(permissions)?;
+        let page = area.pages.get_mut(&vpn).unwrap();
+
+        if Arc::strong_count(page) == 1 {
+            self.root_pgt
+                .update_flags(vpn, pte_flags)
+                .map_err(|pgt_err| VMError::PgtError(vpn, pgt_err))?;
+        } else {
+            let new_page = alloc_page().ok_or(VMError::AcquirePageFailed)?;
+            let new_ppn = new_page.get_ppn();
+            unsafe {
+                ptr::copy_nonoverlapping(
+                    get_pa_mut_ptr(page.get_ppn().get_pa()),
+                    get_pa_mut_ptr(new_ppn.get_pa()),
+                    PAGE_SIZE_BYTES,
+                )
+            };
+
+            self.root_pgt
+                .remap(vpn, new_ppn, pte_flags)
+                .map_err(|pgt_err| VMError::PgtError(vpn, pgt_err))?;
+            *page = Arc::new(new_page);
+        }
+
+        self.flush_tlb(Some((vpn, VPN(vpn.0 + 1))));
+        Ok(())
+    }
+
     /// Tries to map the `va` with the `min_permissions` into
     /// this [VMSpace]. The actual permissions follow the [VMArea]
     /// containing the `va`.
+    ///
+    /// If the `va` is already mapped, the fault is treated as
+    /// a write to a copy-on-write page.
     pub(crate) fn map_fault_page(
         &mut self,
         va: usize,
         min_permissions: usize,
     ) -> Result<(), VMError> {
-        self.map(VPN::from_va(va), min_permissions)
+        let vpn = VPN::from_va(va);
+        if self.find_area_mut(vpn)?.pages.contains_key(&vpn) {
+            self.copy_on_write(vpn, min_permissions)
+        } else {
+            self.map(vpn, min_permissions)
+        }
+    }
+
+    /// Returns the physical address behind the user `va` as
+    /// if it were written, i.e., mapping its page or copying
+    /// it on write first. The address then stays the same
+    /// until the page is unmapped.
+    pub(crate) fn get_writable_pa(&mut self, va: usize) -> Result<usize, VMError> {
+        let vpn = VPN::from_va(va);
+        let is_writable = self
+            .root_pgt
+            .get_pte_flags(vpn)
+            .is_some_and(|flags| flags & PTE::FLAG_W != 0);
+        if !is_writable {
+            self.map_fault_page(va, PERMISSION_U | PERMISSION_W)?;
+        }
+
+        let page = &self.find_area_mut(vpn)?.pages[&vpn];
+        Ok(page.get_ppn().get_pa() + (va & (PAGE_SIZE_BYTES - 1)))
     }
 
     /// Adds a new [VMArea] according to the given properties,
@@ -500,8 +864,15 @@ impl VMSpace {
     }
 
     /// Unmaps any existing mappings within range [start_vpn]
-    /// to [end_vpn] (exclusive).
+    /// to [end_vpn] (exclusive), and flushes them from the
+    /// TLBs even if it fails halfway.
     pub(crate) fn unmap(&mut self, start_vpn: VPN, end_vpn: VPN) -> Result<(), VMError> {
+        let result = self.unmap_areas(start_vpn, end_vpn);
+        self.flush_tlb(Some((start_vpn, end_vpn)));
+        result
+    }
+
+    fn unmap_areas(&mut self, start_vpn: VPN, end_vpn: VPN) -> Result<(), VMError> {
         for i in (0..self.areas.len()).rev() {
             let area = &mut self.areas[i];
 
@@ -568,6 +939,26 @@ impl VMSpace {
     }
 }
 
+/// The kernel stack of a thread, which is a page in the
+/// kernel memory range and thus mapped in every [VMSpace].
+#[derive(Debug)]
+pub(crate) struct KernelStack {
+    page: Page,
+}
+
+impl KernelStack {
+    pub(crate) fn new() -> Result<Self, VMError> {
+        let page = alloc_page().ok_or(VMError::AcquirePageFailed)?;
+        Ok(Self { page })
+    }
+
+    /// Returns the end of the stack, i.e., the sp value when
+    /// the stack is empty.
+    pub(crate) fn get_end(&self) -> usize {
+        self.page.get_ppn().get_pa() + KERNEL_VA_OFFSET + PAGE_SIZE_BYTES
+    }
+}
+
 /// An abstraction over a range of virtual memory.
 #[derive(Debug)]
 struct VMArea {
@@ -576,7 +967,7 @@ struct VMArea {
     end_vpn: VPN,
     map_type: MapType,
     permissions: usize,
-    pages: BTreeMap<VPN, Page>,
+    pages: BTreeMap<VPN, Arc<Page>>,
 }
 
 impl VMArea {
@@ -599,9 +990,6 @@ impl VMArea {
 pub(crate) enum MapType {
     /// Maps the [VPN] to a newly allocated [PPN].
     Anonymous,
-    /// Maps the [VPN] to the [PPN] located [KERNEL_VA_OFFSET]
-    /// below it.
-    KernelVaOffset,
 }
 
 #[allow(dead_code)]
@@ -625,4 +1013,5 @@ pub(crate) enum VMError {
     AreaOverlapping(VPN, VPN),
     /// (start_vpn, end_vpn).
     EmptyArea(VPN, VPN),
+    NoFreeStackArea,
 }
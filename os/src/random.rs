@@ -0,0 +1,57 @@
+use crate::sync::spin::SpinLock;
+use crate::{info, log};
+
+/// The state of the SplitMix64 generator, see
+/// https://prng.di.unimi.it/splitmix64.c.
+static STATE: SpinLock<u64> = SpinLock::new(0);
+
+/// Seeds the generator with the `SEED` set at build time
+/// in the deterministic mode, or with the time counter
+/// otherwise.
+pub fn init() {
+    let seed = get_seed();
+    *STATE.lock() = seed;
+    info!("Random seed: {}", seed);
+}
+
+#[cfg(feature = "deterministic")]
+fn get_seed() -> u64 {
+    const DEFAULT_SEED: u64 = 0;
+
+    let Some(env_setting) = option_env!("SEED") else {
+        return DEFAULT_SEED;
+    };
+    env_setting.parse().unwrap_or_else(|_| {
+        crate::println!(
+            "Invalid seed '{}', default to {}.",
+            env_setting,
+            DEFAULT_SEED
+        );
+        DEFAULT_SEED
+    })
+}
+
+#[cfg(not(feature = "deterministic"))]
+fn get_seed() -> u64 {
+    crate::timer::read_time() as u64
+}
+
+/// Returns the next pseudo-random number, which is not
+/// suitable for cryptographic use.
+pub(crate) fn next_u64() -> u64 {
+    let mut state = STATE.lock();
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Fills the `dst` with pseudo-random bytes.
+pub(crate) fn fill_bytes(dst: &mut [u8]) {
+    for chunk in dst.chunks_mut(size_of::<u64>()) {
+        let bytes = next_u64().to_ne_bytes();
+        chunk.copy_from_slice(&bytes[..chunk.len()]);
+    }
+}
@@ -3,6 +3,14 @@ pub fn console_putchar(c: usize) {
     sbi_rt::legacy::console_putchar(c);
 }
 
+/// Returns the next byte from the console, or [None] if
+/// nothing has arrived.
+pub fn console_getchar() -> Option<u8> {
+    #[allow(deprecated)]
+    let c = sbi_rt::legacy::console_getchar();
+    u8::try_from(c).ok()
+}
+
 pub fn shutdown(is_failure: bool) -> ! {
     use sbi_rt::{NoReason, Shutdown, SystemFailure, system_reset};
     if is_failure {
@@ -13,6 +21,33 @@ pub fn shutdown(is_failure: bool) -> ! {
     unreachable!()
 }
 
+/// Starts the hart `hart_id` at the physical address
+/// `start_pa` with satp disabled, passing the hart ID in a0
+/// and the `opaque` in a1. Returns whether it succeeded.
+pub(super) fn start_hart(hart_id: usize, start_pa: usize, opaque: usize) -> bool {
+    sbi_rt::hart_start(hart_id, start_pa, opaque).error == 0
+}
+
+/// Returns whether the hart `hart_id` exists, whatever its
+/// state is.
+pub(super) fn has_hart(hart_id: usize) -> bool {
+    sbi_rt::hart_get_status(hart_id).error == 0
+}
+
+/// Flushes the translations of [start_va, start_va+size)
+/// in the `asid` on the harts in the `hart_mask`. A `size`
+/// of usize::MAX flushes the whole `asid`. Returns whether
+/// it succeeded.
+pub(super) fn remote_sfence_vma_asid(
+    hart_mask: usize,
+    start_va: usize,
+    size: usize,
+    asid: usize,
+) -> bool {
+    let hart_mask = sbi_rt::HartMask::from_mask_base(hart_mask, 0);
+    sbi_rt::remote_sfence_vma_asid(hart_mask, start_va, size, asid).error == 0
+}
+
 pub(super) fn set_mtimecmp(value: usize) {
     sbi_rt::set_timer(value as _);
 }
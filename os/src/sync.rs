@@ -1 +1,3 @@
+pub(crate) mod mutex;
 pub(crate) mod spin;
+pub(crate) mod wait_queue;
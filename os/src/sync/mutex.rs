@@ -0,0 +1,75 @@
+use core::{
+    cell::UnsafeCell,
+    ops::{Deref, DerefMut},
+};
+
+use crate::sync::spin::SpinLock;
+use crate::sync::wait_queue::WaitQueue;
+
+/// A lock that blocks the tasks waiting for it instead of
+/// spinning, so it can be held across blocking operations
+/// such as disk I/O.
+///
+/// It is locked by tasks, except at boot when no other
+/// hart runs and so it never waits.
+pub(crate) struct Mutex<T> {
+    is_locked: SpinLock<bool>,
+    waiters: WaitQueue,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T> Sync for Mutex<T> where T: Send {}
+
+impl<T> Mutex<T> {
+    pub(crate) const fn new(data: T) -> Self {
+        Self {
+            is_locked: SpinLock::new(false),
+            waiters: WaitQueue::new(),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+        loop {
+            let mut is_locked = self.is_locked.lock();
+            if !*is_locked {
+                *is_locked = true;
+                return MutexGuard { lock: self };
+            }
+            drop(is_locked);
+
+            self.waiters.wait_if(|| *self.is_locked.lock());
+        }
+    }
+
+    fn unlock(&self) {
+        *self.is_locked.lock() = false;
+        self.waiters.wake_all();
+    }
+}
+
+pub(crate) struct MutexGuard<'a, T> {
+    lock: &'a Mutex<T>,
+}
+
+unsafe impl<T> Sync for MutexGuard<'_, T> where T: Sync {}
+
+impl<T> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.unlock();
+    }
+}
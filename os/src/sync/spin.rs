@@ -7,6 +7,11 @@ use core::{
     sync::atomic::{AtomicBool, Ordering},
 };
 
+use riscv::regs::sstatus;
+
+/// A lock that spins until it is free. The interrupts of
+/// the hart are off while it is held, so that a handler
+/// cannot spin on a lock held by the code it interrupts.
 pub struct SpinLock<T> {
     locked: AtomicBool,
     data: UnsafeCell<T>,
@@ -23,6 +28,7 @@ impl<T> SpinLock<T> {
     }
 
     pub fn lock(&self) -> SpinGuard<'_, T> {
+        let is_sie_set = sstatus::clear_sie() & sstatus::SIE_BIT != 0;
         while self
             .locked
             .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
@@ -30,7 +36,10 @@ impl<T> SpinLock<T> {
         {
             hint::spin_loop();
         }
-        SpinGuard { lock: self }
+        SpinGuard {
+            lock: self,
+            is_sie_set,
+        }
     }
 
     fn unlock(&self) {
@@ -38,8 +47,19 @@ impl<T> SpinLock<T> {
     }
 }
 
+impl<T> fmt::Debug for SpinLock<T> {
+    /// Leaves out the data, which cannot be read without
+    /// taking the lock.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SpinLock").finish_non_exhaustive()
+    }
+}
+
 pub struct SpinGuard<'a, T> {
     lock: &'a SpinLock<T>,
+    /// Whether the interrupts were on before locking, which
+    /// are turned back on once unlocked.
+    is_sie_set: bool,
 }
 
 unsafe impl<T> Sync for SpinGuard<'_, T> where T: Sync {}
@@ -61,6 +81,9 @@ impl<T> DerefMut for SpinGuard<'_, T> {
 impl<T> Drop for SpinGuard<'_, T> {
     fn drop(&mut self) {
         self.lock.unlock();
+        if self.is_sie_set {
+            sstatus::set_sie();
+        }
     }
 }
 
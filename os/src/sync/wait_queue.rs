@@ -0,0 +1,81 @@
+extern crate alloc;
+
+use alloc::collections::VecDeque;
+use core::mem;
+
+use crate::sync::spin::SpinLock;
+use crate::task::prelude::{
+    block_current_task, get_current_task_id, run_next_task, wake_blocked_task,
+};
+
+/// A queue of the tasks blocked until an event, such as the
+/// arrival of input. It should be locked before the task
+/// registry.
+#[derive(Debug)]
+pub(crate) struct WaitQueue {
+    task_ids: SpinLock<VecDeque<usize>>,
+}
+
+impl WaitQueue {
+    pub(crate) const fn new() -> Self {
+        Self {
+            task_ids: SpinLock::new(VecDeque::new()),
+        }
+    }
+
+    /// Blocks the current task on this queue if `should_wait`
+    /// returns true, and returns once the task is woken,
+    /// possibly by a signal. Returns whether the task has
+    /// waited.
+    ///
+    /// The `should_wait` runs with the queue locked, so an
+    /// event followed by [wake_all] cannot slip in between it
+    /// and the blocking. The caller should check again after
+    /// being woken, since another task may have consumed the
+    /// event.
+    ///
+    /// This function panics if the thread is not running
+    /// a task.
+    ///
+    /// [wake_all]: WaitQueue::wake_all
+    pub(crate) fn wait_if(&self, should_wait: impl FnOnce() -> bool) -> bool {
+        let mut task_ids = self.task_ids.lock();
+        if !should_wait() {
+            return false;
+        }
+
+        let task_id = get_current_task_id();
+        task_ids.push_back(task_id);
+        block_current_task(None);
+        drop(task_ids);
+
+        run_next_task();
+
+        // The task is still queued if a signal has woken it.
+        self.task_ids.lock().retain(|&id| id != task_id);
+        true
+    }
+
+    /// Wakes the task that has been blocked on this queue the
+    /// longest, if any. Returns whether a task is woken.
+    pub(crate) fn wake_one(&self) -> bool {
+        loop {
+            let Some(task_id) = self.task_ids.lock().pop_front() else {
+                return false;
+            };
+            // A task woken by a signal is skipped, since it
+            // would not take the event.
+            if wake_blocked_task(task_id) {
+                return true;
+            }
+        }
+    }
+
+    /// Wakes all the tasks blocked on this queue.
+    pub(crate) fn wake_all(&self) {
+        let task_ids = mem::take(&mut *self.task_ids.lock());
+        task_ids.into_iter().for_each(|task_id| {
+            wake_blocked_task(task_id);
+        });
+    }
+}
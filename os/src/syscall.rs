@@ -1,32 +1,135 @@
 mod io;
 mod mm;
 mod process;
+mod signal;
+mod sync;
+mod time;
 
 extern crate alloc;
 
+use alloc::string::String;
+use alloc::vec;
+
+use crate::fs::prelude::{Dirent, Stat};
+use crate::mm::prelude::{check_u_va_range, copy_from_user, copy_to_user};
 use crate::syscall::{
-    io::sys_write,
+    io::{
+        sys_chdir, sys_close, sys_dup, sys_dup2, sys_fstat, sys_getdents, sys_mkdir, sys_open,
+        sys_pipe, sys_read, sys_unlink, sys_write,
+    },
     mm::mmap,
-    process::{sys_exit, sys_task_info, sys_yield},
+    process::{
+        sys_exec, sys_exit, sys_fork, sys_gettid, sys_set_priority, sys_set_user_counters,
+        sys_task_info, sys_thread_create, sys_waitpid, sys_waittid, sys_yield,
+    },
+    signal::{sys_kill, sys_sigaction, sys_sigprocmask, sys_sigreturn},
+    sync::{
+        sys_condvar_create, sys_condvar_signal, sys_condvar_wait, sys_enable_deadlock_detect,
+        sys_futex, sys_mutex_create, sys_mutex_lock, sys_mutex_unlock, sys_semaphore_create,
+        sys_semaphore_down, sys_semaphore_up,
+    },
+    time::{sys_clock_gettime, sys_get_time, sys_nanosleep},
 };
 use crate::task::prelude::{TaskInfo, get_current_task_id};
 use crate::{log, warn};
 
+const SYSCALL_DUP: usize = 23;
+/// The number of dup3 in Linux, which has no dup2 on RISC-V.
+const SYSCALL_DUP2: usize = 24;
+/// The number of mkdirat in Linux, without the dirfd.
+const SYSCALL_MKDIR: usize = 34;
+/// The number of unlinkat in Linux, without the dirfd.
+const SYSCALL_UNLINK: usize = 35;
+const SYSCALL_CHDIR: usize = 49;
+const SYSCALL_OPEN: usize = 56;
+const SYSCALL_CLOSE: usize = 57;
+/// The number of pipe2 in Linux, without the flags.
+const SYSCALL_PIPE: usize = 59;
+/// The number of getdents64 in Linux.
+const SYSCALL_GETDENTS: usize = 61;
+const SYSCALL_READ: usize = 63;
 const SYSCALL_WRITE: usize = 64;
+const SYSCALL_FSTAT: usize = 80;
 const SYSCALL_MMAP: usize = 90;
 const SYSCALL_MUNMAP: usize = 215;
 const SYSCALL_EXIT: usize = 93;
+const SYSCALL_FUTEX: usize = 98;
+const SYSCALL_NANOSLEEP: usize = 101;
+const SYSCALL_CLOCK_GETTIME: usize = 113;
 const SYSCALL_YIELD: usize = 124;
+const SYSCALL_KILL: usize = 129;
+/// The number of rt_sigaction in Linux.
+const SYSCALL_SIGACTION: usize = 134;
+/// The number of rt_sigprocmask in Linux.
+const SYSCALL_SIGPROCMASK: usize = 135;
+/// The number of rt_sigreturn in Linux.
+const SYSCALL_SIGRETURN: usize = 139;
+const SYSCALL_SET_PRIORITY: usize = 140;
+const SYSCALL_GET_TIME: usize = 169;
+const SYSCALL_GETTID: usize = 178;
+const SYSCALL_FORK: usize = 220;
+const SYSCALL_EXEC: usize = 221;
+const SYSCALL_WAITPID: usize = 260;
 const SYSCALL_TASK_INFO: usize = (1 << 63) | 1;
+const SYSCALL_SET_USER_COUNTERS: usize = (1 << 63) | 2;
+const SYSCALL_THREAD_CREATE: usize = (1 << 63) | 3;
+const SYSCALL_WAITTID: usize = (1 << 63) | 4;
+const SYSCALL_MUTEX_CREATE: usize = (1 << 63) | 5;
+const SYSCALL_MUTEX_LOCK: usize = (1 << 63) | 6;
+const SYSCALL_MUTEX_UNLOCK: usize = (1 << 63) | 7;
+const SYSCALL_SEMAPHORE_CREATE: usize = (1 << 63) | 8;
+const SYSCALL_SEMAPHORE_UP: usize = (1 << 63) | 9;
+const SYSCALL_SEMAPHORE_DOWN: usize = (1 << 63) | 10;
+const SYSCALL_CONDVAR_CREATE: usize = (1 << 63) | 11;
+const SYSCALL_CONDVAR_SIGNAL: usize = (1 << 63) | 12;
+const SYSCALL_CONDVAR_WAIT: usize = (1 << 63) | 13;
+const SYSCALL_ENABLE_DEADLOCK_DETECT: usize = (1 << 63) | 14;
 
-pub fn syscall_handler(syscall_id: usize, args: [usize; 3]) -> isize {
+pub fn syscall_handler(syscall_id: usize, args: [usize; 6]) -> isize {
     match syscall_id {
+        SYSCALL_DUP => sys_dup(args[0]),
+        SYSCALL_DUP2 => sys_dup2(args[0], args[1]),
+        SYSCALL_MKDIR => sys_mkdir(args[0] as *const u8, args[1]),
+        SYSCALL_UNLINK => sys_unlink(args[0] as *const u8, args[1], args[2]),
+        SYSCALL_CHDIR => sys_chdir(args[0] as *const u8, args[1]),
+        SYSCALL_OPEN => sys_open(args[0] as *const u8, args[1], args[2]),
+        SYSCALL_CLOSE => sys_close(args[0]),
+        SYSCALL_PIPE => sys_pipe(args[0] as *mut _),
+        SYSCALL_GETDENTS => sys_getdents(args[0], args[1] as *mut Dirent, args[2]),
+        SYSCALL_READ => sys_read(args[0], args[1] as *mut u8, args[2]),
         SYSCALL_WRITE => sys_write(args[0], args[1] as *const u8, args[2]),
+        SYSCALL_FSTAT => sys_fstat(args[0], args[1] as *mut Stat),
         SYSCALL_MMAP => mmap(args[0], args[1], args[2]),
         SYSCALL_MUNMAP => mm::munmap(args[0], args[1]),
         SYSCALL_EXIT => sys_exit(args[0] as isize),
+        SYSCALL_FUTEX => sys_futex(args[0] as *const u32, args[1], args[2], args[3] as *const _),
+        SYSCALL_NANOSLEEP => sys_nanosleep(args[0] as *const _),
+        SYSCALL_CLOCK_GETTIME => sys_clock_gettime(args[0], args[1] as *mut _),
         SYSCALL_YIELD => sys_yield(),
+        SYSCALL_KILL => sys_kill(args[0] as isize, args[1]),
+        SYSCALL_SIGACTION => sys_sigaction(args[0], args[1] as *const _, args[2] as *mut _),
+        SYSCALL_SIGPROCMASK => sys_sigprocmask(args[0], args[1] as *const _, args[2] as *mut _),
+        SYSCALL_SIGRETURN => sys_sigreturn(),
+        SYSCALL_SET_PRIORITY => sys_set_priority(args[0] as isize),
+        SYSCALL_GET_TIME => sys_get_time(args[0] as *mut _),
+        SYSCALL_GETTID => sys_gettid(),
+        SYSCALL_FORK => sys_fork(),
+        SYSCALL_EXEC => sys_exec(args[0] as *const u8, args[1], args[2] as *const _, args[3]),
+        SYSCALL_WAITPID => sys_waitpid(args[0] as isize, args[1] as *mut i32),
         SYSCALL_TASK_INFO => sys_task_info(args[0], args[1] as *mut TaskInfo),
+        SYSCALL_SET_USER_COUNTERS => sys_set_user_counters(args[0]),
+        SYSCALL_THREAD_CREATE => sys_thread_create(args[0], args[1]),
+        SYSCALL_WAITTID => sys_waittid(args[0] as isize, args[1] as *mut i32),
+        SYSCALL_MUTEX_CREATE => sys_mutex_create(),
+        SYSCALL_MUTEX_LOCK => sys_mutex_lock(args[0]),
+        SYSCALL_MUTEX_UNLOCK => sys_mutex_unlock(args[0]),
+        SYSCALL_SEMAPHORE_CREATE => sys_semaphore_create(args[0]),
+        SYSCALL_SEMAPHORE_UP => sys_semaphore_up(args[0]),
+        SYSCALL_SEMAPHORE_DOWN => sys_semaphore_down(args[0]),
+        SYSCALL_CONDVAR_CREATE => sys_condvar_create(),
+        SYSCALL_CONDVAR_SIGNAL => sys_condvar_signal(args[0]),
+        SYSCALL_CONDVAR_WAIT => sys_condvar_wait(args[0], args[1]),
+        SYSCALL_ENABLE_DEADLOCK_DETECT => sys_enable_deadlock_detect(args[0]),
         _ => panic!("Unknown syscall, id={syscall_id}, args={args:?}."),
     }
 }
@@ -50,3 +153,83 @@ fn log_failed_copy_to(dst: *mut u8, len: usize, failed_len: usize) {
         failed_len,
     );
 }
+
+/// Copies the UTF-8 string of `len` bytes at `src` from
+/// user space, or returns [None] if it cannot be copied or
+/// it is longer than `max_len`.
+pub(super) fn copy_str_from_user(src: *const u8, len: usize, max_len: usize) -> Option<String> {
+    if len > max_len || !check_u_va_range(src.addr(), len) {
+        log_failed_copy_from(src, len, len);
+        return None;
+    }
+
+    let mut dst = vec![0; len];
+    let failed_len = unsafe { copy_from_user(src, dst.as_mut_ptr(), len) };
+    if failed_len != 0 {
+        log_failed_copy_from(src, len, failed_len);
+        return None;
+    }
+
+    match String::from_utf8(dst) {
+        Ok(result) => Some(result),
+        Err(_) => {
+            warn!(
+                "Task {:?}: String is not valid UTF-8, src={:#x}",
+                get_current_task_id(),
+                src.addr()
+            );
+            None
+        }
+    }
+}
+
+/// Copies a `T` from `src` in user space, or returns [None]
+/// if it cannot be copied.
+pub(super) fn read_from_user<T: Default>(src: *const T) -> Option<T> {
+    let src = src as *const u8;
+    let len = size_of::<T>();
+    if !check_u_va_range(src.addr(), len) {
+        log_failed_copy_from(src, len, len);
+        return None;
+    }
+
+    let mut result = T::default();
+    let dst = (&raw mut result) as *mut u8;
+    let failed_len = unsafe { copy_from_user(src, dst, len) };
+    if failed_len != 0 {
+        log_failed_copy_from(src, len, failed_len);
+        return None;
+    }
+    Some(result)
+}
+
+/// Returns whether a `T` fits at `dst` in user space, so
+/// that a syscall can fail before it has any effect. The
+/// copy may still fail on an unmapped page.
+pub(super) fn check_writable<T>(dst: *mut T) -> bool {
+    let dst = dst as *mut u8;
+    let len = size_of::<T>();
+    let result = check_u_va_range(dst.addr(), len);
+    if !result {
+        log_failed_copy_to(dst, len, len);
+    }
+    result
+}
+
+/// Copies the `src` to `dst` in user space, and returns
+/// whether it is copied.
+pub(super) fn write_to_user<T>(dst: *mut T, src: &T) -> bool {
+    if !check_writable(dst) {
+        return false;
+    }
+
+    let dst = dst as *mut u8;
+    let len = size_of::<T>();
+    let src = src as *const T as *const u8;
+    let failed_len = unsafe { copy_to_user(src, dst, len) };
+    if failed_len != 0 {
+        log_failed_copy_to(dst, len, failed_len);
+        return false;
+    }
+    true
+}
@@ -1,22 +1,236 @@
 extern crate alloc;
 
+use alloc::sync::Arc;
 use alloc::vec;
-use core::str;
+use alloc::vec::Vec;
 
-use crate::mm::prelude::{check_u_va_range, copy_from_user};
-use crate::syscall::log_failed_copy_from;
-use crate::task::prelude::get_current_task_id;
-use crate::{log, print, warn};
+use crate::fs::prelude::{self as fs_p, Dentry, Dirent, File, FsError, Stat};
+use crate::mm::prelude::{check_u_va_range, copy_from_user, copy_to_user};
+use crate::syscall::{copy_str_from_user, log_failed_copy_from, log_failed_copy_to};
+use crate::task::prelude::{get_current_task_id, update_process};
+use crate::{info, log, warn};
 
-const FD_STDOUT: usize = 1;
+const MAX_PATH_LEN: usize = 256;
 
+/// The flag of [sys_unlink] to remove a directory, see
+/// [here].
+///
+/// [here]: https://github.com/torvalds/linux/blob/master/include/uapi/linux/fcntl.h
+const AT_REMOVEDIR: usize = 0x200;
+
+/// Opens the file at the path of `len` bytes at `path` with
+/// the `flags`, where a relative path starts from the cwd.
+///
+/// Returns the lowest free file descriptor on success, or
+/// -1 if the file cannot be opened or no descriptor is free.
+pub(super) fn sys_open(path: *const u8, len: usize, flags: usize) -> isize {
+    let task_id = get_current_task_id();
+
+    let Some(path) = copy_str_from_user(path, len, MAX_PATH_LEN) else {
+        return -1;
+    };
+    let file = match fs_p::open(&get_current_cwd(), &path, flags) {
+        Ok(file) => file,
+        Err(err) => {
+            warn!("Task {:?}: Failed to open {}, err={:?}", task_id, path, err);
+            return -1;
+        }
+    };
+
+    let mut fd = None;
+    update_process(task_id, |process| {
+        fd = process.get_fd_table_mut().alloc(file)
+    });
+    let Some(fd) = fd else {
+        warn!("Task {:?}: No free file descriptor", task_id);
+        return -1;
+    };
+
+    info!("Task {:?}: Opened {} as fd {}", task_id, path, fd);
+    fd as isize
+}
+
+/// Closes the `fd`. The [File] is released once no other
+/// descriptor refers to it.
+///
+/// Returns 0 on success, or -1 if the `fd` is not open.
+pub(super) fn sys_close(fd: usize) -> isize {
+    let task_id = get_current_task_id();
+
+    let mut file = None;
+    update_process(task_id, |process| {
+        file = process.get_fd_table_mut().remove(fd)
+    });
+    if file.is_none() {
+        log_bad_fd(fd);
+        return -1;
+    }
+
+    // Drop the file after the task registry is unlocked.
+    drop(file);
+    0
+}
+
+/// Duplicates the `fd` as the lowest free file descriptor.
+///
+/// Returns the new descriptor on success, or -1 if the `fd`
+/// is not open or no descriptor is free.
+pub(super) fn sys_dup(fd: usize) -> isize {
+    let task_id = get_current_task_id();
+
+    let mut new_fd = None;
+    update_process(task_id, |process| {
+        let fd_table = process.get_fd_table_mut();
+        new_fd = fd_table.get(fd).map(|file| fd_table.alloc(file));
+    });
+
+    match new_fd {
+        Some(Some(new_fd)) => new_fd as isize,
+        Some(None) => {
+            warn!("Task {:?}: No free file descriptor", task_id);
+            -1
+        }
+        None => {
+            log_bad_fd(fd);
+            -1
+        }
+    }
+}
+
+/// Duplicates the `fd` as the `new_fd`, closing the file
+/// the `new_fd` refers to unless it is the `fd` itself.
+///
+/// Returns the `new_fd` on success, or -1 if the `fd` is
+/// not open or the `new_fd` is out of range.
+pub(super) fn sys_dup2(fd: usize, new_fd: usize) -> isize {
+    let task_id = get_current_task_id();
+
+    let mut prev_file = None;
+    let mut result = -1;
+    update_process(task_id, |process| {
+        let fd_table = process.get_fd_table_mut();
+        let Some(file) = fd_table.get(fd) else {
+            return;
+        };
+        prev_file = fd_table.get(new_fd);
+        if fd_table.insert(new_fd, file) {
+            result = new_fd as isize;
+        }
+    });
+
+    // Drop the replaced file after the task registry is
+    // unlocked.
+    drop(prev_file);
+    if result == -1 {
+        warn!("Task {:?}: Failed to dup {} to {}", task_id, fd, new_fd);
+    }
+    result
+}
+
+/// Creates a pipe and writes the file descriptors of its
+/// read end and write end to `fds`.
+///
+/// Returns 0 on success, or -1 if `fds` cannot be written
+/// or no two descriptors are free.
+pub(super) fn sys_pipe(fds: *mut [i32; 2]) -> isize {
+    let task_id = get_current_task_id();
+
+    let dst = fds as *mut u8;
+    let len = size_of::<[i32; 2]>();
+    if !check_u_va_range(dst.addr(), len) {
+        log_failed_copy_to(dst, len, len);
+        return -1;
+    }
+
+    let (read_end, write_end) = fs_p::pipe();
+    let mut result = None;
+    let mut unused_end = None;
+    update_process(task_id, |process| {
+        let fd_table = process.get_fd_table_mut();
+        let Some(read_fd) = fd_table.alloc(read_end) else {
+            unused_end = Some(write_end);
+            return;
+        };
+        match fd_table.alloc(write_end) {
+            Some(write_fd) => result = Some([read_fd as i32, write_fd as i32]),
+            None => unused_end = fd_table.remove(read_fd),
+        }
+    });
+    // Drop the unused end after the task registry is
+    // unlocked.
+    drop(unused_end);
+    let Some(pipe_fds) = result else {
+        warn!("Task {:?}: No free file descriptor", task_id);
+        return -1;
+    };
+
+    let src = (&raw const pipe_fds) as *const u8;
+    let failed_len = unsafe { copy_to_user(src, dst, len) };
+    if failed_len != 0 {
+        log_failed_copy_to(dst, len, failed_len);
+        let mut ends = [None, None];
+        update_process(task_id, |process| {
+            let fd_table = process.get_fd_table_mut();
+            ends = pipe_fds.map(|fd| fd_table.remove(fd as usize));
+        });
+        drop(ends);
+        return -1;
+    }
+    info!("Task {:?}: Opened a pipe as fds {:?}", task_id, pipe_fds);
+    0
+}
+
+/// Reads up to `count` bytes from the `fd` into `buf`.
+///
+/// Returns the number of bytes read, which is zero at the
+/// end of the file, or -1 on failure.
+pub(super) fn sys_read(fd: usize, buf: *mut u8, count: usize) -> isize {
+    let Some(file) = get_current_file(fd) else {
+        return -1;
+    };
+    if !file.readable() {
+        log_bad_fd(fd);
+        return -1;
+    }
+
+    if !check_u_va_range(buf.addr(), count) {
+        log_failed_copy_to(buf, count, count);
+        return -1;
+    }
+
+    let mut src = vec![0; count];
+    let read_len = match file.read(&mut src) {
+        Ok(len) => len,
+        Err(err) => {
+            warn!(
+                "Task {:?}: Failed to read {}, err={:?}",
+                get_current_task_id(),
+                fd,
+                err
+            );
+            return -1;
+        }
+    };
+
+    let failed_len = unsafe { copy_to_user(src.as_ptr(), buf, read_len) };
+    if failed_len != 0 {
+        log_failed_copy_to(buf, read_len, failed_len);
+        return -1;
+    }
+
+    read_len as isize
+}
+
+/// Writes `count` bytes at `buf` to the `fd`.
+///
+/// Returns the number of bytes written, or -1 on failure,
+/// e.g., if the `fd` is a pipe with no read end open.
 pub(super) fn sys_write(fd: usize, buf: *const u8, count: usize) -> isize {
-    if fd != FD_STDOUT {
-        warn!(
-            "Task {:?}: Unsupported file descriptor {}",
-            get_current_task_id(),
-            fd
-        );
+    let Some(file) = get_current_file(fd) else {
+        return -1;
+    };
+    if !file.writable() {
+        log_bad_fd(fd);
         return -1;
     }
 
@@ -33,8 +247,194 @@ pub(super) fn sys_write(fd: usize, buf: *const u8, count: usize) -> isize {
         return -1;
     }
 
-    let str = str::from_utf8(&dst).unwrap();
-    print!("{str}");
+    match file.write(&dst) {
+        Ok(len) => len as isize,
+        Err(err) => {
+            warn!(
+                "Task {:?}: Failed to write {}, err={:?}",
+                get_current_task_id(),
+                fd,
+                err
+            );
+            -1
+        }
+    }
+}
+
+/// Writes the [Stat] of the `fd` to `stat`.
+///
+/// Returns 0 on success, or -1 on failure.
+pub(super) fn sys_fstat(fd: usize, stat: *mut Stat) -> isize {
+    let Some(file) = get_current_file(fd) else {
+        return -1;
+    };
+
+    let dst = stat as *mut u8;
+    let len = size_of::<Stat>();
+    if !check_u_va_range(dst.addr(), len) {
+        log_failed_copy_to(dst, len, len);
+        return -1;
+    }
+
+    let file_stat = file.stat();
+    let src = (&raw const file_stat) as *const u8;
+    let failed_len = unsafe { copy_to_user(src, dst, len) };
+    if failed_len != 0 {
+        log_failed_copy_to(dst, len, failed_len);
+        return -1;
+    }
+    0
+}
+
+/// Creates an empty directory at the path of `len` bytes at
+/// `path`.
+///
+/// Returns 0 on success, or -1 on failure.
+pub(super) fn sys_mkdir(path: *const u8, len: usize) -> isize {
+    let Some(path) = copy_str_from_user(path, len, MAX_PATH_LEN) else {
+        return -1;
+    };
+    if let Err(err) = fs_p::mkdir(&get_current_cwd(), &path) {
+        log_failed_path_op("mkdir", &path, err);
+        return -1;
+    }
+    0
+}
+
+/// Removes the file at the path of `len` bytes at `path`,
+/// or the empty directory if the `flags` has
+/// [AT_REMOVEDIR]. An open file stays accessible until it
+/// is closed, on the file systems that support it.
+///
+/// Returns 0 on success, or -1 on failure.
+pub(super) fn sys_unlink(path: *const u8, len: usize, flags: usize) -> isize {
+    if flags & !AT_REMOVEDIR != 0 {
+        warn!(
+            "Task {:?}: Invalid unlink flags {:#x}",
+            get_current_task_id(),
+            flags
+        );
+        return -1;
+    }
+    let Some(path) = copy_str_from_user(path, len, MAX_PATH_LEN) else {
+        return -1;
+    };
+    let is_dir = flags & AT_REMOVEDIR != 0;
+    if let Err(err) = fs_p::unlink(&get_current_cwd(), &path, is_dir) {
+        log_failed_path_op("unlink", &path, err);
+        return -1;
+    }
+    0
+}
+
+/// Changes the cwd to the directory at the path of `len`
+/// bytes at `path`.
+///
+/// Returns 0 on success, or -1 on failure.
+pub(super) fn sys_chdir(path: *const u8, len: usize) -> isize {
+    let task_id = get_current_task_id();
+
+    let Some(path) = copy_str_from_user(path, len, MAX_PATH_LEN) else {
+        return -1;
+    };
+    let cwd = match fs_p::lookup_dir(&get_current_cwd(), &path) {
+        Ok(cwd) => cwd,
+        Err(err) => {
+            log_failed_path_op("chdir", &path, err);
+            return -1;
+        }
+    };
+
+    let mut prev_cwd = None;
+    update_process(task_id, |process| prev_cwd = Some(process.replace_cwd(cwd)));
+    // Drop the previous cwd after the task registry is
+    // unlocked.
+    drop(prev_cwd);
+    0
+}
+
+/// Reads up to `count` entries of the directory `fd` into
+/// `dirents`, continuing from where the last call ended.
+///
+/// Returns the number of entries read, which is zero at the
+/// end of the directory, or -1 on failure.
+pub(super) fn sys_getdents(fd: usize, dirents: *mut Dirent, count: usize) -> isize {
+    let Some(file) = get_current_file(fd) else {
+        return -1;
+    };
+
+    let dst = dirents as *mut u8;
+    let Some(len) = count.checked_mul(size_of::<Dirent>()) else {
+        log_failed_copy_to(dst, usize::MAX, usize::MAX);
+        return -1;
+    };
+    if !check_u_va_range(dst.addr(), len) {
+        log_failed_copy_to(dst, len, len);
+        return -1;
+    }
+
+    let entries = match file.read_dir(count) {
+        Ok(entries) => entries,
+        Err(err) => {
+            warn!(
+                "Task {:?}: Failed to read directory {}, err={:?}",
+                get_current_task_id(),
+                fd,
+                err
+            );
+            return -1;
+        }
+    };
+    let src: Vec<Dirent> = entries.iter().map(Dirent::from).collect();
+    let len = src.len() * size_of::<Dirent>();
+    let failed_len = unsafe { copy_to_user(src.as_ptr() as *const u8, dst, len) };
+    if failed_len != 0 {
+        log_failed_copy_to(dst, len, failed_len);
+        return -1;
+    }
+    src.len() as isize
+}
+
+/// Returns the cwd of the current task.
+fn get_current_cwd() -> Arc<Dentry> {
+    let mut cwd = None;
+    update_process(get_current_task_id(), |process| {
+        cwd = Some(process.get_cwd().clone())
+    });
+    cwd.unwrap()
+}
+
+fn log_failed_path_op(op: &str, path: &str, err: FsError) {
+    warn!(
+        "Task {:?}: Failed to {} {}, err={:?}",
+        get_current_task_id(),
+        op,
+        path,
+        err
+    );
+}
+
+/// Returns the [File] of the `fd` of the current task, or
+/// [None] if it is not open.
+///
+/// The task registry is unlocked on return, so the file
+/// can be accessed without blocking the other tasks.
+fn get_current_file(fd: usize) -> Option<Arc<dyn File>> {
+    let mut file = None;
+    update_process(get_current_task_id(), |process| {
+        file = process.get_fd_table().get(fd)
+    });
+
+    if file.is_none() {
+        log_bad_fd(fd);
+    }
+    file
+}
 
-    count as isize
+fn log_bad_fd(fd: usize) {
+    warn!(
+        "Task {:?}: Bad file descriptor {}",
+        get_current_task_id(),
+        fd
+    );
 }
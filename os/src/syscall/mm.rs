@@ -1,10 +1,8 @@
-use core::arch::asm;
-
 use crate::mm::prelude::{
     MapType, PAGE_SIZE_BYTES, PERMISSION_R, PERMISSION_U, PERMISSION_W, PERMISSION_X, VPN,
     check_u_va_range,
 };
-use crate::task::prelude::{get_current_task_id, update_tcb};
+use crate::task::prelude::{get_current_task_id, update_vm_space};
 
 const ALL_PROT_FLAGS: usize = PROT_EXEC | PROT_READ | PROT_WRITE;
 const PROT_EXEC: usize = 1;
@@ -23,15 +21,13 @@ pub(super) fn mmap(addr: usize, len: usize, prot: usize) -> isize {
     let task_id = get_current_task_id();
     let mut result = Ok(());
 
-    update_tcb(task_id, |tcb| {
+    update_vm_space(task_id, |vm_space| {
         let start_vpn = VPN::from_va(addr);
         let end_vpn = VPN::from_va(addr + len + PAGE_SIZE_BYTES - 1);
         let map_type = MapType::Anonymous;
         let permissions = to_permissions(prot);
 
-        result = tcb
-            .get_vm_space_mut()
-            .add_new_area(start_vpn, end_vpn, map_type, permissions);
+        result = vm_space.add_new_area(start_vpn, end_vpn, map_type, permissions);
     });
 
     if result.is_ok() { 0 } else { -1 }
@@ -64,12 +60,11 @@ pub(super) fn munmap(addr: usize, len: usize) -> isize {
     let task_id = get_current_task_id();
     let mut result = Ok(());
 
-    update_tcb(task_id, |tcb| {
+    update_vm_space(task_id, |vm_space| {
         let start_vpn = VPN::from_va(addr);
         let end_vpn = VPN::from_va(addr + len + PAGE_SIZE_BYTES - 1);
-        result = tcb.get_vm_space_mut().unmap(start_vpn, end_vpn);
+        result = vm_space.unmap(start_vpn, end_vpn);
     });
 
-    unsafe { asm!("sfence.vma") };
     if result.is_ok() { 0 } else { -1 }
 }
@@ -1,19 +1,31 @@
-use crate::mm::prelude::{check_u_va_range, copy_to_user};
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::mm::prelude::{check_u_va, check_u_va_range, copy_from_user, copy_to_user};
 use crate::task::prelude::{
-    TaskInfo, TaskState, exchange_current_task_state, get_current_task_id, get_task_info,
-    run_next_task,
+    TaskInfo, TaskState, WaitResult, count_current_threads, create_current_thread,
+    exchange_current_task_state, exec_current_task, exit_current_task, find_app_elf,
+    fork_current_task, get_current_task_id, get_task_info, reap_current_task_child,
+    reap_current_thread, run_next_task, set_current_task_priority, set_current_task_user_counters,
+    wait_current_task_child, wait_current_thread,
 };
-use crate::{info, log};
+use crate::{info, log, warn};
 
-use crate::syscall::log_failed_copy_to;
+use crate::syscall::{
+    check_writable, copy_str_from_user, log_failed_copy_from, log_failed_copy_to, write_to_user,
+};
+
+const MAX_APP_NAME_LEN: usize = 256;
+const MAX_EXEC_ARGS: usize = 32;
+const MAX_EXEC_ARG_LEN: usize = 256;
 
 pub(super) fn sys_exit(exit_code: isize) -> isize {
     let task_id = get_current_task_id();
 
-    let state = exchange_current_task_state(TaskState::Running, TaskState::Exited);
-    if let Err(state) = state {
-        panic!("Task {:?}: Expected running but got {:?}", task_id, state)
-    }
+    exit_current_task(exit_code);
 
     info!("Task {:?}: Exited with code {}", task_id, exit_code);
     run_next_task();
@@ -33,6 +45,230 @@ pub(super) fn sys_yield() -> isize {
     0
 }
 
+/// Sets the priority of the current task, which is used
+/// by the stride scheduler.
+///
+/// Returns the `priority` on success, or -1 if it is less
+/// than the minimum priority.
+pub(super) fn sys_set_priority(priority: isize) -> isize {
+    let task_id = get_current_task_id();
+
+    if priority < 0 || !set_current_task_priority(priority as usize) {
+        warn!("Task {:?}: Invalid priority {}", task_id, priority);
+        return -1;
+    }
+
+    info!("Task {:?}: Set priority to {}", task_id, priority);
+    priority
+}
+
+/// Sets which of the cycle, time and retired instruction
+/// counters the current task can read directly in user
+/// mode, as a mask of bits 0, 1 and 2 respectively.
+///
+/// Returns the previous mask on success, or -1 if the
+/// `user_counters` has other bits.
+pub(super) fn sys_set_user_counters(user_counters: usize) -> isize {
+    let task_id = get_current_task_id();
+
+    let Some(prev) = set_current_task_user_counters(user_counters) else {
+        warn!(
+            "Task {:?}: Invalid user counters {:#x}",
+            task_id, user_counters
+        );
+        return -1;
+    };
+
+    info!(
+        "Task {:?}: Set user counters to {:#x}",
+        task_id, user_counters
+    );
+    prev as isize
+}
+
+pub(super) fn sys_fork() -> isize {
+    let task_id = get_current_task_id();
+
+    match fork_current_task() {
+        Ok(child_id) => {
+            info!("Task {:?}: Forked task {}", task_id, child_id);
+            child_id as isize
+        }
+        Err(err) => {
+            warn!("Task {:?}: Failed to fork, err={:?}", task_id, err);
+            -1
+        }
+    }
+}
+
+/// Replaces the image of the current task with the app at
+/// the absolute path `name`, or the app `name` in /bin.
+/// The `argv` points to `argc` (address, length) pairs of
+/// the argument strings.
+///
+/// Returns argc on success, which becomes a0 of the new
+/// image, or -1 on failure.
+pub(super) fn sys_exec(name: *const u8, len: usize, argv: *const [usize; 2], argc: usize) -> isize {
+    let task_id = get_current_task_id();
+
+    let Some(name) = copy_str_from_user(name, len, MAX_APP_NAME_LEN) else {
+        return -1;
+    };
+    let Some(elf_bytes) = find_app_elf(&name) else {
+        warn!("Task {:?}: Cannot find app {}", task_id, name);
+        return -1;
+    };
+
+    // The other threads would be left without their image.
+    if count_current_threads() > 1 {
+        warn!("Task {:?}: Cannot exec with other threads", task_id);
+        return -1;
+    }
+
+    if argc > MAX_EXEC_ARGS {
+        warn!("Task {:?}: Too many arguments, argc={}", task_id, argc);
+        return -1;
+    }
+
+    let src = argv as *const u8;
+    let len = argc * size_of::<[usize; 2]>();
+    if !check_u_va_range(src.addr(), len) {
+        log_failed_copy_from(src, len, len);
+        return -1;
+    }
+
+    let mut raw_args = vec![[0usize; 2]; argc];
+    let failed_len = unsafe { copy_from_user(src, raw_args.as_mut_ptr() as *mut u8, len) };
+    if failed_len != 0 {
+        log_failed_copy_from(src, len, failed_len);
+        return -1;
+    }
+
+    let mut args = Vec::with_capacity(argc);
+    for [addr, len] in raw_args {
+        let Some(arg) = copy_str_from_user(addr as *const u8, len, MAX_EXEC_ARG_LEN) else {
+            return -1;
+        };
+        args.push(arg);
+    }
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    match exec_current_task(&elf_bytes, &args, &[]) {
+        Ok(argc) => {
+            info!("Task {:?}: Executes app {}", task_id, name);
+            argc as isize
+        }
+        Err(err) => {
+            warn!(
+                "Task {:?}: Failed to execute app {}, err={:?}",
+                task_id, name, err
+            );
+            -1
+        }
+    }
+}
+
+/// Waits for the child `pid`, or any child if `pid` is -1,
+/// to exit, and then writes its exit code to `exit_code`
+/// unless it is null and reaps it.
+///
+/// Returns the task ID of the reaped child, or -1 if there
+/// is no matching child, a signal interrupts the wait, or
+/// the exit code cannot be written, in which case the child
+/// is left for another wait.
+pub(super) fn sys_waitpid(pid: isize, exit_code: *mut i32) -> isize {
+    let task_id = get_current_task_id();
+
+    if !exit_code.is_null() && !check_writable(exit_code) {
+        return -1;
+    }
+
+    loop {
+        let (child_id, child_exit_code) = match wait_current_task_child(pid) {
+            WaitResult::Exited(child_id, child_exit_code) => (child_id, child_exit_code as i32),
+            WaitResult::NoChild | WaitResult::Interrupted => return -1,
+        };
+        if !exit_code.is_null() && !write_to_user(exit_code, &child_exit_code) {
+            return -1;
+        }
+        // Another thread may have reaped it first.
+        if reap_current_task_child(child_id) {
+            info!(
+                "Task {:?}: Reaped task {} with exit code {}",
+                task_id, child_id, child_exit_code
+            );
+            return child_id as isize;
+        }
+    }
+}
+
+/// Creates a thread in the current process, which starts
+/// from `entry` with `arg` in a0 on a new user stack. The
+/// thread should exit rather than return from `entry`.
+///
+/// Returns the task ID of the thread, or -1 on failure.
+pub(super) fn sys_thread_create(entry: usize, arg: usize) -> isize {
+    let task_id = get_current_task_id();
+
+    if !check_u_va(entry) {
+        warn!("Task {:?}: Invalid thread entry {:#x}", task_id, entry);
+        return -1;
+    }
+
+    match create_current_thread(entry, arg) {
+        Ok(thread_id) => {
+            info!("Task {:?}: Created thread {}", task_id, thread_id);
+            thread_id as isize
+        }
+        Err(err) => {
+            warn!("Task {:?}: Failed to create thread, err={:?}", task_id, err);
+            -1
+        }
+    }
+}
+
+/// Returns the task ID of the current thread.
+pub(super) fn sys_gettid() -> isize {
+    get_current_task_id() as isize
+}
+
+/// Waits for the thread `tid` of the current process to
+/// exit, and then writes its exit code to `exit_code`
+/// unless it is null and reaps it.
+///
+/// Returns `tid`, or -1 if it is not another thread of the
+/// process than the main thread, a signal interrupts the
+/// wait, or the exit code cannot be written, in which case
+/// the thread is left for another wait.
+pub(super) fn sys_waittid(tid: isize, exit_code: *mut i32) -> isize {
+    let task_id = get_current_task_id();
+
+    if tid <= 0 {
+        warn!("Task {:?}: Invalid tid {} to wait for", task_id, tid);
+        return -1;
+    }
+    if !exit_code.is_null() && !check_writable(exit_code) {
+        return -1;
+    }
+
+    loop {
+        let thread_exit_code = match wait_current_thread(tid as usize) {
+            WaitResult::Exited(_, thread_exit_code) => thread_exit_code as i32,
+            WaitResult::NoChild | WaitResult::Interrupted => return -1,
+        };
+        if !exit_code.is_null() && !write_to_user(exit_code, &thread_exit_code) {
+            return -1;
+        }
+        if reap_current_thread(tid as usize) {
+            info!(
+                "Task {:?}: Reaped thread {} with exit code {}",
+                task_id, tid, thread_exit_code
+            );
+            return tid;
+        }
+    }
+}
+
 pub(super) fn sys_task_info(task_id: usize, data: *mut TaskInfo) -> isize {
     let dst = data as *mut u8;
     let len = size_of::<TaskInfo>();
@@ -0,0 +1,127 @@
+use crate::syscall::{check_writable, read_from_user, write_to_user};
+use crate::task::prelude::{
+    SIGSEGV, SigAction, force_current_signal, get_current_task_id, send_signal,
+    set_current_sigaction, set_current_sigmask, sigreturn_current_task,
+};
+use crate::{info, log, warn};
+
+/// Sends the signal `signum` to the task `pid`. If `signum`
+/// is 0, only checks that the task exists.
+///
+/// Returns 0 on success, or -1 if the `pid` or `signum` is
+/// invalid.
+pub(super) fn sys_kill(pid: isize, signum: usize) -> isize {
+    let task_id = get_current_task_id();
+
+    if pid <= 0 {
+        warn!("Task {:?}: Invalid pid {} to kill", task_id, pid);
+        return -1;
+    }
+    if let Err(err) = send_signal(pid as usize, signum) {
+        warn!(
+            "Task {:?}: Failed to send signal {} to {}, err={:?}",
+            task_id, signum, pid, err
+        );
+        return -1;
+    }
+
+    info!("Task {:?}: Sent signal {} to {}", task_id, signum, pid);
+    0
+}
+
+/// Sets the action of the signal `signum` to the one at
+/// `action` unless it is null, and writes the previous one
+/// to `prev_action` unless it is null.
+///
+/// Returns 0 on success, or -1 on failure.
+pub(super) fn sys_sigaction(
+    signum: usize,
+    action: *const SigAction,
+    prev_action: *mut SigAction,
+) -> isize {
+    let task_id = get_current_task_id();
+
+    let action = if action.is_null() {
+        None
+    } else {
+        let Some(action) = read_from_user(action) else {
+            return -1;
+        };
+        Some(action)
+    };
+    if !prev_action.is_null() && !check_writable(prev_action) {
+        return -1;
+    }
+
+    let prev = match set_current_sigaction(signum, action) {
+        Ok(prev) => prev,
+        Err(err) => {
+            warn!(
+                "Task {:?}: Failed to set the action of signal {}, err={:?}",
+                task_id, signum, err
+            );
+            return -1;
+        }
+    };
+    if !prev_action.is_null() && !write_to_user(prev_action, &prev) {
+        return -1;
+    }
+    0
+}
+
+/// Changes the blocked signals by the mask at `set` unless
+/// it is null, as told by `how`, i.e., blocks them, unblocks
+/// them or replaces the blocked ones with them. Writes the
+/// previously blocked ones to `prev_set` unless it is null.
+///
+/// Returns 0 on success, or -1 on failure.
+pub(super) fn sys_sigprocmask(how: usize, set: *const u64, prev_set: *mut u64) -> isize {
+    let task_id = get_current_task_id();
+
+    let set = if set.is_null() {
+        None
+    } else {
+        let Some(set) = read_from_user(set) else {
+            return -1;
+        };
+        Some(set)
+    };
+    if !prev_set.is_null() && !check_writable(prev_set) {
+        return -1;
+    }
+
+    let prev = match set_current_sigmask(how, set) {
+        Ok(prev) => prev,
+        Err(err) => {
+            warn!(
+                "Task {:?}: Failed to set the signal mask, err={:?}",
+                task_id, err
+            );
+            return -1;
+        }
+    };
+    if !prev_set.is_null() && !write_to_user(prev_set, &prev) {
+        return -1;
+    }
+    0
+}
+
+/// Returns from a signal handler to the code it interrupted,
+/// restoring the registers and the blocked signals saved on
+/// the user stack. A bad frame raises [SIGSEGV].
+///
+/// Returns the restored a0 on success, or -1 on failure.
+pub(super) fn sys_sigreturn() -> isize {
+    match sigreturn_current_task() {
+        Ok(a0) => a0 as isize,
+        Err(err) => {
+            warn!(
+                "Task {:?}: Failed to return from a signal handler, err={:?}",
+                get_current_task_id(),
+                err
+            );
+            force_current_signal(SIGSEGV);
+            -1
+        }
+    }
+}
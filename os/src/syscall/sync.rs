@@ -0,0 +1,242 @@
+use crate::mm::prelude::check_u_va_range;
+use crate::syscall::read_from_user;
+use crate::task::prelude::{
+    FutexWaitResult, Resource, SyncError, acquire_current_resource, create_current_condvar,
+    create_current_mutex, create_current_semaphore, futex_wait, futex_wake, get_current_task_id,
+    release_current_resource, set_current_deadlock_detect, signal_current_condvar, update_vm_space,
+    wait_current_condvar,
+};
+use crate::timer::{self, TimeSpec};
+use crate::{info, log, warn};
+
+/// Blocks while the futex word still has the given value.
+const FUTEX_WAIT: usize = 0;
+/// Wakes the tasks blocked on the futex word.
+const FUTEX_WAKE: usize = 1;
+
+/// Returned instead of blocking when the deadlock detection
+/// finds that the wait could never end.
+const DEADLOCK: isize = -0xDEAD;
+
+/// Operates on the futex word, a `u32` at `addr`, as told by
+/// `op`. The word is identified by its physical address, so
+/// the tasks mapping the same page meet at it.
+///
+/// [FUTEX_WAIT] blocks the current task if the word is `val`,
+/// until it is woken or the duration `timeout` points to
+/// passes, unless `timeout` is null. It returns 0 once woken,
+/// -2 if the word is not `val`, -3 on timeout, or -1 if a
+/// signal interrupts it.
+///
+/// [FUTEX_WAKE] wakes at most `val` tasks blocked on the
+/// word, and returns the number of tasks woken.
+///
+/// Both return -1 if the arguments are invalid.
+pub(super) fn sys_futex(
+    addr: *const u32,
+    op: usize,
+    val: usize,
+    timeout: *const TimeSpec,
+) -> isize {
+    let task_id = get_current_task_id();
+
+    if !addr.is_aligned() || !check_u_va_range(addr.addr(), size_of::<u32>()) {
+        warn!("Task {:?}: Invalid futex address {:p}", task_id, addr);
+        return -1;
+    }
+    let Some(pa) = get_futex_pa(addr) else {
+        return -1;
+    };
+
+    match op {
+        FUTEX_WAIT => {
+            let wakeup_time = if timeout.is_null() {
+                None
+            } else {
+                let Some(duration) = read_from_user(timeout) else {
+                    return -1;
+                };
+                if !duration.is_valid() {
+                    warn!("Task {:?}: Invalid timeout {:?}", task_id, duration);
+                    return -1;
+                }
+                Some(timer::read_time().saturating_add(duration.to_ticks()))
+            };
+
+            let mut is_readable = true;
+            let should_wait = || match read_from_user(addr) {
+                Some(word) => word as usize == val,
+                None => {
+                    is_readable = false;
+                    false
+                }
+            };
+            match futex_wait(pa, should_wait, wakeup_time) {
+                FutexWaitResult::Woken => 0,
+                FutexWaitResult::NotWaited if is_readable => -2,
+                FutexWaitResult::NotWaited => -1,
+                FutexWaitResult::TimedOut => -3,
+                FutexWaitResult::Interrupted => -1,
+            }
+        }
+        FUTEX_WAKE => futex_wake(pa, val) as isize,
+        _ => {
+            warn!("Task {:?}: Unsupported futex operation {}", task_id, op);
+            -1
+        }
+    }
+}
+
+/// Returns the physical address of the futex word at `addr`,
+/// after resolving any copy-on-write, so that it stays the
+/// same for the later writes to the word.
+fn get_futex_pa(addr: *const u32) -> Option<usize> {
+    let task_id = get_current_task_id();
+    let mut result = Ok(0);
+
+    update_vm_space(task_id, |vm_space| {
+        result = vm_space.get_writable_pa(addr.addr());
+    });
+
+    match result {
+        Ok(pa) => Some(pa),
+        Err(err) => {
+            warn!(
+                "Task {:?}: Failed to resolve futex {:p}, err={:?}",
+                task_id, addr, err
+            );
+            None
+        }
+    }
+}
+
+/// Creates a mutex in the current process.
+///
+/// Returns the mutex ID.
+pub(super) fn sys_mutex_create() -> isize {
+    create_current_mutex() as isize
+}
+
+/// Locks the mutex `mutex_id` of the current process,
+/// blocking until it is unlocked.
+///
+/// Returns 0 on success, -1 if the mutex does not exist or a
+/// signal interrupts the wait, or [DEADLOCK] if the wait
+/// could never end.
+pub(super) fn sys_mutex_lock(mutex_id: usize) -> isize {
+    to_result(acquire_current_resource(Resource::Mutex(mutex_id)))
+}
+
+/// Unlocks the mutex `mutex_id` held by the current thread.
+///
+/// Returns 0 on success, or -1 if the mutex does not exist
+/// or is not held by the current thread.
+pub(super) fn sys_mutex_unlock(mutex_id: usize) -> isize {
+    to_result(release_current_resource(Resource::Mutex(mutex_id)))
+}
+
+/// Creates a semaphore with `count` units in the current
+/// process.
+///
+/// Returns the semaphore ID.
+pub(super) fn sys_semaphore_create(count: usize) -> isize {
+    create_current_semaphore(count) as isize
+}
+
+/// Gives back a unit of the semaphore `sem_id` of the
+/// current process.
+///
+/// Returns 0 on success, or -1 if the semaphore does not
+/// exist.
+pub(super) fn sys_semaphore_up(sem_id: usize) -> isize {
+    to_result(release_current_resource(Resource::Semaphore(sem_id)))
+}
+
+/// Takes a unit of the semaphore `sem_id` of the current
+/// process, blocking until there is one.
+///
+/// Returns 0 on success, -1 if the semaphore does not exist
+/// or a signal interrupts the wait, or [DEADLOCK] if the
+/// wait could never end.
+pub(super) fn sys_semaphore_down(sem_id: usize) -> isize {
+    to_result(acquire_current_resource(Resource::Semaphore(sem_id)))
+}
+
+/// Creates a condition variable in the current process.
+///
+/// Returns the condition variable ID.
+pub(super) fn sys_condvar_create() -> isize {
+    create_current_condvar() as isize
+}
+
+/// Wakes a thread waiting on the condition variable
+/// `condvar_id` of the current process.
+///
+/// Returns 0 on success, or -1 if it does not exist.
+pub(super) fn sys_condvar_signal(condvar_id: usize) -> isize {
+    to_result(signal_current_condvar(condvar_id))
+}
+
+/// Unlocks the mutex `mutex_id` and blocks until the
+/// condition variable `condvar_id` is signaled, and then
+/// locks the mutex again.
+///
+/// Returns 0 on success, -1 if either does not exist or the
+/// mutex is not held, or [DEADLOCK] if locking the mutex
+/// again could never end, in which case it stays unlocked.
+/// It also returns -1 with the mutex unlocked if a signal
+/// interrupts locking it.
+pub(super) fn sys_condvar_wait(condvar_id: usize, mutex_id: usize) -> isize {
+    to_result(wait_current_condvar(condvar_id, mutex_id))
+}
+
+/// Enables the deadlock detection of the current process if
+/// `is_enabled` is 1, or disables it if 0. Then the mutexes
+/// and semaphores fail with [DEADLOCK] instead of blocking
+/// if no order lets all the waiting threads go on. It
+/// assumes that only the holders give back the units.
+///
+/// Returns 0 on success, or -1 if `is_enabled` is invalid.
+pub(super) fn sys_enable_deadlock_detect(is_enabled: usize) -> isize {
+    let task_id = get_current_task_id();
+
+    let is_enabled = match is_enabled {
+        0 => false,
+        1 => true,
+        _ => {
+            warn!(
+                "Task {:?}: Invalid deadlock detection flag {}",
+                task_id, is_enabled
+            );
+            return -1;
+        }
+    };
+    set_current_deadlock_detect(is_enabled);
+    info!(
+        "Task {:?}: Set the deadlock detection to {}",
+        task_id, is_enabled
+    );
+    0
+}
+
+fn to_result(result: Result<(), SyncError>) -> isize {
+    match result {
+        Ok(()) => 0,
+        Err(SyncError::Deadlock(resource)) => {
+            warn!(
+                "Task {:?}: Deadlock detected on {:?}",
+                get_current_task_id(),
+                resource
+            );
+            DEADLOCK
+        }
+        Err(err) => {
+            warn!(
+                "Task {:?}: Failed to operate on a sync object, err={:?}",
+                get_current_task_id(),
+                err
+            );
+            -1
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use crate::syscall::{read_from_user, write_to_user};
+use crate::task::prelude::{get_current_task_id, has_current_pending_signal, sleep_current_task};
+use crate::timer::{self, TimeSpec, TimeVal};
+use crate::{info, log, warn};
+
+/// The time since the Unix epoch, which follows the RTC.
+const CLOCK_REALTIME: usize = 0;
+/// The time since system start, which follows the time
+/// counter and never goes backwards.
+const CLOCK_MONOTONIC: usize = 1;
+
+/// Writes the current time of the clock `clock_id` to `tp`.
+///
+/// Returns 0 on success, or -1 if the `clock_id` is not
+/// supported or `tp` cannot be written.
+pub(super) fn sys_clock_gettime(clock_id: usize, tp: *mut TimeSpec) -> isize {
+    let time = match clock_id {
+        CLOCK_REALTIME => timer::read_real_time(),
+        CLOCK_MONOTONIC => timer::read_monotonic_time(),
+        _ => {
+            warn!(
+                "Task {:?}: Unsupported clock {}",
+                get_current_task_id(),
+                clock_id
+            );
+            return -1;
+        }
+    };
+    if write_to_user(tp, &time) { 0 } else { -1 }
+}
+
+/// Writes the current real time to `tv` as gettimeofday.
+/// The obsolete time zone argument is ignored.
+///
+/// Returns 0 on success, or -1 if `tv` cannot be written.
+pub(super) fn sys_get_time(tv: *mut TimeVal) -> isize {
+    if write_to_user(tv, &TimeVal::from(timer::read_real_time())) {
+        0
+    } else {
+        -1
+    }
+}
+
+/// Suspends the current task for at least the duration
+/// `req` points to, unless a signal interrupts it. The
+/// remaining duration is not reported.
+///
+/// Returns 0 on success, or -1 if `req` cannot be read or
+/// is invalid, or if a signal interrupts the sleep.
+pub(super) fn sys_nanosleep(req: *const TimeSpec) -> isize {
+    let task_id = get_current_task_id();
+    let Some(duration) = read_from_user(req) else {
+        return -1;
+    };
+
+    if !duration.is_valid() {
+        warn!("Task {:?}: Invalid duration {:?}", task_id, duration);
+        return -1;
+    }
+
+    info!("Task {:?}: Sleep for {:?}", task_id, duration);
+    let wakeup_time = timer::read_time().saturating_add(duration.to_ticks());
+    sleep_current_task(wakeup_time);
+    if timer::read_time() < wakeup_time && has_current_pending_signal() {
+        info!("Task {:?}: Sleep interrupted by a signal", task_id);
+        return -1;
+    }
+    0
+}
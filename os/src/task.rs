@@ -1,116 +1,782 @@
 extern crate alloc;
 
 mod apps;
+mod futex;
 pub(crate) mod prelude;
+mod process;
+mod processor;
+mod sched;
+mod signal;
+mod sleep;
 mod state;
+mod sync_objects;
 
+use alloc::boxed::Box;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::arch::{asm, global_asm};
-use core::ptr::null_mut;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
-use crate::mm::prelude::VMSpace;
+use riscv::regs::scounteren;
+
+use crate::fs::prelude::poll_console_without_irq;
+use crate::mm::prelude::{KernelStack, VMError, VMSpace, get_kernel_satp};
+use crate::random;
 use crate::sbi::shutdown;
 use crate::sync::spin::SpinLock;
 use crate::timer;
 use crate::trap::{self, TrapContext};
-use crate::{debug, info, log};
+use crate::{debug, info, log, warn};
+
+use crate::task::apps::{find_app_elf, get_app_names};
+use crate::task::process::ProcessControlBlock;
+use crate::task::processor::{get_idle_context_ptr, pick_next_task, push_ready_task};
+use crate::task::sched::{MIN_PRIORITY, ReadyTask};
+use crate::task::sleep::{get_earliest_wakeup_time, pop_expired_task, push_sleeping_task};
+use crate::task::state::{
+    DEFAULT_USER_COUNTERS, TaskContext, TaskControlBlock, TaskState, TaskStatistics, alloc_task_id,
+};
+
+// The registry of all tasks, while the ready ones are also
+// queued in the run queues of the harts. It should be
+// locked before any run queue when both are needed.
+//
+// The TCBs are boxed so that their [TaskContext]s stay
+// at fixed addresses while being switched.
+static ALL_TASKS: SpinLock<Vec<Box<TaskControlBlock>>> = SpinLock::new(Vec::new());
 
-use crate::task::apps::{get_app_elf, get_total_apps};
-use crate::task::state::{TaskContext, TaskControlBlock, TaskState, TaskStatistics};
+/// The name of the app that becomes the init task, i.e.,
+/// the parent of the other apps and of the orphan tasks.
+const INIT_APP_NAME: &str = "initproc";
+static INIT_TASK_ID: AtomicUsize = AtomicUsize::new(0);
 
-// The design should be revisited if the environment
-// is not single-threaded, not single-core, or allows
-// interrupts when the kernel is running.
-static ALL_TASKS: SpinLock<Vec<TaskControlBlock>> = SpinLock::new(Vec::new());
+/// The exit code of a task terminated by a signal.
+const KILLED_EXIT_CODE: isize = -1;
 
 global_asm!(include_str!("task/switch.S"));
 unsafe extern "C" {
     unsafe fn __switch(curr_context: *mut TaskContext, next_context: *const TaskContext);
 }
 
-pub(super) fn start() -> ! {
-    for i in 0..get_total_apps() {
-        add_task(get_app_elf(i));
+/// Adds the init task and the other apps as its children
+/// to the run queue of the hart `hart_id`, from which the
+/// other harts steal.
+pub(super) fn add_initial_tasks(hart_id: usize) {
+    let init_elf = find_app_elf(INIT_APP_NAME).expect("Cannot find the init app");
+    let init_id = add_task(&init_elf, &[INIT_APP_NAME], None, hart_id);
+    INIT_TASK_ID.store(init_id, Ordering::Relaxed);
+
+    for name in get_app_names() {
+        if name == INIT_APP_NAME {
+            continue;
+        }
+        if let Some(elf_bytes) = find_app_elf(&name) {
+            add_task(&elf_bytes, &[&name], Some(init_id), hart_id);
+        }
     }
-    run_next_task();
-    unreachable!()
 }
 
-fn add_task(elf_bytes: &[u8]) {
-    // Create task vm space and tcb
-    let vm_space = VMSpace::new_user(elf_bytes).expect("Failed to create user vm space");
-    let satp = vm_space.get_satp();
+/// Adds a process for the app `elf_bytes` with the `args`
+/// as a child of the `parent_id` to the run queue of the
+/// hart `hart_id`, and returns the task ID of its main
+/// thread, i.e., its process ID.
+fn add_task(elf_bytes: &[u8], args: &[&str], parent_id: Option<usize>, hart_id: usize) -> usize {
+    // Create task vm space, process and tcb
+    let mut vm_space = VMSpace::new_user(elf_bytes).expect("Failed to create user vm space");
+    let (user_sp, argv, envp) = vm_space
+        .push_initial_stack(args, &[], &generate_random_bytes())
+        .expect("Failed to push initial user stack");
     let entry = vm_space.get_entry_addr();
-    let user_sp = vm_space.get_u_stack_end();
-    let kernel_sp = vm_space.get_k_stack_end() - size_of::<TrapContext>();
+    let u_stack_end = vm_space.get_u_stack_end();
+    let kernel_stack = KernelStack::new().expect("Failed to allocate kernel stack");
 
+    let task_id = alloc_task_id();
+    let process = ProcessControlBlock::new(task_id, parent_id);
     let tcb = TaskControlBlock::new_ready(
-        vm_space,
-        trap::__restore_u_ctx as usize,
-        kernel_sp,
-        kernel_sp,
-        satp,
+        task_id,
+        Arc::new(SpinLock::new(process)),
+        Arc::new(SpinLock::new(vm_space)),
+        kernel_stack,
+        u_stack_end,
     );
 
     // Push initial trap context to kernel stack
-    let trap_context = TrapContext::new_initial(entry, user_sp, tcb.get_task_id());
-    unsafe { (kernel_sp as *mut TrapContext).write_volatile(trap_context) };
+    let trap_context = TrapContext::new_initial(entry, user_sp, [args.len(), argv, envp], task_id);
+    unsafe { tcb.get_trap_context_ptr().write_volatile(trap_context) };
 
-    // Push tcb to the task list
-    ALL_TASKS.lock().push(tcb);
+    // Push tcb to the task list and link it to its parent
+    let mut all_tasks = ALL_TASKS.lock();
+    if let Some(parent_id) = parent_id {
+        let parent = all_tasks
+            .iter()
+            .find(|tcb| tcb.get_task_id() == parent_id)
+            .expect("Cannot find the parent task");
+        parent.get_process().lock().add_child_id(task_id);
+    }
+    let task = ReadyTask {
+        task_id,
+        priority: tcb.get_priority(),
+        pass: 0,
+    };
+    all_tasks.push(Box::new(tcb));
+    push_ready_task(hart_id, task);
+    task_id
 }
 
-/// Searches for and runs a ready task, or shuts down
-/// if no task is found.
-pub(crate) fn run_next_task() {
+/// Creates a child process of the current task, whose
+/// main thread resumes from the same [TrapContext] but with
+/// a copy-on-write copy of the current task's [VMSpace].
+/// The other threads are not copied. Returns the task ID
+/// of the child or the corresponding [VMError].
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn fork_current_task() -> Result<usize, VMError> {
+    let task_id = get_current_task_id();
+    let kernel_stack = KernelStack::new()?;
+
+    let mut all_tasks = ALL_TASKS.lock();
+    let parent = all_tasks
+        .iter()
+        .find(|tcb| tcb.get_task_id() == task_id)
+        .unwrap();
+
+    // Create child vm space, process and tcb
+    let vm_space = VMSpace::new_forked(&mut parent.get_vm_space().lock())?;
+    let child_id = alloc_task_id();
+    let process = parent.get_process().lock().new_forked(child_id);
+
+    let mut tcb = TaskControlBlock::new_ready(
+        child_id,
+        Arc::new(SpinLock::new(process)),
+        Arc::new(SpinLock::new(vm_space)),
+        kernel_stack,
+        parent.get_u_stack_end(),
+    );
+    let task = ReadyTask {
+        task_id: child_id,
+        priority: parent.get_priority(),
+        pass: parent.get_statistics().get_sched_pass(),
+    };
+    tcb.set_priority(task.priority);
+    tcb.set_user_counters(parent.get_user_counters());
+    *tcb.get_signals_mut() = parent.get_signals().new_forked();
+    parent.get_process().lock().add_child_id(child_id);
+
+    // Push the copied trap context to the child's kernel stack
+    let parent_trap_context = unsafe { parent.get_trap_context_ptr().as_ref() }.unwrap();
+    let trap_context = TrapContext::new_forked(parent_trap_context, child_id);
+    unsafe { tcb.get_trap_context_ptr().write_volatile(trap_context) };
+
+    all_tasks.push(Box::new(tcb));
+    push_ready_task(get_current_hart_id(), task);
+    Ok(child_id)
+}
+
+/// Creates a thread in the process of the current task,
+/// which shares its [VMSpace] and starts from the `entry`
+/// with the `arg` in a0 on a new user stack. Returns the
+/// task ID of the thread or the corresponding [VMError].
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn create_current_thread(entry: usize, arg: usize) -> Result<usize, VMError> {
+    let task_id = get_current_task_id();
+    let kernel_stack = KernelStack::new()?;
+
+    let mut all_tasks = ALL_TASKS.lock();
+    let curr = all_tasks
+        .iter()
+        .find(|tcb| tcb.get_task_id() == task_id)
+        .unwrap();
+
+    let vm_space = curr.get_vm_space().clone();
+    let u_stack_end = vm_space.lock().add_thread_stack_area()?;
+    let thread_id = alloc_task_id();
+    let mut tcb = TaskControlBlock::new_ready(
+        thread_id,
+        curr.get_process().clone(),
+        vm_space,
+        kernel_stack,
+        u_stack_end,
+    );
+    let task = ReadyTask {
+        task_id: thread_id,
+        priority: curr.get_priority(),
+        pass: curr.get_statistics().get_sched_pass(),
+    };
+    tcb.set_priority(task.priority);
+    tcb.set_user_counters(curr.get_user_counters());
+    *tcb.get_signals_mut() = curr.get_signals().new_forked();
+    // A thread created while the process exits goes with it.
+    if curr.get_process().lock().get_exit_code().is_some() {
+        tcb.get_signals_mut().add_kill();
+    }
+
+    // The arguments of main are in the same registers.
+    let trap_context = TrapContext::new_initial(entry, u_stack_end, [arg, 0, 0], thread_id);
+    unsafe { tcb.get_trap_context_ptr().write_volatile(trap_context) };
+
+    all_tasks.push(Box::new(tcb));
+    push_ready_task(get_current_hart_id(), task);
+    Ok(thread_id)
+}
+
+/// Replaces the image of the current task with the app
+/// `elf_bytes`. The current task keeps its task ID and
+/// kernel stack, and its [TrapContext] is rewritten to
+/// start from the app's entry with the `args` and `envs`
+/// on a new user stack. The other threads of the process
+/// should have exited.
+///
+/// Returns argc, which the caller should leave in a0, or
+/// returns the corresponding [VMError] and the current
+/// task is left unchanged.
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn exec_current_task(
+    elf_bytes: &[u8],
+    args: &[&str],
+    envs: &[&str],
+) -> Result<usize, VMError> {
+    let task_id = get_current_task_id();
+
+    let mut vm_space = VMSpace::new_user(elf_bytes)?;
+    let (user_sp, argv, envp) =
+        vm_space.push_initial_stack(args, envs, &generate_random_bytes())?;
+    let entry = vm_space.get_entry_addr();
+    let u_stack_end = vm_space.get_u_stack_end();
+
+    // Switch to the new vm space before the previous one
+    // and its page tables are dropped.
+    vm_space.activate(get_current_hart_id());
+
+    let mut all_tasks = ALL_TASKS.lock();
+    let tcb = all_tasks
+        .iter_mut()
+        .find(|tcb| tcb.get_task_id() == task_id)
+        .unwrap();
+    let prev_vm_space = tcb.replace_vm_space(Arc::new(SpinLock::new(vm_space)), u_stack_end);
+    tcb.get_process()
+        .lock()
+        .get_sig_actions_mut()
+        .reset_handlers();
+    let trap_context_ptr = tcb.get_trap_context_ptr();
+    drop(all_tasks);
+    drop(prev_vm_space);
+
+    // Rewrite the trap context in place
+    let trap_context = TrapContext::new_initial(entry, user_sp, [args.len(), argv, envp], task_id);
+    unsafe { trap_context_ptr.write_volatile(trap_context) };
+    Ok(args.len())
+}
+
+/// Returns 16 bytes for the [AT_RANDOM] entry of a new
+/// user stack. They are pseudo-random and not suitable for
+/// cryptographic use.
+///
+/// [AT_RANDOM]: https://man7.org/linux/man-pages/man3/getauxval.3.html
+fn generate_random_bytes() -> [u8; 16] {
+    let mut result = [0; 16];
+    random::fill_bytes(&mut result);
+    result
+}
+
+/// Turns the current task into a zombie with `exit_code`.
+/// If it is the main thread, its process exits as well, see
+/// [exit_current_process]. Otherwise, its user stack is
+/// released, and the [TaskControlBlock] is kept until
+/// another thread waits for it or the process is reaped.
+///
+/// This function panics if the thread is not running a
+/// task, or if the init task exits with living children.
+pub(crate) fn exit_current_task(exit_code: isize) {
+    exit_current(exit_code, false);
+}
+
+/// Makes the process of the current task exit with the
+/// `exit_code` unless it is already exiting, and turns the
+/// current task into a zombie. The other threads are sent
+/// [SIGKILL], which wakes those blocked or sleeping, so they
+/// exit once they return to user space.
+///
+/// Once its last thread exits, the process closes its files
+/// and hands its children over to the init task. Its
+/// threads are kept until the parent reaps it.
+///
+/// This function panics if the thread is not running a
+/// task, or if the init task exits with living children.
+///
+/// [SIGKILL]: signal::SIGKILL
+fn exit_current_process(exit_code: isize) {
+    exit_current(exit_code, true);
+}
+
+fn exit_current(exit_code: isize, exits_process: bool) {
+    let task_id = get_current_task_id();
+    let init_id = INIT_TASK_ID.load(Ordering::Relaxed);
+
     let mut all_tasks = ALL_TASKS.lock();
+    let tcb = all_tasks
+        .iter_mut()
+        .find(|tcb| tcb.get_task_id() == task_id)
+        .unwrap();
+
+    let state = tcb.get_state();
+    if state != TaskState::Running {
+        panic!("Task {task_id}: Expected running but got {state:?}")
+    }
+    tcb.set_state(TaskState::Zombie);
+    tcb.set_exit_code(exit_code);
+    let process = tcb.get_process().clone();
+    let vm_space = tcb.get_vm_space().clone();
+    let u_stack_end = tcb.get_u_stack_end();
 
-    let mut curr_context = null_mut();
-    if let Some(task_id) = try_get_current_task_id()
-        && let Some(mut tcb) = take_task_tcb(&mut all_tasks, task_id)
-    {
-        curr_context = tcb.get_context_mut() as *mut TaskContext;
-        let state = tcb.get_state();
-        if state == TaskState::Ready {
-            all_tasks.push(tcb);
-        } else if state == TaskState::Running {
-            panic!("Attempt to switch task {task_id} but its state is running")
+    let process_id = process.lock().get_process_id();
+    let exits_process = exits_process || task_id == process_id;
+    if exits_process && process.lock().get_exit_code().is_none() {
+        process.lock().set_exit_code(exit_code);
+    }
+    process.lock().get_sync_objects_mut().remove_thread(task_id);
+
+    let mut is_last = true;
+    for tcb in all_tasks.iter_mut() {
+        if Arc::ptr_eq(tcb.get_process(), &process) && tcb.get_state() != TaskState::Zombie {
+            is_last = false;
+            if exits_process {
+                tcb.get_signals_mut().add_kill();
+                signal::wake_interrupted_task(tcb);
+            }
         }
     }
 
-    if let Some(task_id) = get_next_task_id(&all_tasks) {
-        let mut tcb = take_task_tcb(&mut all_tasks, task_id).unwrap();
-        let next_context = tcb.get_context() as *const TaskContext;
+    let mut files = Vec::new();
+    let mut init_waiters = None;
+    if is_last {
+        let mut process = process.lock();
+        files = process.get_fd_table_mut().close_all();
+        let children_ids = process.take_children_ids();
+        drop(process);
+
+        if !children_ids.is_empty() && process_id == init_id {
+            panic!("The init task exited with children {children_ids:?}")
+        }
+        for tcb in all_tasks.iter() {
+            if children_ids.contains(&tcb.get_task_id()) {
+                tcb.get_process().lock().set_parent_id(Some(init_id));
+            } else if tcb.get_task_id() == init_id {
+                let mut init = tcb.get_process().lock();
+                children_ids.iter().for_each(|&id| init.add_child_id(id));
+                init_waiters = Some(init.get_exit_waiters().clone());
+            }
+        }
+    }
+    drop(all_tasks);
 
-        let time = timer::read_time_ms();
-        debug!(
-            "Task {} starts at {}.{:03} seconds since system start",
-            task_id,
-            time / 1000,
-            time % 1000,
+    // Drop the files after the task registry is unlocked,
+    // since closing a pipe wakes the tasks waiting on it.
+    drop(files);
+    // The init task may be waiting for the children handed
+    // over, some of which may have exited.
+    if let Some(init_waiters) = init_waiters {
+        init_waiters.wake_all();
+    }
+
+    // The other stacks go with the vm space once the process
+    // is reaped.
+    if exits_process {
+        return;
+    }
+    if let Err(err) = vm_space.lock().remove_thread_stack_area(u_stack_end) {
+        warn!(
+            "Task {}: Failed to remove the user stack, err={:?}",
+            task_id, err
         );
-        tcb.set_state(TaskState::Running);
-        tcb.record_run_start();
-        all_tasks.push(tcb);
+    }
+}
 
-        // The design should be revisited if the environment
-        // is not single-threaded, not single-core, or allows
-        // interrupts when the kernel is running.
-        drop(all_tasks);
+/// The outcome of [wait_current_task_child] and
+/// [wait_current_thread].
+pub(crate) enum WaitResult {
+    /// (task_id, exit_code) of the exited child or thread.
+    Exited(usize, isize),
+    /// The current task has no matching child or thread.
+    NoChild,
+    /// A signal has interrupted the wait.
+    Interrupted,
+}
 
-        timer::set_next_timer_interrupt();
-        unsafe { __switch(curr_context, next_context) };
-    } else {
-        info!("No more tasks to run, bye bye.");
-        shutdown(false)
+/// Blocks the current task until a child process of it
+/// whose process ID is `pid`, or any child if `pid` is -1,
+/// has exited, and returns the child without reaping it,
+/// see [reap_current_task_child].
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn wait_current_task_child(pid: isize) -> WaitResult {
+    wait_current_exit(|| find_exited_child(pid))
+}
+
+/// Blocks the current task until the thread `tid` in its
+/// process has exited, and returns the thread without
+/// reaping it, see [reap_current_thread]. The main thread
+/// cannot be waited for, since it only exits with the
+/// process.
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn wait_current_thread(tid: usize) -> WaitResult {
+    wait_current_exit(|| find_exited_thread(tid))
+}
+
+/// Blocks the current task on the exit waiters of its
+/// process until `find` returns an outcome, i.e., not
+/// [None], or a signal arrives.
+fn wait_current_exit(mut find: impl FnMut() -> Option<WaitResult>) -> WaitResult {
+    let mut waiters = None;
+    update_process(get_current_task_id(), |process| {
+        waiters = Some(process.get_exit_waiters().clone());
+    });
+    let waiters = waiters.unwrap();
+
+    loop {
+        let mut result = None;
+        let mut is_interrupted = false;
+        waiters.wait_if(|| {
+            result = find();
+            is_interrupted = signal::has_current_pending_signal();
+            result.is_none() && !is_interrupted
+        });
+        if let Some(result) = result {
+            return result;
+        }
+        if is_interrupted {
+            return WaitResult::Interrupted;
+        }
     }
 }
 
-fn get_next_task_id(tasks: &Vec<TaskControlBlock>) -> Option<usize> {
-    tasks
+/// Returns the exited child of the current task matching
+/// the `pid` as [wait_current_task_child], or [None] if the
+/// matching children are still alive.
+fn find_exited_child(pid: isize) -> Option<WaitResult> {
+    let task_id = get_current_task_id();
+
+    let all_tasks = ALL_TASKS.lock();
+    let process = all_tasks
         .iter()
-        .position(|tcb| tcb.get_state() == TaskState::Ready)
-        .map(|i| tasks[i].get_task_id())
+        .find(|tcb| tcb.get_task_id() == task_id)
+        .unwrap()
+        .get_process();
+
+    let is_matched = |id: usize| pid == -1 || pid == id as isize;
+    let children_ids = process
+        .lock()
+        .get_children_ids()
+        .iter()
+        .copied()
+        .filter(|&id| is_matched(id))
+        .collect::<Vec<_>>();
+    if children_ids.is_empty() {
+        return Some(WaitResult::NoChild);
+    }
+
+    // A child has exited once all its threads are zombies
+    // and have been switched out.
+    children_ids.iter().find_map(|&child_id| {
+        let child = all_tasks
+            .iter()
+            .find(|tcb| tcb.get_task_id() == child_id)?
+            .get_process();
+        let has_exited = all_tasks
+            .iter()
+            .filter(|tcb| Arc::ptr_eq(tcb.get_process(), child))
+            .all(|tcb| tcb.get_state() == TaskState::Zombie && !tcb.is_on_hart());
+        has_exited.then(|| WaitResult::Exited(child_id, child.lock().get_exit_code().unwrap()))
+    })
+}
+
+/// Returns the exited thread `tid` as [wait_current_thread],
+/// or [None] if it is still alive.
+fn find_exited_thread(tid: usize) -> Option<WaitResult> {
+    let task_id = get_current_task_id();
+
+    let all_tasks = ALL_TASKS.lock();
+    let process = all_tasks
+        .iter()
+        .find(|tcb| tcb.get_task_id() == task_id)
+        .unwrap()
+        .get_process();
+    let process_id = process.lock().get_process_id();
+    if tid == task_id || tid == process_id {
+        return Some(WaitResult::NoChild);
+    }
+
+    let Some(thread) = all_tasks
+        .iter()
+        .find(|tcb| tcb.get_task_id() == tid && Arc::ptr_eq(tcb.get_process(), process))
+    else {
+        return Some(WaitResult::NoChild);
+    };
+    let has_exited = thread.get_state() == TaskState::Zombie && !thread.is_on_hart();
+    has_exited.then(|| WaitResult::Exited(tid, thread.get_exit_code()))
+}
+
+/// Reaps the exited child process `child_id` of the current
+/// task, whose threads and [VMSpace] are released. Returns
+/// whether it is reaped, which it is not if another thread
+/// has reaped it first.
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn reap_current_task_child(child_id: usize) -> bool {
+    let task_id = get_current_task_id();
+
+    let mut all_tasks = ALL_TASKS.lock();
+    let process = all_tasks
+        .iter()
+        .find(|tcb| tcb.get_task_id() == task_id)
+        .unwrap()
+        .get_process()
+        .clone();
+    if !process.lock().get_children_ids().contains(&child_id) {
+        return false;
+    }
+    let child = all_tasks
+        .iter()
+        .find(|tcb| tcb.get_task_id() == child_id)
+        .unwrap()
+        .get_process()
+        .clone();
+
+    let mut threads = Vec::new();
+    let mut i = 0;
+    while i < all_tasks.len() {
+        if Arc::ptr_eq(all_tasks[i].get_process(), &child) {
+            threads.push(all_tasks.swap_remove(i));
+        } else {
+            i += 1;
+        }
+    }
+    process.lock().remove_child_id(child_id);
+    drop(all_tasks);
+    drop(threads);
+    true
+}
+
+/// Reaps the exited thread `tid` in the process of the
+/// current task, whose kernel stack is then released.
+/// Returns whether it is reaped, which it is not if another
+/// thread has reaped it first.
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn reap_current_thread(tid: usize) -> bool {
+    let task_id = get_current_task_id();
+
+    let mut all_tasks = ALL_TASKS.lock();
+    let process = all_tasks
+        .iter()
+        .find(|tcb| tcb.get_task_id() == task_id)
+        .unwrap()
+        .get_process()
+        .clone();
+    let Some(index) = all_tasks
+        .iter()
+        .position(|tcb| tcb.get_task_id() == tid && Arc::ptr_eq(tcb.get_process(), &process))
+    else {
+        return false;
+    };
+
+    let thread = all_tasks.swap_remove(index);
+    drop(all_tasks);
+    drop(thread);
+    true
+}
+
+/// Returns the number of threads in the process of the
+/// current task that have not exited, including itself.
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn count_current_threads() -> usize {
+    let task_id = get_current_task_id();
+
+    let all_tasks = ALL_TASKS.lock();
+    let process = all_tasks
+        .iter()
+        .find(|tcb| tcb.get_task_id() == task_id)
+        .unwrap()
+        .get_process();
+    all_tasks
+        .iter()
+        .filter(|tcb| Arc::ptr_eq(tcb.get_process(), process))
+        .filter(|tcb| tcb.get_state() != TaskState::Zombie)
+        .count()
+}
+
+/// Switches the current task out to the scheduling loop
+/// of its hart, which then runs the next ready task. The
+/// current task should have left [TaskState::Running].
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn run_next_task() {
+    let task_id = get_current_task_id();
+    let hart_id = get_current_hart_id();
+
+    let mut all_tasks = ALL_TASKS.lock();
+    let tcb = all_tasks
+        .iter_mut()
+        .find(|tcb| tcb.get_task_id() == task_id)
+        .unwrap();
+    if tcb.get_state() == TaskState::Running {
+        panic!("Attempt to switch task {task_id} but its state is running")
+    }
+    let curr_context = tcb.get_context_mut() as *mut TaskContext;
+    drop(all_tasks);
+
+    // No other hart can switch to the task until the loop
+    // has requeued it after the switch, and a zombie cannot
+    // be reaped until then either. The loop runs with the
+    // interrupts off.
+    trap::disable_kernel_interrupts();
+    unsafe { __switch(curr_context, get_idle_context_ptr(hart_id)) };
+    trap::enable_kernel_interrupts();
+}
+
+/// Runs the scheduling loop of the hart `hart_id`, which
+/// repeatedly wakes the due sleeping tasks and the readers
+/// of a polled console, picks a ready task and switches to
+/// it until the task gives up the hart. It shuts down the
+/// machine once all tasks have exited.
+pub(super) fn run_tasks(hart_id: usize) -> ! {
+    let idle_context = get_idle_context_ptr(hart_id);
+    unsafe { idle_context.write(TaskContext::new_initial(0, 0, 0, get_kernel_satp())) };
+
+    loop {
+        wake_sleeping_tasks(hart_id);
+        poll_console_without_irq();
+        let Some(task) = pick_next_task(hart_id) else {
+            if !has_alive_tasks() {
+                info!("No more tasks to run, bye bye.");
+                shutdown(false)
+            }
+
+            // Wait for the earliest wakeup, for a time slice to
+            // retry stealing from the other harts, or for a
+            // device. With the interrupts disabled, wfi returns
+            // on a pending one without trapping, so the external
+            // ones are handled here.
+            timer::set_next_timer_interrupt(get_earliest_wakeup_time());
+            unsafe { asm!("wfi") };
+            trap::handle_external_interrupts(hart_id);
+            continue;
+        };
+
+        let next_context = switch_in_task(hart_id, task);
+        timer::set_next_timer_interrupt(get_earliest_wakeup_time());
+        unsafe { __switch(idle_context, next_context) };
+        switch_out_task(hart_id, task.task_id);
+    }
+}
+
+/// Marks the picked `task` as running on the hart and
+/// returns its [TaskContext] to switch to.
+fn switch_in_task(hart_id: usize, task: ReadyTask) -> *const TaskContext {
+    let mut all_tasks = ALL_TASKS.lock();
+    let tcb = all_tasks
+        .iter_mut()
+        .find(|tcb| tcb.get_task_id() == task.task_id)
+        .expect("Cannot find the task picked by the scheduler");
+
+    let state = tcb.get_state();
+    if state != TaskState::Ready {
+        panic!("Task {}: Expected ready but got {:?}", task.task_id, state)
+    }
+
+    let time = timer::read_time_ms();
+    debug!(
+        "Task {} starts at {}.{:03} seconds since system start, hart={}",
+        task.task_id,
+        time / 1000,
+        time % 1000,
+        hart_id,
+    );
+    tcb.set_state(TaskState::Running);
+    tcb.set_on_hart(true);
+    tcb.record_run_start(hart_id, task.pass);
+    tcb.prepare_vm_space(hart_id);
+    scounteren::write(tcb.get_user_counters());
+
+    let context = tcb.get_context();
+    unsafe { (context.get_tp() as *mut TrapContext).as_mut() }
+        .unwrap()
+        .set_hart_id(hart_id);
+    context as *const TaskContext
+}
+
+/// Releases the task `task_id` that has just been switched
+/// out of the hart, and requeues it if it is still ready.
+/// If it has exited, the threads of its process and its
+/// parent waiting for an exit are woken, since it can only
+/// be reaped from now on.
+fn switch_out_task(hart_id: usize, task_id: usize) {
+    let mut all_tasks = ALL_TASKS.lock();
+    let tcb = all_tasks
+        .iter_mut()
+        .find(|tcb| tcb.get_task_id() == task_id)
+        .unwrap();
+    tcb.set_on_hart(false);
+
+    match tcb.get_state() {
+        TaskState::Ready => {
+            let task = ReadyTask {
+                task_id,
+                priority: tcb.get_priority(),
+                pass: tcb.get_statistics().get_sched_pass(),
+            };
+            push_ready_task(hart_id, task);
+        }
+        // A sleeping or blocked task is queued once it is woken.
+        TaskState::Sleeping | TaskState::Blocked => {}
+        // A zombie is kept for its parent to reap, but its
+        // context is never restored.
+        TaskState::Zombie => {}
+        TaskState::Running => panic!("Task {task_id} is switched out but its state is running"),
+    }
+    if tcb.get_state() != TaskState::Zombie {
+        return;
+    }
+
+    let process = tcb.get_process().lock();
+    let waiters = process.get_exit_waiters().clone();
+    let parent_id = process.get_parent_id();
+    drop(process);
+    let parent_waiters = parent_id.and_then(|parent_id| {
+        let parent = all_tasks
+            .iter()
+            .find(|tcb| tcb.get_task_id() == parent_id)?;
+        Some(parent.get_process().lock().get_exit_waiters().clone())
+    });
+    drop(all_tasks);
+
+    // The waiters check again whether what they wait for has
+    // exited.
+    waiters.wake_all();
+    if let Some(parent_waiters) = parent_waiters {
+        parent_waiters.wake_all();
+    }
+}
+
+/// Returns whether any task has not exited, or is still
+/// being switched out.
+fn has_alive_tasks() -> bool {
+    ALL_TASKS
+        .lock()
+        .iter()
+        .any(|tcb| tcb.get_state() != TaskState::Zombie || tcb.is_on_hart())
 }
 
 /// Returns the task ID of the current task based on
@@ -125,28 +791,33 @@ pub(crate) fn get_current_task_id() -> usize {
 /// Returns the task ID of the current task based on
 /// the current thread pointer, i.e., tp., or [None]
 /// if no task is running.
-fn try_get_current_task_id() -> Option<usize> {
-    let mut tp: usize;
-    unsafe { asm!("mv {}, tp", out(reg) tp) };
+pub(crate) fn try_get_current_task_id() -> Option<usize> {
+    get_current_trap_context().map(TrapContext::get_task_id)
+}
 
-    if tp == 0 {
-        return None;
-    }
+/// Returns the ID of the hart running the current task
+/// based on the current thread pointer, i.e., tp.
+///
+/// # Panic
+/// This function panics if no task is running.
+pub(crate) fn get_current_hart_id() -> usize {
+    get_current_trap_context()
+        .map(TrapContext::get_hart_id)
+        .expect("This hart is not running a task.")
+}
 
-    let result = unsafe { (tp as *const TrapContext).as_ref() }
-        .unwrap()
-        .get_task_id();
-    Some(result)
+/// Returns the [TrapContext] of the current task, which
+/// the tp points to, or [None] if no task is running.
+fn get_current_trap_context() -> Option<&'static TrapContext> {
+    unsafe { get_current_trap_context_ptr().as_ref() }
 }
 
-/// Takes the [TaskControlBlock] with `task_id` from
-/// `tasks`, or returns [None] if no matching task is
-/// found.
-fn take_task_tcb(tasks: &mut Vec<TaskControlBlock>, task_id: usize) -> Option<TaskControlBlock> {
-    let index = tasks.iter().position(|tcb| tcb.get_task_id() == task_id)?;
-    let rand_index = timer::read_time() % tasks.len();
-    tasks.swap(index, rand_index);
-    Some(tasks.swap_remove(rand_index))
+/// Returns the pointer to the [TrapContext] of the current
+/// task, i.e., the tp, which is null if no task is running.
+fn get_current_trap_context_ptr() -> *mut TrapContext {
+    let mut tp: usize;
+    unsafe { asm!("mv {}, tp", out(reg) tp) };
+    tp as *mut TrapContext
 }
 
 /// Updates the [TaskControlBlock] associated with `task_id`
@@ -155,13 +826,46 @@ fn take_task_tcb(tasks: &mut Vec<TaskControlBlock>, task_id: usize) -> Option<Ta
 /// # Panic
 /// This function panics if no matching [TaskControlBlock]
 /// can be found.
-pub(crate) fn update_tcb(task_id: usize, f: impl FnOnce(&mut TaskControlBlock)) {
+fn update_tcb(task_id: usize, f: impl FnOnce(&mut TaskControlBlock)) {
     ALL_TASKS
         .lock()
         .iter_mut()
         .find(|tcb| tcb.get_task_id() == task_id)
-        .map(f)
+        .map(|tcb| f(tcb))
+        .expect("Cannot find a task with the task_id");
+}
+
+/// Updates the [ProcessControlBlock] of the task `task_id`
+/// by applying the function `f`, without holding the task
+/// registry.
+///
+/// # Panic
+/// This function panics if no matching [TaskControlBlock]
+/// can be found.
+pub(crate) fn update_process(task_id: usize, f: impl FnOnce(&mut ProcessControlBlock)) {
+    let process = ALL_TASKS
+        .lock()
+        .iter()
+        .find(|tcb| tcb.get_task_id() == task_id)
+        .map(|tcb| tcb.get_process().clone())
+        .expect("Cannot find a task with the task_id");
+    f(&mut process.lock());
+}
+
+/// Updates the [VMSpace] of the task `task_id` by applying
+/// the function `f`, without holding the task registry.
+///
+/// # Panic
+/// This function panics if no matching [TaskControlBlock]
+/// can be found.
+pub(crate) fn update_vm_space(task_id: usize, f: impl FnOnce(&mut VMSpace)) {
+    let vm_space = ALL_TASKS
+        .lock()
+        .iter()
+        .find(|tcb| tcb.get_task_id() == task_id)
+        .map(|tcb| tcb.get_vm_space().clone())
         .expect("Cannot find a task with the task_id");
+    f(&mut vm_space.lock());
 }
 
 /// Changes the state of the current task to `new` if
@@ -193,6 +897,178 @@ pub(crate) fn exchange_current_task_state(
     }
 }
 
+/// Puts the current task to sleep until the time counter
+/// reaches the `wakeup_time` or a signal arrives, and
+/// switches to the next task. It returns once the task has
+/// been woken and run again.
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn sleep_current_task(wakeup_time: usize) {
+    let task_id = get_current_task_id();
+
+    let mut all_tasks = ALL_TASKS.lock();
+    let tcb = all_tasks
+        .iter_mut()
+        .find(|tcb| tcb.get_task_id() == task_id)
+        .unwrap();
+
+    let state = tcb.get_state();
+    if state != TaskState::Running {
+        panic!("Task {task_id}: Expected running but got {state:?}")
+    }
+    tcb.record_run_end();
+    if signal::is_interrupted(tcb) {
+        tcb.set_state(TaskState::Ready);
+    } else {
+        tcb.set_state(TaskState::Sleeping);
+        tcb.set_wakeup_time(wakeup_time);
+        push_sleeping_task(wakeup_time, task_id);
+    }
+    drop(all_tasks);
+
+    run_next_task();
+}
+
+/// Wakes the sleeping tasks whose wakeup time has passed,
+/// and queues them on the hart `hart_id`.
+fn wake_sleeping_tasks(hart_id: usize) {
+    let time = timer::read_time();
+
+    let mut all_tasks = ALL_TASKS.lock();
+    while let Some((wakeup_time, task_id)) = pop_expired_task(time) {
+        let Some(tcb) = all_tasks
+            .iter_mut()
+            .find(|tcb| tcb.get_task_id() == task_id)
+        else {
+            continue;
+        };
+        // A task blocked with a timeout is woken the same way.
+        let is_waiting = matches!(tcb.get_state(), TaskState::Sleeping | TaskState::Blocked);
+        if !is_waiting || tcb.get_wakeup_time() != wakeup_time {
+            continue;
+        }
+
+        make_task_ready(tcb, hart_id);
+    }
+}
+
+/// Marks the current task as [TaskState::Blocked], until it
+/// is woken or the time counter reaches the `wakeup_time` if
+/// any. The caller should have queued it on a [WaitQueue] or
+/// a futex and then switch it out with [run_next_task].
+///
+/// A signal also wakes the task, and one already pending
+/// leaves it ready, so the caller should check for it with
+/// [has_current_pending_signal] after running again.
+///
+/// This function panics if the thread is not running
+/// a task.
+///
+/// [WaitQueue]: crate::sync::wait_queue::WaitQueue
+/// [has_current_pending_signal]: signal::has_current_pending_signal
+pub(crate) fn block_current_task(wakeup_time: Option<usize>) {
+    let task_id = get_current_task_id();
+
+    let mut all_tasks = ALL_TASKS.lock();
+    let tcb = all_tasks
+        .iter_mut()
+        .find(|tcb| tcb.get_task_id() == task_id)
+        .unwrap();
+
+    let state = tcb.get_state();
+    if state != TaskState::Running {
+        panic!("Task {task_id}: Expected running but got {state:?}")
+    }
+    tcb.record_run_end();
+    if signal::is_interrupted(tcb) {
+        tcb.set_state(TaskState::Ready);
+        return;
+    }
+    tcb.set_state(TaskState::Blocked);
+    tcb.set_wakeup_time(wakeup_time.unwrap_or(0));
+    if let Some(wakeup_time) = wakeup_time {
+        push_sleeping_task(wakeup_time, task_id);
+    }
+}
+
+/// Wakes the blocked task `task_id`, and queues it on the
+/// hart it last ran on. Returns whether it is woken, which
+/// it is not if it is not blocked, e.g., it has been woken
+/// by a signal or has exited.
+pub(crate) fn wake_blocked_task(task_id: usize) -> bool {
+    let mut all_tasks = ALL_TASKS.lock();
+    let Some(tcb) = all_tasks
+        .iter_mut()
+        .find(|tcb| tcb.get_task_id() == task_id)
+    else {
+        return false;
+    };
+
+    if tcb.get_state() != TaskState::Blocked {
+        return false;
+    }
+    let hart_id = tcb.get_statistics().get_last_hart_id();
+    make_task_ready(tcb, hart_id);
+    true
+}
+
+/// Marks the `tcb` as ready and queues it on the hart
+/// `hart_id`.
+fn make_task_ready(tcb: &mut TaskControlBlock, hart_id: usize) {
+    tcb.set_state(TaskState::Ready);
+
+    // A task still being switched out is requeued by its
+    // hart once the switch completes.
+    if !tcb.is_on_hart() {
+        let task = ReadyTask {
+            task_id: tcb.get_task_id(),
+            priority: tcb.get_priority(),
+            pass: tcb.get_statistics().get_sched_pass(),
+        };
+        push_ready_task(hart_id, task);
+    }
+}
+
+/// Sets the priority of the current task, which takes
+/// effect the next time it becomes ready. Returns whether
+/// the `priority` is valid, i.e., at least [MIN_PRIORITY].
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn set_current_task_priority(priority: usize) -> bool {
+    if priority < MIN_PRIORITY {
+        return false;
+    }
+
+    let task_id = get_current_task_id();
+    update_tcb(task_id, |tcb| tcb.set_priority(priority));
+    true
+}
+
+/// Sets the counters the current task can read in user
+/// mode to the `user_counters`, a mask of scounteren bits,
+/// which takes effect immediately and is inherited by its
+/// children. Returns the previous mask, or [None] if the
+/// mask has bits other than those of the counters.
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn set_current_task_user_counters(user_counters: usize) -> Option<usize> {
+    if user_counters & !DEFAULT_USER_COUNTERS != 0 {
+        return None;
+    }
+
+    let task_id = get_current_task_id();
+    let mut prev = 0;
+    update_tcb(task_id, |tcb| {
+        prev = tcb.get_user_counters();
+        tcb.set_user_counters(user_counters);
+    });
+    scounteren::write(user_counters);
+    Some(prev)
+}
+
 /// Records the current mtime as the current task's
 /// last run end time, and updates the total executed
 /// time and the switch count.
@@ -0,0 +1,100 @@
+extern crate alloc;
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use crate::sync::spin::SpinLock;
+
+use crate::task::signal::has_current_pending_signal;
+use crate::task::{block_current_task, get_current_task_id, run_next_task, wake_blocked_task};
+
+/// The number of buckets in [FUTEX_QUEUES].
+const FUTEX_BUCKETS: usize = 64;
+
+/// The tasks waiting on futexes as (futex PA, task ID),
+/// hashed by the physical address of the futex word, so
+/// that the tasks sharing the word meet at the same bucket.
+/// A bucket should be locked before the task registry.
+static FUTEX_QUEUES: [SpinLock<VecDeque<(usize, usize)>>; FUTEX_BUCKETS] =
+    [const { SpinLock::new(VecDeque::new()) }; FUTEX_BUCKETS];
+
+/// The outcome of [futex_wait].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FutexWaitResult {
+    /// Woken by [futex_wake].
+    Woken,
+    /// Not blocked, since the check of the word failed.
+    NotWaited,
+    /// Woken since the wakeup time has passed.
+    TimedOut,
+    /// Woken by a signal.
+    Interrupted,
+}
+
+fn get_bucket(pa: usize) -> &'static SpinLock<VecDeque<(usize, usize)>> {
+    &FUTEX_QUEUES[(pa / size_of::<u32>()) % FUTEX_BUCKETS]
+}
+
+/// Blocks the current task on the futex word at `pa` if
+/// `should_wait` returns true, until it is woken by
+/// [futex_wake] or a signal, or the time counter reaches
+/// the `wakeup_time` if any.
+///
+/// The `should_wait` runs with the bucket locked, so a change
+/// of the word followed by [futex_wake] cannot slip in
+/// between it and the blocking.
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn futex_wait(
+    pa: usize,
+    should_wait: impl FnOnce() -> bool,
+    wakeup_time: Option<usize>,
+) -> FutexWaitResult {
+    let task_id = get_current_task_id();
+    let bucket = get_bucket(pa);
+
+    let mut waiters = bucket.lock();
+    if !should_wait() {
+        return FutexWaitResult::NotWaited;
+    }
+    waiters.push_back((pa, task_id));
+    block_current_task(wakeup_time);
+    drop(waiters);
+
+    run_next_task();
+
+    // The task is still queued only if no one has woken it.
+    let mut waiters = bucket.lock();
+    let position = waiters.iter().position(|&waiter| waiter == (pa, task_id));
+    if let Some(i) = position {
+        waiters.remove(i);
+    }
+    drop(waiters);
+
+    match position {
+        Some(_) if has_current_pending_signal() => FutexWaitResult::Interrupted,
+        Some(_) => FutexWaitResult::TimedOut,
+        None => FutexWaitResult::Woken,
+    }
+}
+
+/// Wakes at most `count` tasks waiting on the futex word at
+/// `pa`, in the order they started waiting. Returns the
+/// number of tasks woken.
+pub(crate) fn futex_wake(pa: usize, count: usize) -> usize {
+    let mut task_ids = Vec::new();
+    get_bucket(pa).lock().retain(|&(waiter_pa, task_id)| {
+        let is_woken = waiter_pa == pa && task_ids.len() < count;
+        if is_woken {
+            task_ids.push(task_id);
+        }
+        !is_woken
+    });
+
+    // The tasks woken by a signal are not counted.
+    task_ids
+        .into_iter()
+        .filter(|&task_id| wake_blocked_task(task_id))
+        .count()
+}
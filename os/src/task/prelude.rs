@@ -1,13 +1,58 @@
 pub(crate) use super::TaskInfo;
+pub(crate) use super::WaitResult;
+pub(crate) use super::add_initial_tasks;
+pub(crate) use super::block_current_task;
+pub(crate) use super::count_current_threads;
+pub(crate) use super::create_current_thread;
 pub(crate) use super::exchange_current_task_state;
+pub(crate) use super::exec_current_task;
+pub(crate) use super::exit_current_task;
+pub(crate) use super::fork_current_task;
+pub(crate) use super::get_current_hart_id;
 pub(crate) use super::get_current_task_id;
 pub(crate) use super::get_task_info;
+pub(crate) use super::reap_current_task_child;
+pub(crate) use super::reap_current_thread;
 pub(crate) use super::record_current_run_end;
 pub(crate) use super::record_current_syscall;
 pub(crate) use super::run_next_task;
-pub(crate) use super::start;
-pub(crate) use super::update_tcb;
+pub(crate) use super::run_tasks;
+pub(crate) use super::set_current_task_priority;
+pub(crate) use super::set_current_task_user_counters;
+pub(crate) use super::sleep_current_task;
+pub(crate) use super::try_get_current_task_id;
+pub(crate) use super::update_process;
+pub(crate) use super::update_vm_space;
+pub(crate) use super::wait_current_task_child;
+pub(crate) use super::wait_current_thread;
+pub(crate) use super::wake_blocked_task;
 
-pub(crate) use super::apps::log_app_elfs;
+pub(crate) use super::apps::find_app_elf;
+
+pub(crate) use super::futex::FutexWaitResult;
+pub(crate) use super::futex::futex_wait;
+pub(crate) use super::futex::futex_wake;
+
+pub(crate) use super::signal::SIGILL;
+pub(crate) use super::signal::SIGSEGV;
+pub(crate) use super::signal::SigAction;
+pub(crate) use super::signal::force_current_signal;
+pub(crate) use super::signal::handle_current_signals;
+pub(crate) use super::signal::has_current_pending_signal;
+pub(crate) use super::signal::send_signal;
+pub(crate) use super::signal::set_current_sigaction;
+pub(crate) use super::signal::set_current_sigmask;
+pub(crate) use super::signal::sigreturn_current_task;
 
 pub(crate) use super::state::TaskState;
+
+pub(crate) use super::sync_objects::Resource;
+pub(crate) use super::sync_objects::SyncError;
+pub(crate) use super::sync_objects::acquire_current_resource;
+pub(crate) use super::sync_objects::create_current_condvar;
+pub(crate) use super::sync_objects::create_current_mutex;
+pub(crate) use super::sync_objects::create_current_semaphore;
+pub(crate) use super::sync_objects::release_current_resource;
+pub(crate) use super::sync_objects::set_current_deadlock_detect;
+pub(crate) use super::sync_objects::signal_current_condvar;
+pub(crate) use super::sync_objects::wait_current_condvar;
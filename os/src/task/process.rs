@@ -0,0 +1,146 @@
+extern crate alloc;
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::fs::prelude::{self as fs_p, Dentry, FdTable};
+use crate::sync::wait_queue::WaitQueue;
+
+use crate::task::signal::SigActions;
+use crate::task::sync_objects::SyncObjects;
+
+/// The state shared by the threads of a process, whose
+/// process ID is the task ID of its main thread. The user
+/// space is shared through the [TaskControlBlock]s instead,
+/// each of which keeps it alive while running in it.
+///
+/// [TaskControlBlock]: super::state::TaskControlBlock
+#[allow(dead_code)]
+#[derive(Debug)]
+pub(crate) struct ProcessControlBlock {
+    process_id: usize,
+    /// The process ID of the parent, or [None] if the process
+    /// has no parent, i.e., the init process.
+    parent_id: Option<usize>,
+    children_ids: Vec<usize>,
+    fd_table: FdTable,
+    /// The current working directory, which the relative
+    /// paths start from.
+    cwd: Arc<Dentry>,
+    /// The exit code of the process, or [None] if it is not
+    /// exiting.
+    exit_code: Option<isize>,
+    /// The mutexes, semaphores and condition variables of
+    /// the process, which a forked child does not inherit.
+    sync_objects: SyncObjects,
+    /// The actions of the signals, which a forked child
+    /// inherits.
+    sig_actions: SigActions,
+    /// The threads waiting for a child process or another
+    /// thread of this process to exit.
+    exit_waiters: Arc<WaitQueue>,
+}
+
+impl ProcessControlBlock {
+    pub(super) fn new(process_id: usize, parent_id: Option<usize>) -> Self {
+        Self {
+            process_id,
+            parent_id,
+            children_ids: Vec::new(),
+            fd_table: FdTable::new_stdio(),
+            cwd: fs_p::get_root(),
+            exit_code: None,
+            sync_objects: SyncObjects::new(),
+            sig_actions: SigActions::new(),
+            exit_waiters: Arc::new(WaitQueue::new()),
+        }
+    }
+
+    /// Returns the process `process_id` forked from this one,
+    /// which inherits the open files, the current working
+    /// directory and the signal actions.
+    pub(super) fn new_forked(&self, process_id: usize) -> Self {
+        Self {
+            process_id,
+            parent_id: Some(self.process_id),
+            children_ids: Vec::new(),
+            fd_table: self.fd_table.clone(),
+            cwd: self.cwd.clone(),
+            exit_code: None,
+            sync_objects: SyncObjects::new(),
+            sig_actions: self.sig_actions.clone(),
+            exit_waiters: Arc::new(WaitQueue::new()),
+        }
+    }
+
+    pub(super) fn get_process_id(&self) -> usize {
+        self.process_id
+    }
+
+    pub(super) fn get_parent_id(&self) -> Option<usize> {
+        self.parent_id
+    }
+
+    pub(super) fn set_parent_id(&mut self, parent_id: Option<usize>) {
+        self.parent_id = parent_id;
+    }
+
+    pub(super) fn get_children_ids(&self) -> &[usize] {
+        &self.children_ids
+    }
+
+    pub(super) fn add_child_id(&mut self, child_id: usize) {
+        self.children_ids.push(child_id);
+    }
+
+    pub(super) fn remove_child_id(&mut self, child_id: usize) {
+        self.children_ids.retain(|&id| id != child_id);
+    }
+
+    /// Removes and returns all the children's process IDs.
+    pub(super) fn take_children_ids(&mut self) -> Vec<usize> {
+        core::mem::take(&mut self.children_ids)
+    }
+
+    pub(super) fn get_exit_code(&self) -> Option<isize> {
+        self.exit_code
+    }
+
+    pub(super) fn set_exit_code(&mut self, exit_code: isize) {
+        self.exit_code = Some(exit_code);
+    }
+
+    pub(super) fn get_sync_objects_mut(&mut self) -> &mut SyncObjects {
+        &mut self.sync_objects
+    }
+
+    pub(super) fn get_exit_waiters(&self) -> &Arc<WaitQueue> {
+        &self.exit_waiters
+    }
+
+    pub(super) fn get_sig_actions(&self) -> &SigActions {
+        &self.sig_actions
+    }
+
+    pub(super) fn get_sig_actions_mut(&mut self) -> &mut SigActions {
+        &mut self.sig_actions
+    }
+
+    pub(crate) fn get_fd_table(&self) -> &FdTable {
+        &self.fd_table
+    }
+
+    pub(crate) fn get_fd_table_mut(&mut self) -> &mut FdTable {
+        &mut self.fd_table
+    }
+
+    pub(crate) fn get_cwd(&self) -> &Arc<Dentry> {
+        &self.cwd
+    }
+
+    /// Replaces the current working directory with `cwd`
+    /// and returns the previous one.
+    pub(crate) fn replace_cwd(&mut self, cwd: Arc<Dentry>) -> Arc<Dentry> {
+        core::mem::replace(&mut self.cwd, cwd)
+    }
+}
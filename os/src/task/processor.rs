@@ -0,0 +1,64 @@
+use core::cell::UnsafeCell;
+
+use crate::hart::MAX_HARTS;
+use crate::sync::spin::SpinLock;
+
+use crate::task::sched::{ActiveScheduler, ReadyTask, Scheduler};
+use crate::task::state::TaskContext;
+
+/// The per-hart scheduling state, indexed by hart ID.
+static PROCESSORS: [Processor; MAX_HARTS] = [const { Processor::new() }; MAX_HARTS];
+
+/// The scheduling state of a hart.
+struct Processor {
+    /// The context of the hart's scheduling loop, which a
+    /// task switches to when it gives up the hart. Only its
+    /// own hart accesses it.
+    idle_context: UnsafeCell<TaskContext>,
+    /// The run queue of the hart, from which the other harts
+    /// may steal when theirs are empty.
+    scheduler: SpinLock<ActiveScheduler>,
+}
+
+// SAFETY: The idle_context is only accessed by its own hart.
+unsafe impl Sync for Processor {}
+
+impl Processor {
+    const fn new() -> Self {
+        Self {
+            idle_context: UnsafeCell::new(TaskContext::new_initial(0, 0, 0, 0)),
+            scheduler: SpinLock::new(ActiveScheduler::new()),
+        }
+    }
+}
+
+/// Returns the pointer to the idle context of the hart.
+pub(super) fn get_idle_context_ptr(hart_id: usize) -> *mut TaskContext {
+    PROCESSORS[hart_id].idle_context.get()
+}
+
+/// Adds the `task` to the run queue of the hart.
+pub(super) fn push_ready_task(hart_id: usize, task: ReadyTask) {
+    PROCESSORS[hart_id].scheduler.lock().push_ready(task);
+}
+
+/// Picks the next task for the hart from its own run queue,
+/// or steals one from the other harts if it is empty.
+/// Returns [None] if no task is ready on any hart.
+pub(super) fn pick_next_task(hart_id: usize) -> Option<ReadyTask> {
+    let mut scheduler = PROCESSORS[hart_id].scheduler.lock();
+    if let Some(task) = scheduler.pop_next() {
+        return Some(task);
+    }
+
+    // Only one run queue is locked at a time, so that two
+    // harts stealing from each other cannot deadlock.
+    drop(scheduler);
+    let stolen = (1..MAX_HARTS)
+        .map(|i| (hart_id + i) % MAX_HARTS)
+        .find_map(|victim| PROCESSORS[victim].scheduler.lock().steal())?;
+
+    let mut scheduler = PROCESSORS[hart_id].scheduler.lock();
+    scheduler.push_ready(stolen);
+    scheduler.pop_next()
+}
@@ -0,0 +1,146 @@
+extern crate alloc;
+
+use alloc::collections::VecDeque;
+
+/// The priority of a task unless it is set otherwise.
+/// A child inherits the priority of its parent.
+pub(crate) const DEFAULT_PRIORITY: usize = 16;
+/// The lowest priority a task can be set to.
+pub(crate) const MIN_PRIORITY: usize = 2;
+
+/// The scheduler chosen at build time. It is the stride
+/// scheduler if the `sched-stride` feature is enabled, or
+/// the round-robin one otherwise.
+///
+/// The `deterministic` feature requires the round-robin
+/// one, whose run queue is a strict FIFO.
+#[cfg(all(feature = "sched-stride", feature = "deterministic"))]
+compile_error!("The deterministic mode requires the round-robin scheduler.");
+#[cfg(not(feature = "sched-stride"))]
+pub(super) type ActiveScheduler = RoundRobinScheduler;
+#[cfg(feature = "sched-stride")]
+pub(super) type ActiveScheduler = StrideScheduler;
+
+pub(super) const SCHED_POLICY: SchedPolicy = ActiveScheduler::POLICY;
+
+/// The scheduling policy, which is mirrored in user space.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SchedPolicy {
+    RoundRobin,
+    Stride,
+}
+
+/// A ready task as seen by a [Scheduler]. The `pass` is
+/// carried along so that a task keeps it when it moves
+/// between the run queues of different harts.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct ReadyTask {
+    pub(super) task_id: usize,
+    pub(super) priority: usize,
+    pub(super) pass: usize,
+}
+
+/// A scheduler decides the order in which the ready tasks
+/// of a hart run. It only tracks task IDs; the task states
+/// are kept in the [TaskControlBlock]s.
+///
+/// [TaskControlBlock]: super::state::TaskControlBlock
+pub(super) trait Scheduler {
+    const POLICY: SchedPolicy;
+
+    /// Adds the `task` to the ready tasks.
+    fn push_ready(&mut self, task: ReadyTask);
+
+    /// Removes and returns the ready task to run next, with
+    /// its pass advanced for the run, or returns [None] if
+    /// no task is ready.
+    fn pop_next(&mut self) -> Option<ReadyTask>;
+
+    /// Removes and returns the ready task that would run
+    /// last, for an idle hart to steal, or returns [None] if
+    /// no task is ready.
+    fn steal(&mut self) -> Option<ReadyTask>;
+}
+
+/// Runs the ready tasks in the order they become ready.
+#[allow(dead_code)] // Unused if another one is chosen
+pub(super) struct RoundRobinScheduler {
+    ready_queue: VecDeque<ReadyTask>,
+}
+
+#[allow(dead_code)]
+impl RoundRobinScheduler {
+    pub(super) const fn new() -> Self {
+        Self {
+            ready_queue: VecDeque::new(),
+        }
+    }
+}
+
+impl Scheduler for RoundRobinScheduler {
+    const POLICY: SchedPolicy = SchedPolicy::RoundRobin;
+
+    fn push_ready(&mut self, task: ReadyTask) {
+        self.ready_queue.push_back(task);
+    }
+
+    fn pop_next(&mut self) -> Option<ReadyTask> {
+        self.ready_queue.pop_front()
+    }
+
+    fn steal(&mut self) -> Option<ReadyTask> {
+        self.ready_queue.pop_back()
+    }
+}
+
+/// Runs the ready task with the smallest pass value, and
+/// then advances its pass by a stride inversely
+/// proportional to its priority, see [here].
+///
+/// [here]: https://web.eecs.umich.edu/~mosharaf/Readings/Stride.pdf
+#[allow(dead_code)] // Unused if another one is chosen
+pub(super) struct StrideScheduler {
+    /// The ready tasks, in the order they become ready to
+    /// break ties.
+    ready_tasks: VecDeque<ReadyTask>,
+    /// The pass value of the last picked task. A task that
+    /// is new or comes from another hart starts from no less
+    /// than it, so that it does not monopolize the hart.
+    curr_pass: usize,
+}
+
+#[allow(dead_code)]
+impl StrideScheduler {
+    const BIG_STRIDE: usize = 1 << 20;
+
+    pub(super) const fn new() -> Self {
+        Self {
+            ready_tasks: VecDeque::new(),
+            curr_pass: 0,
+        }
+    }
+}
+
+impl Scheduler for StrideScheduler {
+    const POLICY: SchedPolicy = SchedPolicy::Stride;
+
+    fn push_ready(&mut self, mut task: ReadyTask) {
+        task.pass = task.pass.max(self.curr_pass);
+        self.ready_tasks.push_back(task);
+    }
+
+    fn pop_next(&mut self) -> Option<ReadyTask> {
+        let index = (0..self.ready_tasks.len()).min_by_key(|&i| self.ready_tasks[i].pass)?;
+        let mut task = self.ready_tasks.remove(index).unwrap();
+
+        self.curr_pass = task.pass;
+        task.pass += Self::BIG_STRIDE / task.priority.max(MIN_PRIORITY);
+        Some(task)
+    }
+
+    fn steal(&mut self) -> Option<ReadyTask> {
+        let index = (0..self.ready_tasks.len()).max_by_key(|&i| self.ready_tasks[i].pass)?;
+        self.ready_tasks.remove(index)
+    }
+}
@@ -0,0 +1,479 @@
+extern crate alloc;
+
+use alloc::sync::Arc;
+
+use crate::mm::prelude::{check_u_va_range, copy_from_user, copy_to_user};
+use crate::task::state::TaskControlBlock;
+use crate::task::{
+    ALL_TASKS, KILLED_EXIT_CODE, TaskState, exit_current_process, get_current_task_id,
+    get_current_trap_context_ptr, make_task_ready, record_current_run_end, run_next_task,
+    update_tcb,
+};
+use crate::trap::{TrapContext, UserRegisters};
+use crate::{info, log, warn};
+
+/// The number of signals, which are numbered from 1 as in
+/// Linux.
+const NSIG: usize = 32;
+
+// Signals with special handling, see [here].
+//
+// [here]: https://man7.org/linux/man-pages/man7/signal.7.html
+pub(crate) const SIGILL: usize = 4;
+const SIGKILL: usize = 9;
+pub(crate) const SIGSEGV: usize = 11;
+const SIGCHLD: usize = 17;
+const SIGCONT: usize = 18;
+const SIGSTOP: usize = 19;
+const SIGTSTP: usize = 20;
+const SIGTTIN: usize = 21;
+const SIGTTOU: usize = 22;
+const SIGURG: usize = 23;
+const SIGWINCH: usize = 28;
+
+/// All the signals as a mask.
+const ALL_SIGNALS: u64 = ((1 << NSIG) - 1) & !1;
+/// The signals that cannot be caught, blocked or ignored.
+const UNCATCHABLE: u64 = sig_bit(SIGKILL) | sig_bit(SIGSTOP);
+/// The signals whose default action is to ignore them. The
+/// stop signals are among them, since a task cannot be
+/// stopped.
+const IGNORED_BY_DEFAULT: u64 = sig_bit(SIGCHLD)
+    | sig_bit(SIGCONT)
+    | sig_bit(SIGSTOP)
+    | sig_bit(SIGTSTP)
+    | sig_bit(SIGTTIN)
+    | sig_bit(SIGTTOU)
+    | sig_bit(SIGURG)
+    | sig_bit(SIGWINCH);
+
+// Handlers of [SigAction] that are not functions.
+const SIG_DFL: usize = 0;
+const SIG_IGN: usize = 1;
+
+// Flags of [SigAction].
+const SA_NODEFER: usize = 0x40000000;
+const SA_RESETHAND: usize = 0x80000000;
+
+// How [set_current_sigmask] changes the blocked signals.
+const SIG_BLOCK: usize = 0;
+const SIG_UNBLOCK: usize = 1;
+const SIG_SETMASK: usize = 2;
+
+const fn sig_bit(signum: usize) -> u64 {
+    1 << signum
+}
+
+/// How a task handles a signal, which is mirrored in user
+/// space.
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub(crate) struct SigAction {
+    /// The address of the handler, which is called with the
+    /// signal number in a0, or [SIG_DFL] or [SIG_IGN].
+    pub(crate) handler: usize,
+    pub(crate) flags: usize,
+    /// The address the handler returns to, which should make
+    /// the sigreturn syscall.
+    pub(crate) restorer: usize,
+    /// The signals blocked while the handler runs, besides
+    /// the one handled unless [SA_NODEFER] is set.
+    pub(crate) mask: u64,
+}
+
+/// The actions of the signals of a process, which its
+/// threads share.
+#[derive(Debug, Clone)]
+pub(super) struct SigActions([SigAction; NSIG]);
+
+impl SigActions {
+    pub(super) fn new() -> Self {
+        Self([SigAction::default(); NSIG])
+    }
+
+    /// Resets the handlers to the default action for a new
+    /// image, while the ignored signals stay ignored.
+    pub(super) fn reset_handlers(&mut self) {
+        for action in self.0.iter_mut() {
+            if action.handler != SIG_IGN {
+                *action = SigAction::default();
+            }
+        }
+    }
+
+    fn is_ignored(&self, signum: usize) -> bool {
+        match self.0[signum].handler {
+            SIG_IGN => true,
+            SIG_DFL => IGNORED_BY_DEFAULT & sig_bit(signum) != 0,
+            _ => false,
+        }
+    }
+}
+
+/// The signals of a task.
+#[derive(Debug, Clone)]
+pub(super) struct SignalState {
+    /// The signals sent but not handled yet, as a mask.
+    pending: u64,
+    /// The signals held pending until unblocked, as a mask.
+    blocked: u64,
+}
+
+impl SignalState {
+    pub(super) fn new() -> Self {
+        Self {
+            pending: 0,
+            blocked: 0,
+        }
+    }
+
+    /// Returns the state of a forked child or a new thread,
+    /// which inherits the blocked signals but none of the
+    /// pending signals.
+    pub(super) fn new_forked(&self) -> Self {
+        Self {
+            pending: 0,
+            blocked: self.blocked,
+        }
+    }
+
+    /// Makes [SIGKILL] pending, e.g., when another thread
+    /// makes the process exit.
+    pub(super) fn add_kill(&mut self) {
+        self.pending |= sig_bit(SIGKILL);
+    }
+
+    /// Returns whether a pending signal should interrupt a
+    /// blocking syscall, i.e., it is neither blocked nor
+    /// ignored by the `actions`.
+    fn has_interrupting(&self, actions: &SigActions) -> bool {
+        let deliverable = self.pending & !self.blocked;
+        (1..NSIG).any(|signum| deliverable & sig_bit(signum) != 0 && !actions.is_ignored(signum))
+    }
+
+    /// Takes the lowest pending signal that is not blocked,
+    /// and returns it with its action among the `actions`
+    /// and the signals that were blocked. If the action is a
+    /// handler, the signals to block while it runs are
+    /// blocked.
+    fn take_next(&mut self, actions: &mut SigActions) -> Option<(usize, SigAction, u64)> {
+        let deliverable = self.pending & !self.blocked;
+        if deliverable == 0 {
+            return None;
+        }
+        let signum = deliverable.trailing_zeros() as usize;
+        self.pending &= !sig_bit(signum);
+
+        let action = actions.0[signum];
+        let blocked = self.blocked;
+        if action.handler != SIG_DFL && action.handler != SIG_IGN {
+            self.blocked |= action.mask & !UNCATCHABLE;
+            if action.flags & SA_NODEFER == 0 {
+                self.blocked |= sig_bit(signum);
+            }
+            if action.flags & SA_RESETHAND != 0 {
+                actions.0[signum] = SigAction::default();
+            }
+        }
+        Some((signum, action, blocked))
+    }
+}
+
+/// What is pushed to the user stack before a signal handler
+/// is called, and popped by the sigreturn syscall.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct SignalFrame {
+    /// The registers of the interrupted user code.
+    registers: UserRegisters,
+    /// The signals blocked before the handler was called.
+    blocked: u64,
+}
+
+#[allow(dead_code)]
+#[derive(Debug)]
+pub(crate) enum SignalError {
+    /// (requested signal number).
+    InvalidSignal(usize),
+    /// (requested signal number).
+    Uncatchable(usize),
+    /// (requested task ID).
+    NoSuchTask(usize),
+    /// (requested how of sigprocmask).
+    InvalidHow(usize),
+    /// (stack pointer of the frame).
+    BadFrame(usize),
+}
+
+fn check_signum(signum: usize) -> Result<(), SignalError> {
+    if signum == 0 || signum >= NSIG {
+        return Err(SignalError::InvalidSignal(signum));
+    }
+    Ok(())
+}
+
+/// Sends the signal `signum` to the task `task_id`, which
+/// handles it when it next returns to user space. A task
+/// blocked or sleeping in the kernel is woken, and its
+/// syscall fails unless the signal is blocked or ignored.
+/// If `signum` is 0, only checks that the task exists.
+pub(crate) fn send_signal(task_id: usize, signum: usize) -> Result<(), SignalError> {
+    if signum != 0 {
+        check_signum(signum)?;
+    }
+
+    let mut all_tasks = ALL_TASKS.lock();
+    let tcb = all_tasks
+        .iter_mut()
+        .find(|tcb| tcb.get_task_id() == task_id)
+        .ok_or(SignalError::NoSuchTask(task_id))?;
+    if signum == 0 {
+        return Ok(());
+    }
+
+    tcb.get_signals_mut().pending |= sig_bit(signum);
+    wake_interrupted_task(tcb);
+    Ok(())
+}
+
+/// Returns whether the `tcb` has a pending signal that
+/// interrupts its blocking syscalls. It locks the process
+/// of the `tcb`.
+pub(super) fn is_interrupted(tcb: &TaskControlBlock) -> bool {
+    let process = tcb.get_process().lock();
+    tcb.get_signals()
+        .has_interrupting(process.get_sig_actions())
+}
+
+/// Wakes the `tcb` if it is blocked or sleeping and has a
+/// pending signal that interrupts it, and queues it on the
+/// hart it last ran on.
+pub(super) fn wake_interrupted_task(tcb: &mut TaskControlBlock) {
+    let is_waiting = matches!(tcb.get_state(), TaskState::Blocked | TaskState::Sleeping);
+    if is_waiting && is_interrupted(tcb) {
+        let hart_id = tcb.get_statistics().get_last_hart_id();
+        make_task_ready(tcb, hart_id);
+    }
+}
+
+/// Returns whether the current task has a pending signal
+/// that interrupts its blocking syscalls, which should then
+/// fail so that the signal is handled.
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn has_current_pending_signal() -> bool {
+    let mut result = false;
+    update_tcb(get_current_task_id(), |tcb| result = is_interrupted(tcb));
+    result
+}
+
+/// Sends the signal `signum` raised by a fault of the
+/// current task. If the signal is blocked or ignored, its
+/// action is reset to the default one for the whole
+/// process, which terminates it, since the faulting code
+/// cannot go on.
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn force_current_signal(signum: usize) {
+    update_tcb(get_current_task_id(), |tcb| {
+        let process = tcb.get_process().clone();
+        let mut process = process.lock();
+        let action = &mut process.get_sig_actions_mut().0[signum];
+        let signals = tcb.get_signals_mut();
+        if signals.blocked & sig_bit(signum) != 0 || action.handler == SIG_IGN {
+            *action = SigAction::default();
+            signals.blocked &= !sig_bit(signum);
+        }
+        signals.pending |= sig_bit(signum);
+    });
+}
+
+/// Sets the action of the signal `signum` of the process of
+/// the current task to `action` if any, and returns the
+/// previous one. The action is shared by all the threads.
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn set_current_sigaction(
+    signum: usize,
+    action: Option<SigAction>,
+) -> Result<SigAction, SignalError> {
+    check_signum(signum)?;
+    if action.is_some() && UNCATCHABLE & sig_bit(signum) != 0 {
+        return Err(SignalError::Uncatchable(signum));
+    }
+
+    let task_id = get_current_task_id();
+    let mut all_tasks = ALL_TASKS.lock();
+    let process = all_tasks
+        .iter()
+        .find(|tcb| tcb.get_task_id() == task_id)
+        .unwrap()
+        .get_process()
+        .clone();
+
+    let mut process_guard = process.lock();
+    let actions = &mut process_guard.get_sig_actions_mut().0;
+    let prev_action = actions[signum];
+    let Some(action) = action else {
+        return Ok(prev_action);
+    };
+    actions[signum] = action;
+    drop(process_guard);
+
+    // A signal that becomes ignored is discarded by all the
+    // threads.
+    if action.handler == SIG_IGN {
+        for tcb in all_tasks.iter_mut() {
+            if Arc::ptr_eq(tcb.get_process(), &process) {
+                tcb.get_signals_mut().pending &= !sig_bit(signum);
+            }
+        }
+    }
+    Ok(prev_action)
+}
+
+/// Changes the blocked signals of the current task by the
+/// `set` as told by `how` if any, and returns the previous
+/// ones. [SIGKILL] and [SIGSTOP] cannot be blocked.
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn set_current_sigmask(how: usize, set: Option<u64>) -> Result<u64, SignalError> {
+    if set.is_some() && !matches!(how, SIG_BLOCK | SIG_UNBLOCK | SIG_SETMASK) {
+        return Err(SignalError::InvalidHow(how));
+    }
+
+    let mut prev_blocked = 0;
+    update_tcb(get_current_task_id(), |tcb| {
+        let signals = tcb.get_signals_mut();
+        prev_blocked = signals.blocked;
+        let Some(set) = set else {
+            return;
+        };
+        let blocked = match how {
+            SIG_BLOCK => signals.blocked | set,
+            SIG_UNBLOCK => signals.blocked & !set,
+            _ => set,
+        };
+        signals.blocked = blocked & ALL_SIGNALS & !UNCATCHABLE;
+    });
+    Ok(prev_blocked)
+}
+
+/// Handles the pending signals of the current task that are
+/// not blocked, before it returns to user space with the
+/// `context`. The ignored ones are discarded, and the first
+/// one with a handler makes the `context` call the handler.
+/// The others are handled after the handler returns.
+///
+/// A signal whose default action is to terminate the task
+/// makes its whole process exit, and switches to the next
+/// task and never returns.
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn handle_current_signals(context: &mut TrapContext) {
+    let task_id = get_current_task_id();
+
+    loop {
+        let mut next = None;
+        update_tcb(task_id, |tcb| {
+            let process = tcb.get_process().clone();
+            let mut process = process.lock();
+            next = tcb
+                .get_signals_mut()
+                .take_next(process.get_sig_actions_mut());
+        });
+        let Some((signum, action, blocked)) = next else {
+            return;
+        };
+
+        match action.handler {
+            SIG_IGN => {}
+            SIG_DFL if IGNORED_BY_DEFAULT & sig_bit(signum) != 0 => {}
+            SIG_DFL => terminate_current_task(task_id, signum),
+            handler => {
+                let sp = match push_signal_frame(context, blocked) {
+                    Ok(sp) => sp,
+                    Err(err) => {
+                        warn!(
+                            "Task {}: Failed to push the frame of signal {}, err={:?}",
+                            task_id, signum, err
+                        );
+                        terminate_current_task(task_id, SIGSEGV);
+                        return;
+                    }
+                };
+                info!("Task {}: Handling signal {}", task_id, signum);
+                context.set_user_call(handler, signum, sp, action.restorer);
+                return;
+            }
+        }
+    }
+}
+
+/// Saves the registers of the `context` and the `blocked`
+/// signals on the user stack below its stack pointer, and
+/// returns the new stack pointer.
+fn push_signal_frame(context: &TrapContext, blocked: u64) -> Result<usize, SignalError> {
+    let registers = context.get_user_registers();
+    let len = size_of::<SignalFrame>();
+    // The stack pointer stays 16-byte aligned as the calling
+    // convention requires.
+    let sp = registers.get_sp().wrapping_sub(len) & !0xf;
+    if !check_u_va_range(sp, len) {
+        return Err(SignalError::BadFrame(sp));
+    }
+
+    let frame = SignalFrame { registers, blocked };
+    let src = (&raw const frame) as *const u8;
+    if unsafe { copy_to_user(src, sp as *mut u8, len) } != 0 {
+        return Err(SignalError::BadFrame(sp));
+    }
+    Ok(sp)
+}
+
+/// Restores the registers and the blocked signals of the
+/// current task saved by the last signal handled, from the
+/// frame at its stack pointer. The [TrapContext] is
+/// rewritten in place.
+///
+/// Returns the restored a0, which the caller should leave
+/// in a0, or returns [SignalError::BadFrame] and the
+/// current task is left unchanged.
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn sigreturn_current_task() -> Result<usize, SignalError> {
+    let context = get_current_trap_context_ptr();
+    let sp = unsafe { (*context).get_user_registers().get_sp() };
+
+    let len = size_of::<SignalFrame>();
+    if !check_u_va_range(sp, len) {
+        return Err(SignalError::BadFrame(sp));
+    }
+    let mut frame = SignalFrame {
+        registers: unsafe { (*context).get_user_registers() },
+        blocked: 0,
+    };
+    let dst = (&raw mut frame) as *mut u8;
+    if unsafe { copy_from_user(sp as *const u8, dst, len) } != 0 {
+        return Err(SignalError::BadFrame(sp));
+    }
+
+    update_tcb(get_current_task_id(), |tcb| {
+        tcb.get_signals_mut().blocked = frame.blocked & ALL_SIGNALS & !UNCATCHABLE;
+    });
+    unsafe { (*context).set_user_registers(&frame.registers) };
+    Ok(frame.registers.get_a0())
+}
+
+fn terminate_current_task(task_id: usize, signum: usize) {
+    record_current_run_end();
+    exit_current_process(KILLED_EXIT_CODE);
+    warn!("Task {}: Terminated by signal {}", task_id, signum);
+    run_next_task();
+}
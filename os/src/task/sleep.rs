@@ -0,0 +1,38 @@
+extern crate alloc;
+
+use alloc::collections::BinaryHeap;
+use core::cmp::Reverse;
+
+use crate::sync::spin::SpinLock;
+
+/// The sleeping tasks as (wakeup_time, task_id), with the
+/// earliest wakeup time on top. It should be locked after
+/// the task registry and before any run queue.
+static SLEEP_QUEUE: SpinLock<BinaryHeap<Reverse<(usize, usize)>>> =
+    SpinLock::new(BinaryHeap::new());
+
+/// Adds the task `task_id` to be woken at the `wakeup_time`.
+pub(super) fn push_sleeping_task(wakeup_time: usize, task_id: usize) {
+    SLEEP_QUEUE.lock().push(Reverse((wakeup_time, task_id)));
+}
+
+/// Removes and returns the (wakeup_time, task_id) of a task
+/// whose wakeup time is not after the `time`, or returns
+/// [None] if there is no such task.
+pub(super) fn pop_expired_task(time: usize) -> Option<(usize, usize)> {
+    let mut sleep_queue = SLEEP_QUEUE.lock();
+    let &Reverse((wakeup_time, _)) = sleep_queue.peek()?;
+    if wakeup_time > time {
+        return None;
+    }
+    sleep_queue.pop().map(|Reverse(entry)| entry)
+}
+
+/// Returns the earliest wakeup time of the sleeping tasks,
+/// or [None] if no task is sleeping.
+pub(super) fn get_earliest_wakeup_time() -> Option<usize> {
+    SLEEP_QUEUE
+        .lock()
+        .peek()
+        .map(|&Reverse((wakeup_time, _))| wakeup_time)
+}
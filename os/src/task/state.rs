@@ -1,35 +1,104 @@
+extern crate alloc;
+
+use alloc::sync::Arc;
 use core::sync::atomic::{AtomicUsize, Ordering};
 
-use crate::mm::prelude::VMSpace;
+use riscv::regs::scounteren;
+
+use crate::mm::prelude::{KernelStack, VMSpace};
+use crate::sync::spin::SpinLock;
 use crate::timer;
+use crate::trap::{self, TrapContext};
+
+use crate::task::process::ProcessControlBlock;
+use crate::task::sched::{DEFAULT_PRIORITY, SCHED_POLICY, SchedPolicy};
+use crate::task::signal::SignalState;
 
 static NEXT_TASK_ID: AtomicUsize = AtomicUsize::new(1);
 
 pub(super) const MAX_SYSCALLS_TRACKED: usize = 6;
 
+/// The counters a task can read in user mode unless it is
+/// set otherwise, as a mask of scounteren bits. A child
+/// inherits the mask of its parent.
+pub(super) const DEFAULT_USER_COUNTERS: usize =
+    scounteren::CY_BIT | scounteren::TM_BIT | scounteren::IR_BIT;
+
+/// Allocates a task ID, which also becomes the process ID
+/// of a new process.
+pub(super) fn alloc_task_id() -> usize {
+    NEXT_TASK_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// A thread of a process, which the scheduler runs as a
+/// task.
 #[allow(dead_code)]
 #[derive(Debug)]
 pub(crate) struct TaskControlBlock {
     task_id: usize,
-    vm_space: VMSpace,
+    process: Arc<SpinLock<ProcessControlBlock>>,
+    /// The user space shared by the threads of the process.
+    vm_space: Arc<SpinLock<VMSpace>>,
+    /// The kernel stack, at whose end the [TrapContext] lies.
+    kernel_stack: KernelStack,
+    /// The end of the task's user stack in the [VMSpace].
+    u_stack_end: usize,
+    signals: SignalState,
     state: TaskState,
+    /// Whether a hart is still on the task's kernel stack,
+    /// i.e., it is running or being switched out. A zombie
+    /// can only be reaped once this is false.
+    on_hart: bool,
+    /// The priority used by the scheduler, the higher the
+    /// more often the task runs if the policy respects it.
+    priority: usize,
+    /// The counters the task can read in user mode, as a
+    /// mask of scounteren bits.
+    user_counters: usize,
+    /// The time counter value at which the task wakes up if
+    /// it is in [TaskState::Sleeping], or in
+    /// [TaskState::Blocked] with a timeout. It is 0 for the
+    /// latter without a timeout.
+    wakeup_time: usize,
+    /// The exit code of the task, only meaningful if it is
+    /// in [TaskState::Zombie].
+    exit_code: isize,
     context: TaskContext,
     statistics: TaskStatistics,
 }
 
 impl TaskControlBlock {
+    /// Returns a ready task `task_id` in the `process`, which
+    /// returns to user space with the [TrapContext] to be
+    /// written at the end of its `kernel_stack`.
     pub(super) fn new_ready(
-        vm_space: VMSpace,
-        ra: usize,
-        kernel_sp: usize,
-        tp: usize,
-        satp: usize,
+        task_id: usize,
+        process: Arc<SpinLock<ProcessControlBlock>>,
+        vm_space: Arc<SpinLock<VMSpace>>,
+        kernel_stack: KernelStack,
+        u_stack_end: usize,
     ) -> Self {
+        let kernel_sp = kernel_stack.get_end() - size_of::<TrapContext>();
+        let satp = vm_space.lock().get_satp();
         Self {
-            task_id: NEXT_TASK_ID.fetch_add(1, Ordering::Relaxed),
+            task_id,
+            process,
             vm_space,
+            kernel_stack,
+            u_stack_end,
+            signals: SignalState::new(),
             state: TaskState::Ready,
-            context: TaskContext::new_initial(ra, kernel_sp, tp, satp),
+            on_hart: false,
+            priority: DEFAULT_PRIORITY,
+            user_counters: DEFAULT_USER_COUNTERS,
+            wakeup_time: 0,
+            exit_code: 0,
+            context: TaskContext::new_initial(
+                trap::__restore_u_ctx as usize,
+                kernel_sp,
+                kernel_sp,
+                satp,
+            ),
             statistics: TaskStatistics::new_zeros(),
         }
     }
@@ -38,8 +107,58 @@ impl TaskControlBlock {
         self.task_id
     }
 
-    pub(crate) fn get_vm_space_mut(&mut self) -> &mut VMSpace {
-        &mut self.vm_space
+    pub(super) fn get_process(&self) -> &Arc<SpinLock<ProcessControlBlock>> {
+        &self.process
+    }
+
+    pub(super) fn get_vm_space(&self) -> &Arc<SpinLock<VMSpace>> {
+        &self.vm_space
+    }
+
+    /// Returns the pointer to the task's [TrapContext] at the
+    /// end of its kernel stack.
+    pub(super) fn get_trap_context_ptr(&self) -> *mut TrapContext {
+        (self.kernel_stack.get_end() - size_of::<TrapContext>()) as *mut TrapContext
+    }
+
+    pub(super) fn get_u_stack_end(&self) -> usize {
+        self.u_stack_end
+    }
+
+    pub(super) fn get_exit_code(&self) -> isize {
+        self.exit_code
+    }
+
+    pub(super) fn set_exit_code(&mut self, exit_code: isize) {
+        self.exit_code = exit_code;
+    }
+
+    pub(super) fn get_signals(&self) -> &SignalState {
+        &self.signals
+    }
+
+    pub(super) fn get_signals_mut(&mut self) -> &mut SignalState {
+        &mut self.signals
+    }
+
+    /// Replaces the task's [VMSpace] with `vm_space` whose
+    /// user stack ends at `u_stack_end`, and returns the
+    /// previous one. The satp saved in the task's
+    /// [TaskContext] is updated accordingly.
+    pub(super) fn replace_vm_space(
+        &mut self,
+        vm_space: Arc<SpinLock<VMSpace>>,
+        u_stack_end: usize,
+    ) -> Arc<SpinLock<VMSpace>> {
+        self.context.satp = vm_space.lock().get_satp();
+        self.u_stack_end = u_stack_end;
+        core::mem::replace(&mut self.vm_space, vm_space)
+    }
+
+    /// Prepares the task's [VMSpace] to run on the `hart_id`,
+    /// and updates the satp saved in its [TaskContext].
+    pub(super) fn prepare_vm_space(&mut self, hart_id: usize) {
+        self.context.satp = self.vm_space.lock().prepare_switch(hart_id);
     }
 
     pub(super) fn get_state(&self) -> TaskState {
@@ -50,6 +169,38 @@ impl TaskControlBlock {
         self.state = new_state;
     }
 
+    pub(super) fn is_on_hart(&self) -> bool {
+        self.on_hart
+    }
+
+    pub(super) fn set_on_hart(&mut self, on_hart: bool) {
+        self.on_hart = on_hart;
+    }
+
+    pub(super) fn get_priority(&self) -> usize {
+        self.priority
+    }
+
+    pub(super) fn set_priority(&mut self, priority: usize) {
+        self.priority = priority;
+    }
+
+    pub(super) fn get_user_counters(&self) -> usize {
+        self.user_counters
+    }
+
+    pub(super) fn set_user_counters(&mut self, user_counters: usize) {
+        self.user_counters = user_counters;
+    }
+
+    pub(super) fn get_wakeup_time(&self) -> usize {
+        self.wakeup_time
+    }
+
+    pub(super) fn set_wakeup_time(&mut self, wakeup_time: usize) {
+        self.wakeup_time = wakeup_time;
+    }
+
     pub(super) fn get_context(&self) -> &TaskContext {
         &self.context
     }
@@ -63,9 +214,12 @@ impl TaskControlBlock {
     }
 
     /// Records the current mtime as the task's last run start
-    /// time and updates relevant statistics.
-    pub(super) fn record_run_start(&mut self) {
+    /// time on the `hart_id` with the `sched_pass`, and
+    /// updates relevant statistics.
+    pub(super) fn record_run_start(&mut self, hart_id: usize, sched_pass: usize) {
         let time = timer::read_time();
+        self.statistics.set_last_hart_id(hart_id);
+        self.statistics.set_sched_pass(sched_pass);
 
         if self.statistics.get_first_run_start_mtime() == 0 {
             self.statistics.set_first_run_start_mtime(time);
@@ -101,8 +255,18 @@ impl TaskControlBlock {
 pub(crate) enum TaskState {
     Ready,
     Running,
-    Killed,
-    Exited,
+    /// The task is waiting for its wakeup time, and is not
+    /// in any run queue until then.
+    Sleeping,
+    /// The task is waiting on a [WaitQueue] for an event or
+    /// on a futex, and is not in any run queue until it is
+    /// woken.
+    ///
+    /// [WaitQueue]: crate::sync::wait_queue::WaitQueue
+    Blocked,
+    /// The task has exited or been killed, and is waiting
+    /// for its parent to reap it.
+    Zombie,
 }
 
 #[derive(Debug)]
@@ -117,7 +281,7 @@ pub(super) struct TaskContext {
 }
 
 impl TaskContext {
-    pub(super) fn new_initial(ra: usize, sp: usize, tp: usize, satp: usize) -> Self {
+    pub(super) const fn new_initial(ra: usize, sp: usize, tp: usize, satp: usize) -> Self {
         Self {
             s: [0; 12],
             ra,
@@ -126,6 +290,12 @@ impl TaskContext {
             satp,
         }
     }
+
+    /// Returns the saved tp, i.e., the address of the task's
+    /// [TrapContext].
+    pub(super) fn get_tp(&self) -> usize {
+        self.tp
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -145,6 +315,14 @@ pub(super) struct TaskStatistics {
     /// Can only track up to [MAX_SYSCALL_TRACKED] different
     /// syscalls.
     syscall_counts: [(usize, usize); MAX_SYSCALLS_TRACKED],
+    /// The scheduling policy the kernel is built with.
+    #[allow(dead_code)]
+    sched_policy: SchedPolicy,
+    /// The pass value of a task under [SchedPolicy::Stride],
+    /// or zero under the other policies.
+    sched_pass: usize,
+    /// The hart that the task last ran on.
+    last_hart_id: usize,
 }
 
 impl TaskStatistics {
@@ -157,9 +335,28 @@ impl TaskStatistics {
             mtime_total_waiting: 0,
             switch_count: 0,
             syscall_counts: [(0, 0); MAX_SYSCALLS_TRACKED],
+            sched_policy: SCHED_POLICY,
+            sched_pass: 0,
+            last_hart_id: 0,
         }
     }
 
+    pub(super) fn get_sched_pass(&self) -> usize {
+        self.sched_pass
+    }
+
+    fn set_sched_pass(&mut self, value: usize) {
+        self.sched_pass = value;
+    }
+
+    pub(super) fn get_last_hart_id(&self) -> usize {
+        self.last_hart_id
+    }
+
+    fn set_last_hart_id(&mut self, value: usize) {
+        self.last_hart_id = value;
+    }
+
     fn set_first_run_start_mtime(&mut self, value: usize) {
         self.mtime_first_run_start = value;
     }
@@ -0,0 +1,353 @@
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::sync::wait_queue::WaitQueue;
+use crate::task::signal::has_current_pending_signal;
+use crate::task::{get_current_task_id, update_process};
+
+/// What the threads of a process acquire and release, in
+/// units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Resource {
+    /// (mutex ID), which has a single unit.
+    Mutex(usize),
+    /// (semaphore ID).
+    Semaphore(usize),
+}
+
+/// The free units of a [Resource] and the threads blocked
+/// until there is one.
+#[derive(Debug)]
+struct ResourceState {
+    available: usize,
+    waiters: Arc<WaitQueue>,
+}
+
+impl ResourceState {
+    fn new(available: usize) -> Self {
+        Self {
+            available,
+            waiters: Arc::new(WaitQueue::new()),
+        }
+    }
+}
+
+/// The mutexes, semaphores and condition variables created
+/// by the threads of a process, identified by their indices.
+/// The units are counted under the process lock, while the
+/// threads block on the [WaitQueue]s outside it.
+#[derive(Debug)]
+pub(super) struct SyncObjects {
+    mutexes: Vec<ResourceState>,
+    semaphores: Vec<ResourceState>,
+    condvars: Vec<Arc<WaitQueue>>,
+    /// The units held by each thread, as (thread ID,
+    /// resource) -> units.
+    allocation: BTreeMap<(usize, Resource), usize>,
+    /// The units each blocked thread waits for, in the same
+    /// form as the `allocation`. It is only tracked while
+    /// the deadlock detection is enabled.
+    need: BTreeMap<(usize, Resource), usize>,
+    is_deadlock_detect_enabled: bool,
+}
+
+impl SyncObjects {
+    pub(super) fn new() -> Self {
+        Self {
+            mutexes: Vec::new(),
+            semaphores: Vec::new(),
+            condvars: Vec::new(),
+            allocation: BTreeMap::new(),
+            need: BTreeMap::new(),
+            is_deadlock_detect_enabled: false,
+        }
+    }
+
+    fn get_state_mut(&mut self, resource: Resource) -> Result<&mut ResourceState, SyncError> {
+        let state = match resource {
+            Resource::Mutex(id) => self.mutexes.get_mut(id),
+            Resource::Semaphore(id) => self.semaphores.get_mut(id),
+        };
+        state.ok_or(SyncError::NoSuchResource(resource))
+    }
+
+    /// Forgets the units held or needed by the exited thread
+    /// `thread_id`. The units it holds are never given back,
+    /// so the waits for them are judged to never end.
+    pub(super) fn remove_thread(&mut self, thread_id: usize) {
+        self.allocation.retain(|&(id, _), _| id != thread_id);
+        self.need.retain(|&(id, _), _| id != thread_id);
+    }
+
+    /// Takes a unit of the `resource` for the thread
+    /// `thread_id` if there is one, and returns whether it
+    /// is taken.
+    fn try_take(&mut self, thread_id: usize, resource: Resource) -> Result<bool, SyncError> {
+        let state = self.get_state_mut(resource)?;
+        if state.available == 0 {
+            return Ok(false);
+        }
+
+        state.available -= 1;
+        *self.allocation.entry((thread_id, resource)).or_insert(0) += 1;
+        decrease(&mut self.need, (thread_id, resource));
+        Ok(true)
+    }
+
+    /// Gives back a unit of the `resource` from the thread
+    /// `thread_id`, and returns the threads waiting for it.
+    /// A mutex can only be given back by its holder, while
+    /// a semaphore can be by any thread.
+    fn give_back(
+        &mut self,
+        thread_id: usize,
+        resource: Resource,
+    ) -> Result<Arc<WaitQueue>, SyncError> {
+        self.get_state_mut(resource)?;
+        let is_held = decrease(&mut self.allocation, (thread_id, resource));
+        if let (Resource::Mutex(id), false) = (resource, is_held) {
+            return Err(SyncError::MutexNotHeld(id));
+        }
+
+        let state = self.get_state_mut(resource)?;
+        state.available += 1;
+        Ok(state.waiters.clone())
+    }
+
+    /// Takes a unit of the `resource` for the thread
+    /// `thread_id` and returns [None] if there is one, or
+    /// returns the threads waiting for it otherwise.
+    ///
+    /// With the deadlock detection enabled, the thread is
+    /// recorded as waiting for it, unless that leaves no
+    /// order in which all the threads could finish, as in
+    /// the banker's algorithm.
+    fn take_or_request(
+        &mut self,
+        thread_id: usize,
+        resource: Resource,
+    ) -> Result<Option<Arc<WaitQueue>>, SyncError> {
+        if self.try_take(thread_id, resource)? {
+            return Ok(None);
+        }
+
+        if self.is_deadlock_detect_enabled {
+            *self.need.entry((thread_id, resource)).or_insert(0) += 1;
+            if !self.is_safe() {
+                decrease(&mut self.need, (thread_id, resource));
+                return Err(SyncError::Deadlock(resource));
+            }
+        }
+        Ok(Some(self.get_state_mut(resource)?.waiters.clone()))
+    }
+
+    /// Returns whether the threads could all finish, each
+    /// one giving back what it holds once its need is met.
+    fn is_safe(&self) -> bool {
+        let mut work = BTreeMap::new();
+        for (id, state) in self.mutexes.iter().enumerate() {
+            work.insert(Resource::Mutex(id), state.available);
+        }
+        for (id, state) in self.semaphores.iter().enumerate() {
+            work.insert(Resource::Semaphore(id), state.available);
+        }
+
+        let mut waiting: Vec<usize> = self.need.keys().map(|&(thread_id, _)| thread_id).collect();
+        waiting.dedup();
+        // The threads that are not waiting finish first, while
+        // the exited ones are not counted.
+        self.add_allocation(&mut work, |thread_id| !waiting.contains(&thread_id));
+
+        while let Some(i) = waiting
+            .iter()
+            .position(|&thread_id| self.can_finish(thread_id, &work))
+        {
+            let finished_id = waiting.swap_remove(i);
+            self.add_allocation(&mut work, |thread_id| thread_id == finished_id);
+        }
+        waiting.is_empty()
+    }
+
+    /// Adds the units held by the threads for which
+    /// `is_finished` returns true to the `work`.
+    fn add_allocation(
+        &self,
+        work: &mut BTreeMap<Resource, usize>,
+        is_finished: impl Fn(usize) -> bool,
+    ) {
+        for (&(thread_id, resource), &units) in self.allocation.iter() {
+            if is_finished(thread_id) {
+                *work.get_mut(&resource).unwrap() += units;
+            }
+        }
+    }
+
+    /// Returns whether the need of the thread `thread_id` can
+    /// be met by the `work`.
+    fn can_finish(&self, thread_id: usize, work: &BTreeMap<Resource, usize>) -> bool {
+        self.need
+            .iter()
+            .filter(|&(&(id, _), _)| id == thread_id)
+            .all(|(&(_, resource), &units)| units <= work[&resource])
+    }
+}
+
+/// Decreases the units of the `key` in the `units` by one,
+/// removing it once it reaches zero. Returns whether there
+/// was a unit.
+fn decrease(units: &mut BTreeMap<(usize, Resource), usize>, key: (usize, Resource)) -> bool {
+    let Some(count) = units.get_mut(&key) else {
+        return false;
+    };
+    *count -= 1;
+    if *count == 0 {
+        units.remove(&key);
+    }
+    true
+}
+
+#[allow(dead_code)]
+#[derive(Debug)]
+pub(crate) enum SyncError {
+    /// (requested resource).
+    NoSuchResource(Resource),
+    /// (requested condvar ID).
+    NoSuchCondvar(usize),
+    /// (requested mutex ID).
+    MutexNotHeld(usize),
+    /// (requested resource).
+    Deadlock(Resource),
+    /// (requested resource), which a signal interrupted the
+    /// wait for.
+    Interrupted(Resource),
+}
+
+/// Applies the function `f` to the [SyncObjects] of the
+/// current process, and returns its result.
+fn update_current_objects<T>(f: impl FnOnce(&mut SyncObjects) -> T) -> T {
+    let mut result = None;
+    update_process(get_current_task_id(), |process| {
+        result = Some(f(process.get_sync_objects_mut()));
+    });
+    result.unwrap()
+}
+
+/// Creates a mutex in the current process, and returns its
+/// ID.
+pub(crate) fn create_current_mutex() -> usize {
+    update_current_objects(|objects| {
+        objects.mutexes.push(ResourceState::new(1));
+        objects.mutexes.len() - 1
+    })
+}
+
+/// Creates a semaphore with `count` units in the current
+/// process, and returns its ID.
+pub(crate) fn create_current_semaphore(count: usize) -> usize {
+    update_current_objects(|objects| {
+        objects.semaphores.push(ResourceState::new(count));
+        objects.semaphores.len() - 1
+    })
+}
+
+/// Creates a condition variable in the current process, and
+/// returns its ID.
+pub(crate) fn create_current_condvar() -> usize {
+    update_current_objects(|objects| {
+        objects.condvars.push(Arc::new(WaitQueue::new()));
+        objects.condvars.len() - 1
+    })
+}
+
+/// Enables or disables the deadlock detection of the
+/// current process.
+pub(crate) fn set_current_deadlock_detect(is_enabled: bool) {
+    update_current_objects(|objects| {
+        objects.is_deadlock_detect_enabled = is_enabled;
+        if !is_enabled {
+            objects.need.clear();
+        }
+    });
+}
+
+/// Takes a unit of the `resource` of the current process for
+/// the current thread, blocking until there is one.
+///
+/// With the deadlock detection enabled, it fails with
+/// [SyncError::Deadlock] instead of blocking if the wait
+/// could never end. It fails with [SyncError::Interrupted]
+/// if a signal arrives while there is no unit.
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn acquire_current_resource(resource: Resource) -> Result<(), SyncError> {
+    let thread_id = get_current_task_id();
+
+    let waiters = update_current_objects(|objects| objects.take_or_request(thread_id, resource))?;
+    let Some(waiters) = waiters else {
+        return Ok(());
+    };
+
+    loop {
+        let mut result = Ok(false);
+        let mut is_interrupted = false;
+        waiters.wait_if(|| {
+            result = update_current_objects(|objects| objects.try_take(thread_id, resource));
+            is_interrupted = has_current_pending_signal();
+            matches!(result, Ok(false)) && !is_interrupted
+        });
+        if result? {
+            return Ok(());
+        }
+        if is_interrupted {
+            update_current_objects(|objects| decrease(&mut objects.need, (thread_id, resource)));
+            return Err(SyncError::Interrupted(resource));
+        }
+    }
+}
+
+/// Gives back a unit of the `resource` of the current
+/// process from the current thread, and wakes a thread
+/// waiting for it.
+pub(crate) fn release_current_resource(resource: Resource) -> Result<(), SyncError> {
+    let thread_id = get_current_task_id();
+    let waiters = update_current_objects(|objects| objects.give_back(thread_id, resource))?;
+    waiters.wake_one();
+    Ok(())
+}
+
+/// Wakes a thread waiting on the condition variable
+/// `condvar_id` of the current process.
+pub(crate) fn signal_current_condvar(condvar_id: usize) -> Result<(), SyncError> {
+    let condvar = update_current_objects(|objects| objects.condvars.get(condvar_id).cloned())
+        .ok_or(SyncError::NoSuchCondvar(condvar_id))?;
+    condvar.wake_one();
+    Ok(())
+}
+
+/// Releases the mutex `mutex_id` held by the current thread
+/// and blocks on the condition variable `condvar_id` until
+/// it is signaled or a signal arrives, and then acquires the
+/// mutex again as [acquire_current_resource].
+///
+/// This function panics if the thread is not running
+/// a task.
+pub(crate) fn wait_current_condvar(condvar_id: usize, mutex_id: usize) -> Result<(), SyncError> {
+    let condvar = update_current_objects(|objects| objects.condvars.get(condvar_id).cloned())
+        .ok_or(SyncError::NoSuchCondvar(condvar_id))?;
+
+    // The mutex is released with the condition variable
+    // locked, so a signal cannot slip in before blocking.
+    let mutex = Resource::Mutex(mutex_id);
+    let mut result = Ok(());
+    condvar.wait_if(|| {
+        result = release_current_resource(mutex);
+        result.is_ok()
+    });
+    result?;
+
+    acquire_current_resource(mutex)
+}
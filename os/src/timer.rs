@@ -1,12 +1,77 @@
 use core::arch::asm;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
+use crate::drivers::goldfish_rtc;
+use crate::machine;
 use crate::sbi;
+use crate::{info, log};
 
-const TIMEBASE_FREQUENCY: usize = 10_000_000; // Hz
+const NANOS_PER_SEC: usize = 1_000_000_000;
+const NANOS_PER_MICRO: usize = 1_000;
+const MILLIS_PER_SEC: usize = 1_000;
+/// The number of time slices per second.
+///
+/// In the deterministic mode, QEMU runs with `-icount` so that the time
+/// counter advances with the retired instructions instead of wall time,
+/// which makes the slices and the preemption points reproducible.
+const TIME_SLICES_PER_SEC: usize = 100;
+
+/// The frequency of the time counter in Hz, which is read
+/// from the device tree.
+static TIMEBASE_FREQUENCY: AtomicUsize = AtomicUsize::new(0);
+
+/// The nanoseconds since the Unix epoch at which the time
+/// counter was zero, which the real time counts from.
+static REALTIME_OFFSET_NANOS: AtomicUsize = AtomicUsize::new(0);
+
+/// `init` sets the timebase frequency and seeds the real time from the
+/// goldfish RTC. In the deterministic mode, or without an RTC, the real
+/// time starts from the epoch instead.
+pub(super) fn init() {
+    let frequency = machine::get_timebase_frequency();
+    TIMEBASE_FREQUENCY.store(frequency, Ordering::Relaxed);
+    info!("Timebase frequency: {} Hz", frequency);
+
+    let rtc_nanos = if cfg!(feature = "deterministic") {
+        0
+    } else {
+        goldfish_rtc::read_time_nanos().unwrap_or(0) as usize
+    };
+    let offset = rtc_nanos.saturating_sub(ticks_to_nanos(read_time()));
+    REALTIME_OFFSET_NANOS.store(offset, Ordering::Relaxed);
+    info!(
+        "Real time at boot: {} seconds since the epoch",
+        rtc_nanos / NANOS_PER_SEC
+    );
+}
+
+/// `get_timebase_frequency` returns the frequency of the time counter in
+/// Hz.
+pub(super) fn get_timebase_frequency() -> usize {
+    TIMEBASE_FREQUENCY.load(Ordering::Relaxed)
+}
+
+/// `ticks_to_nanos` converts the `ticks` of the time counter to
+/// nanoseconds, rounding down.
+fn ticks_to_nanos(ticks: usize) -> usize {
+    (ticks as u128 * NANOS_PER_SEC as u128 / get_timebase_frequency() as u128) as usize
+}
+
+/// `read_monotonic_time` returns the time since system start, which never
+/// goes backwards.
+pub(super) fn read_monotonic_time() -> TimeSpec {
+    TimeSpec::from_nanos(ticks_to_nanos(read_time()))
+}
+
+/// `read_real_time` returns the time since the Unix epoch.
+pub(super) fn read_real_time() -> TimeSpec {
+    let offset = REALTIME_OFFSET_NANOS.load(Ordering::Relaxed);
+    TimeSpec::from_nanos(offset + ticks_to_nanos(read_time()))
+}
 
 /// `read_time_ms` returns the time since system start in millisecond.
 pub(super) fn read_time_ms() -> usize {
-    read_time() / (TIMEBASE_FREQUENCY / 1_000)
+    read_time() / (get_timebase_frequency() / MILLIS_PER_SEC)
 }
 
 /// `read_time` returns the current value of the time counter.
@@ -16,11 +81,65 @@ pub(super) fn read_time() -> usize {
     result
 }
 
-/// `set_next_timer_interrupt` sets a timer interrupt 10 ms later.
+/// `set_next_timer_interrupt` sets a timer interrupt one time slice, i.e.,
+/// 10 ms, later, or at the `wakeup_time` if it is earlier.
 ///
 /// The duration until the first timer interrupt should be long enough
 /// to avoid triggering a trap before the sscratch has been initialized.
-pub(super) fn set_next_timer_interrupt() {
-    let time = TIMEBASE_FREQUENCY / 100 + read_time();
-    sbi::set_mtimecmp(time);
+pub(super) fn set_next_timer_interrupt(wakeup_time: Option<usize>) {
+    let time = get_timebase_frequency() / TIME_SLICES_PER_SEC + read_time();
+    sbi::set_mtimecmp(wakeup_time.map_or(time, |wakeup_time| wakeup_time.min(time)));
+}
+
+/// A duration or a point in time in seconds and nanoseconds
+/// as in POSIX, which is mirrored in user space.
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub(crate) struct TimeSpec {
+    pub(crate) sec: usize,
+    /// Less than one second, i.e., 10^9 nanoseconds.
+    pub(crate) nsec: usize,
+}
+
+impl TimeSpec {
+    fn from_nanos(nanos: usize) -> Self {
+        Self {
+            sec: nanos / NANOS_PER_SEC,
+            nsec: nanos % NANOS_PER_SEC,
+        }
+    }
+
+    /// Returns whether the nanoseconds are less than a second.
+    pub(crate) fn is_valid(&self) -> bool {
+        self.nsec < NANOS_PER_SEC
+    }
+
+    /// Returns the duration in ticks of the time counter,
+    /// rounding up the partial tick and saturating on overflow.
+    pub(crate) fn to_ticks(&self) -> usize {
+        let frequency = get_timebase_frequency();
+        let nsec_ticks = (self.nsec as u128 * frequency as u128).div_ceil(NANOS_PER_SEC as u128);
+        self.sec
+            .saturating_mul(frequency)
+            .saturating_add(nsec_ticks as usize)
+    }
+}
+
+/// A point in time in seconds and microseconds as returned
+/// by gettimeofday, which is mirrored in user space.
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub(crate) struct TimeVal {
+    pub(crate) sec: usize,
+    /// Less than one second, i.e., 10^6 microseconds.
+    pub(crate) usec: usize,
+}
+
+impl From<TimeSpec> for TimeVal {
+    fn from(time: TimeSpec) -> Self {
+        Self {
+            sec: time.sec,
+            usec: time.nsec / NANOS_PER_MICRO,
+        }
+    }
 }
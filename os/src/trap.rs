@@ -1,3 +1,4 @@
+mod irq;
 mod ktrap;
 mod utrap;
 
@@ -6,9 +7,11 @@ use core::arch::{asm, global_asm};
 use riscv::regs::{scause, sie, sstatus, stvec};
 
 use crate::mm::prelude::VMError;
-use crate::task::prelude::update_tcb;
+use crate::task::prelude::update_vm_space;
 use crate::{log, warn};
 
+pub(crate) use irq::{handle_external_interrupts, register_irq_handler};
+
 global_asm!(include_str!("trap/trap.S"));
 
 unsafe extern "C" {
@@ -16,7 +19,13 @@ unsafe extern "C" {
     pub(super) unsafe fn __restore_u_ctx();
 }
 
-pub fn init() {
+/// Initializes the traps of the hart `hart_id`, including
+/// the interrupt sources registered so far. The hart takes
+/// the interrupts in user space, and the external ones also
+/// in the kernel code run for a task, as enabled by
+/// [enable_kernel_interrupts]. The boot code and the
+/// scheduling loop run with sstatus.SIE clear instead.
+pub fn init(hart_id: usize) {
     unsafe extern "C" {
         unsafe fn __stvec();
     }
@@ -27,14 +36,9 @@ pub fn init() {
     let stvec_ok = stvec::install(__stvec as usize, stvec::Mode::Direct);
     assert!(stvec_ok, "Failed to install stvec");
 
-    enable_interrupts();
+    irq::init_hart(hart_id);
     enable_timer_interrupts();
-}
-
-/// Enables all interrupts in supervisor mode. This provides
-/// overall control over interrupt behavior.
-fn enable_interrupts() {
-    sstatus::set_sie();
+    enable_external_interrupts();
 }
 
 /// Enables the timer interrupts in supervisor mode. This
@@ -43,6 +47,32 @@ fn enable_timer_interrupts() {
     sie::set_stie();
 }
 
+/// Enables the external interrupts in supervisor mode,
+/// which the PLIC raises for the enabled sources.
+fn enable_external_interrupts() {
+    sie::set_seie();
+}
+
+/// Lets the hart take the external interrupts while it runs
+/// kernel code for a task, except while it holds a
+/// [SpinLock]. The timer interrupts are held back until it
+/// returns to user space, since the kernel does not preempt
+/// itself.
+///
+/// [SpinLock]: crate::sync::spin::SpinLock
+pub(crate) fn enable_kernel_interrupts() {
+    sie::clear_stie();
+    sstatus::set_sie();
+}
+
+/// Stops the hart from taking interrupts in kernel code,
+/// which should be done before it returns to user space or
+/// switches to the scheduling loop.
+pub(crate) fn disable_kernel_interrupts() {
+    sstatus::clear_sie();
+    sie::set_stie();
+}
+
 /// Tries to fix the page fault for the task by mapping the
 /// page containing address `stval` into its [VMSpace].
 pub(crate) fn do_page_fault(
@@ -51,10 +81,8 @@ pub(crate) fn do_page_fault(
     min_permissions: usize,
 ) -> Result<(), VMError> {
     let mut result = Ok(());
-    update_tcb(task_id, |tcb| {
-        result = tcb
-            .get_vm_space_mut()
-            .map_fault_page(stval, min_permissions);
+    update_vm_space(task_id, |vm_space| {
+        result = vm_space.map_fault_page(stval, min_permissions);
     });
     result
 }
@@ -87,10 +115,21 @@ pub struct TrapContext {
     sstatus: usize,
     sepc: usize,
     task_id: usize,
+    /// The hart that the task is running on, which is set
+    /// each time the task is switched in.
+    hart_id: usize,
 }
 
 impl TrapContext {
-    pub(crate) fn new_initial(entry_addr: usize, user_sp: usize, task_id: usize) -> Self {
+    /// Returns the [TrapContext] that starts a task from the
+    /// `entry_addr` with the `user_sp`. The `main_args`, i.e.,
+    /// argc, argv and envp, are passed in a0 through a2.
+    pub(crate) fn new_initial(
+        entry_addr: usize,
+        user_sp: usize,
+        main_args: [usize; 3],
+        task_id: usize,
+    ) -> Self {
         let sstatus = sstatus::set_spp_user();
 
         let mut result = Self {
@@ -98,15 +137,66 @@ impl TrapContext {
             sstatus,
             sepc: entry_addr,
             task_id,
+            hart_id: 0,
         };
         result.x[2] = user_sp;
+        result.x[10..13].copy_from_slice(&main_args);
         result
     }
 
+    /// Returns a copy of the `parent`'s [TrapContext] for
+    /// its forked child, in which the child sees zero as the
+    /// return value of the fork syscall.
+    pub(crate) fn new_forked(parent: &TrapContext, task_id: usize) -> Self {
+        let mut result = Self {
+            x: parent.x,
+            sstatus: parent.sstatus,
+            sepc: parent.sepc,
+            task_id,
+            hart_id: 0,
+        };
+        result.x[10] = 0;
+        result
+    }
+
+    /// Returns the registers of the interrupted user code.
+    pub(crate) fn get_user_registers(&self) -> UserRegisters {
+        UserRegisters {
+            x: self.x,
+            pc: self.sepc,
+        }
+    }
+
+    /// Sets the registers that the user code resumes with,
+    /// except x0, which stays zero.
+    pub(crate) fn set_user_registers(&mut self, registers: &UserRegisters) {
+        self.x = registers.x;
+        self.x[0] = 0;
+        self.sepc = registers.pc;
+    }
+
+    /// Makes the user code call the function at `entry` with
+    /// the `arg` in a0, on the stack at `sp`. The function
+    /// returns to `ra`.
+    pub(crate) fn set_user_call(&mut self, entry: usize, arg: usize, sp: usize, ra: usize) {
+        self.sepc = entry;
+        self.x[1] = ra;
+        self.x[2] = sp;
+        self.x[10] = arg;
+    }
+
     pub(crate) fn get_task_id(&self) -> usize {
         self.task_id
     }
 
+    pub(crate) fn get_hart_id(&self) -> usize {
+        self.hart_id
+    }
+
+    pub(crate) fn set_hart_id(&mut self, hart_id: usize) {
+        self.hart_id = hart_id;
+    }
+
     /// Returns the `task_id` of the [TrapContext].
     ///
     /// # Safety
@@ -115,4 +205,32 @@ impl TrapContext {
     unsafe fn get_task_id_from_ptr(ptr: *const TrapContext) -> usize {
         unsafe { ptr.as_ref().unwrap().get_task_id() }
     }
+
+    /// Returns the `hart_id` of the [TrapContext].
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be a valid pointer to a [TrapContext].
+    unsafe fn get_hart_id_from_ptr(ptr: *const TrapContext) -> usize {
+        unsafe { ptr.as_ref().unwrap().get_hart_id() }
+    }
+}
+
+/// The registers of user code, which are saved on the user
+/// stack while a signal handler runs.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub(crate) struct UserRegisters {
+    x: [usize; 32],
+    pc: usize,
+}
+
+impl UserRegisters {
+    pub(crate) fn get_sp(&self) -> usize {
+        self.x[2]
+    }
+
+    pub(crate) fn get_a0(&self) -> usize {
+        self.x[10]
+    }
 }
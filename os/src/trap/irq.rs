@@ -0,0 +1,56 @@
+use crate::drivers::plic;
+use crate::sync::spin::SpinLock;
+use crate::{log, warn};
+
+/// The number of interrupt sources of the QEMU virt machine,
+/// where source 0 means no interrupt.
+const MAX_IRQS: usize = 96;
+/// The priority of the sources with handlers, which is above
+/// the zero threshold of every hart.
+const IRQ_PRIORITY: u32 = 1;
+
+/// The handlers of the interrupt sources, indexed by source
+/// number.
+static IRQ_HANDLERS: SpinLock<[Option<fn()>; MAX_IRQS]> = SpinLock::new([None; MAX_IRQS]);
+
+/// Registers the `handler` for the interrupt source `irq`,
+/// replacing the previous one.
+///
+/// A hart only takes the sources registered before it calls
+/// [init](super::init), so drivers should register theirs
+/// before the harts initialize traps.
+pub(crate) fn register_irq_handler(irq: usize, handler: fn()) {
+    assert!(
+        0 < irq && irq < MAX_IRQS,
+        "Invalid interrupt source {}",
+        irq
+    );
+    IRQ_HANDLERS.lock()[irq] = Some(handler);
+    plic::set_priority(irq, IRQ_PRIORITY);
+}
+
+/// Enables the registered sources for the hart.
+pub(super) fn init_hart(hart_id: usize) {
+    let handlers = IRQ_HANDLERS.lock();
+    (1..MAX_IRQS)
+        .filter(|&irq| handlers[irq].is_some())
+        .for_each(|irq| plic::enable(hart_id, irq));
+    plic::set_threshold(hart_id, 0);
+}
+
+/// Claims the pending interrupt sources for the hart, calls
+/// their handlers and completes them. A source taken by
+/// another hart first is not pending any more.
+pub(crate) fn handle_external_interrupts(hart_id: usize) {
+    while let Some(irq) = plic::claim(hart_id) {
+        // The handler is called without the lock, so that it
+        // may take a while or register handlers.
+        let handler = IRQ_HANDLERS.lock().get(irq).copied().flatten();
+        if let Some(handler) = handler {
+            handler();
+        } else {
+            warn!("Hart {}: No handler for interrupt {}", hart_id, irq);
+        }
+        plic::complete(hart_id, irq);
+    }
+}
@@ -7,7 +7,9 @@ use crate::mm::prelude::{
     PERMISSION_R, PERMISSION_U, PERMISSION_W, get_uaccess_fix, is_load_user_fault,
     is_store_user_fault,
 };
-use crate::trap::{TrapContext, do_page_fault, log_do_page_fault_failed, trap_panic};
+use crate::trap::{
+    TrapContext, do_page_fault, handle_external_interrupts, log_do_page_fault_failed, trap_panic,
+};
 
 #[unsafe(no_mangle)]
 fn k_trap_handler(context: &mut TrapContext) {
@@ -44,6 +46,15 @@ fn k_trap_handler(context: &mut TrapContext) {
             }
         }
 
+        // Only the kernel code run for a task enables them.
+        Cause::SupervisorExternalInterrupt if saved_tp != 0 => {
+            // SAFETY:
+            // If the saved_tp is not zero, it should point to the
+            // [TrapContext] of the running task on this hart.
+            let hart_id = unsafe { TrapContext::get_hart_id_from_ptr(saved_tp as *const _) };
+            handle_external_interrupts(hart_id);
+        }
+
         // Unexpected causes
         _ => trap_panic(0, cause, scause, stval, sepc, context),
     }
@@ -6,10 +6,14 @@ use riscv::regs::{
 use crate::mm::prelude::{PERMISSION_R, PERMISSION_U, PERMISSION_W, check_u_va};
 use crate::syscall;
 use crate::task::prelude::{
-    TaskState, exchange_current_task_state, get_current_task_id, record_current_run_end,
-    record_current_syscall, run_next_task,
+    SIGILL, SIGSEGV, TaskState, exchange_current_task_state, force_current_signal,
+    get_current_task_id, handle_current_signals, record_current_run_end, record_current_syscall,
+    run_next_task,
+};
+use crate::trap::{
+    TrapContext, disable_kernel_interrupts, do_page_fault, enable_kernel_interrupts,
+    handle_external_interrupts, log_do_page_fault_failed, trap_panic,
 };
-use crate::trap::{TrapContext, do_page_fault, log_do_page_fault_failed, trap_panic};
 use crate::{info, log, warn};
 
 const EXPECT_RUNNING_TASK_STATE: &str = "Expected the current TaskState to be Running.";
@@ -20,32 +24,41 @@ fn u_trap_handler(context: &mut TrapContext) -> &mut TrapContext {
     let scause = scause::read();
     let sepc = sepc::read();
     let stval = stval::read();
+    enable_kernel_interrupts();
 
     let cause = scause::match_cause(scause);
     match cause {
         Cause::SupervisorTimerInterrupt => {
-            sleep_task();
+            preempt_task();
             info!("Task {:?}: {:?}.", task_id, cause);
             run_next_task();
         }
 
+        Cause::SupervisorExternalInterrupt => {
+            handle_external_interrupts(context.get_hart_id());
+        }
+
         Cause::UserEnvironmentCall => {
             let syscall_id = context.x[17];
             record_current_syscall(syscall_id);
 
             context.sepc += 4;
-            context.x[10] =
-                syscall::syscall_handler(syscall_id, [context.x[10], context.x[11], context.x[12]])
-                    as usize;
+            let args = [
+                context.x[10],
+                context.x[11],
+                context.x[12],
+                context.x[13],
+                context.x[14],
+                context.x[15],
+            ];
+            context.x[10] = syscall::syscall_handler(syscall_id, args) as usize;
         }
 
         Cause::StoreOrAmoPageFault if check_u_va(stval) => {
             let min_permissions = PERMISSION_U | PERMISSION_W;
             if let Err(err) = do_page_fault(task_id, stval, min_permissions) {
                 log_do_page_fault_failed(task_id, stval, min_permissions, err);
-                kill_task();
-                log_task_killed(task_id, cause, stval, sepc);
-                run_next_task();
+                raise_fault_signal(task_id, SIGSEGV, cause, stval, sepc);
             }
         }
 
@@ -53,40 +66,46 @@ fn u_trap_handler(context: &mut TrapContext) -> &mut TrapContext {
             let min_permissions = PERMISSION_U | PERMISSION_R;
             if let Err(err) = do_page_fault(task_id, stval, min_permissions) {
                 log_do_page_fault_failed(task_id, stval, min_permissions, err);
-                kill_task();
-                log_task_killed(task_id, cause, stval, sepc);
-                run_next_task();
+                raise_fault_signal(task_id, SIGSEGV, cause, stval, sepc);
             }
         }
 
-        Cause::IllegalInstruction | Cause::InstructionPageFault => {
-            kill_task();
-            log_task_killed(task_id, cause, stval, sepc);
-            run_next_task();
+        Cause::IllegalInstruction => {
+            raise_fault_signal(task_id, SIGILL, cause, stval, sepc);
+        }
+
+        Cause::InstructionPageFault => {
+            raise_fault_signal(task_id, SIGSEGV, cause, stval, sepc);
         }
 
         // Unexpected causes
         _ => trap_panic(task_id, cause, scause, stval, sepc, context),
     }
 
+    handle_current_signals(context);
+    disable_kernel_interrupts();
     context
 }
 
-fn sleep_task() {
+fn preempt_task() {
     exchange_current_task_state(TaskState::Running, TaskState::Ready)
         .expect(EXPECT_RUNNING_TASK_STATE);
     record_current_run_end();
 }
 
-fn kill_task() {
-    exchange_current_task_state(TaskState::Running, TaskState::Killed)
-        .expect(EXPECT_RUNNING_TASK_STATE);
-    record_current_run_end();
-}
-
-fn log_task_killed(task_id: usize, cause: scause::Cause, stval: usize, sepc: usize) {
+/// Sends the signal `signum` for the fault `cause` to the
+/// current task, which handles it before returning to user
+/// space.
+fn raise_fault_signal(
+    task_id: usize,
+    signum: usize,
+    cause: scause::Cause,
+    stval: usize,
+    sepc: usize,
+) {
     warn!(
-        "Task {}: {:?}, stval={:#x}, spec={:#x}. Kernel killed it.",
-        task_id, cause, stval, sepc
+        "Task {}: {:?}, stval={:#x}, spec={:#x}. Kernel sent signal {}.",
+        task_id, cause, stval, sepc, signum
     );
+    force_current_signal(signum);
 }
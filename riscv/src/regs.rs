@@ -1,5 +1,6 @@
 pub mod satp;
 pub mod scause;
+pub mod scounteren;
 pub mod sepc;
 pub mod sie;
 pub mod sstatus;
@@ -1,12 +1,37 @@
+use crate::{csrr, csrw};
+
 const CSR_NO: usize = 0x180;
+const PPN_MASK: usize = 0xfff_ffff_ffff;
+const ASID_SHIFT: usize = 44;
+const ASID_MASK: usize = 0xffff;
 
-/// Returns the satp value corresponding to the given `ppn`
-/// and `mode`. Any set bits in `ppn` other than the lower
-/// 44 bits are ignored.
-pub fn compute_value(ppn: usize, mode: Mode) -> usize {
-    let ppn = ppn & 0xfff_ffff_ffff;
+/// Returns the satp value corresponding to the given `ppn`,
+/// `asid` and `mode`. Any set bits in `ppn` other than the
+/// lower 44 bits, or in `asid` other than the lower 16 bits,
+/// are ignored.
+pub fn compute_value(ppn: usize, asid: usize, mode: Mode) -> usize {
+    let ppn = ppn & PPN_MASK;
+    let asid = asid & ASID_MASK;
     let mode_value = get_mode_value(mode);
-    (mode_value << 60) | ppn
+    (mode_value << 60) | (asid << ASID_SHIFT) | ppn
+}
+
+/// Returns the number of ASID bits the hart implements,
+/// which is zero if ASIDs are not supported.
+///
+/// It writes all ones to the ASID field of the current satp
+/// and reads back the bits that stick, as suggested by the
+/// RISC-V manual Vol II. The satp is then restored.
+pub fn probe_asid_bits() -> usize {
+    let satp: usize;
+    csrr!(CSR_NO, satp);
+    csrw!(CSR_NO, satp | (ASID_MASK << ASID_SHIFT));
+
+    let probed: usize;
+    csrr!(CSR_NO, probed);
+    csrw!(CSR_NO, satp);
+
+    ((probed >> ASID_SHIFT) & ASID_MASK).count_ones() as usize
 }
 
 pub enum Mode {
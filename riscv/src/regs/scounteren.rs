@@ -0,0 +1,22 @@
+use crate::{csrr, csrw};
+
+const CSR_NO: usize = 0x106;
+/// Permits U-mode to read the cycle counter.
+pub const CY_BIT: usize = 1 << 0;
+/// Permits U-mode to read the time counter.
+pub const TM_BIT: usize = 1 << 1;
+/// Permits U-mode to read the retired instruction counter.
+pub const IR_BIT: usize = 1 << 2;
+
+pub fn read() -> usize {
+    let result: usize;
+    csrr!(CSR_NO, result);
+    result
+}
+
+/// Writes the `value` to control which counters U-mode can
+/// read; reading the others raises an illegal instruction
+/// exception.
+pub fn write(value: usize) {
+    csrw!(CSR_NO, value);
+}
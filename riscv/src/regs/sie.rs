@@ -1,7 +1,8 @@
-use crate::csrrs;
+use crate::{csrrc, csrrs};
 
 const CSR_NO: usize = 0x104;
 const STIE_BIT: usize = 1 << 5;
+const SEIE_BIT: usize = 1 << 9;
 
 /// Sets the STIE bit to enable supervisor-level timer
 /// interrupts, and returns the old value of the register.
@@ -10,3 +11,19 @@ pub fn set_stie() -> usize {
     csrrs!(CSR_NO, result, STIE_BIT);
     result
 }
+
+/// Clears the STIE bit to disable supervisor-level timer
+/// interrupts, and returns the old value of the register.
+pub fn clear_stie() -> usize {
+    let result: usize;
+    csrrc!(CSR_NO, result, STIE_BIT);
+    result
+}
+
+/// Sets the SEIE bit to enable supervisor-level external
+/// interrupts, and returns the old value of the register.
+pub fn set_seie() -> usize {
+    let result: usize;
+    csrrs!(CSR_NO, result, SEIE_BIT);
+    result
+}
@@ -1,7 +1,7 @@
 use crate::{csrr, csrrc, csrrs};
 
 const CSR_NO: usize = 0x100;
-const SIE_BIT: usize = 1 << 1;
+pub const SIE_BIT: usize = 1 << 1;
 const SPP_BIT: usize = 1 << 8;
 const SUM_BIT: usize = 1 << 18;
 
@@ -28,6 +28,15 @@ pub fn set_sie() -> usize {
     result
 }
 
+/// Clears the SIE bit to globally disable all interrupts
+/// in supervisor mode, and returns the old value of the
+/// register.
+pub fn clear_sie() -> usize {
+    let result: usize;
+    csrrc!(CSR_NO, result, SIE_BIT);
+    result
+}
+
 /// Sets the SUM bit to permit S-mode memory accesses to
 /// page that are accessible by U-mode.
 pub fn set_sum_permit() -> usize {
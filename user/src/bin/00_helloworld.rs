@@ -6,7 +6,7 @@ extern crate user_lib;
 use user_lib::{println, yield_now};
 
 #[unsafe(no_mangle)]
-fn main() -> i32 {
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
     for c in "hello world!!!!!".chars() {
         println!("{}", c);
         yield_now();
@@ -6,7 +6,7 @@ extern crate user_lib;
 use user_lib::println;
 
 #[unsafe(no_mangle)]
-fn main() -> i32 {
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
     let mut buffer = [0u32; 10];
     let buffer_size = buffer.len();
 
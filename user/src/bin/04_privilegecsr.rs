@@ -8,7 +8,7 @@ use core::arch::asm;
 use user_lib::println;
 
 #[unsafe(no_mangle)]
-fn main() -> i32 {
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
     println!("Try to access privileged CSR in U Mode");
     println!("Kernel should kill this application!");
 
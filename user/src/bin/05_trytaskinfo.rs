@@ -7,7 +7,7 @@ use user_lib::task::TaskInfo;
 use user_lib::{get_task_info, println};
 
 #[unsafe(no_mangle)]
-fn main() -> i32 {
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
     let mut task_info = TaskInfo::new_placeholder();
 
     for task_id in 1..=6 {
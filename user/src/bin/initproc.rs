@@ -0,0 +1,26 @@
+#![no_std]
+#![no_main]
+
+extern crate user_lib;
+
+use user_lib::{println, wait};
+
+/// The init task. The kernel starts the other apps as its
+/// children and hands orphan tasks over to it. It reaps
+/// them until no child is left.
+#[unsafe(no_mangle)]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    let mut exit_code = 0;
+
+    loop {
+        let pid = wait(&mut exit_code);
+        if pid == -1 {
+            break;
+        }
+        println!(
+            "[initproc] Reaped task {} with exit code {}.",
+            pid, exit_code
+        );
+    }
+    0
+}
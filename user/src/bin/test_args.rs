@@ -0,0 +1,45 @@
+#![no_std]
+#![no_main]
+
+use user_lib::env::{AT_ENTRY, AT_PAGESZ, AT_PHDR, AT_RANDOM, getauxval};
+use user_lib::{exec, fork, println, waitpid};
+
+extern crate user_lib;
+
+const APP_NAME: &str = "test_args";
+const EXTRA_ARGS: [&str; 2] = ["hello", "world"];
+
+#[unsafe(no_mangle)]
+fn main(argc: usize, argv: &[&str]) -> i32 {
+    assert_eq!(argc, argv.len(), "argc should match the length of argv");
+    assert_eq!(argv[0], APP_NAME, "argv[0] should be the app name");
+
+    assert_eq!(getauxval(AT_PAGESZ), Some(4096), "page size should be 4K");
+    assert!(getauxval(AT_PHDR).is_some_and(|v| v != 0));
+    assert!(getauxval(AT_ENTRY).is_some_and(|v| v != 0));
+    assert!(getauxval(AT_RANDOM).is_some_and(|v| v != 0));
+
+    if argc > 1 {
+        assert_eq!(&argv[1..], &EXTRA_ARGS, "extra args should be passed");
+        println!("Child: received args {:?}", argv);
+        return 0;
+    }
+
+    println!("Test args.");
+
+    let pid = fork();
+    assert!(pid >= 0, "fork should succeed");
+
+    if pid == 0 {
+        exec(APP_NAME, &[APP_NAME, EXTRA_ARGS[0], EXTRA_ARGS[1]]);
+        println!("Test args failed if you see this line!");
+        return -1;
+    }
+
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), pid);
+    assert_eq!(exit_code, 0, "child should see the extra args");
+
+    println!("Test args OK!");
+    0
+}
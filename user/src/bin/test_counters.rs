@@ -0,0 +1,49 @@
+#![no_std]
+#![no_main]
+
+use core::time::Duration;
+
+use user_lib::time::{
+    COUNTER_CYCLE, COUNTER_INSTRET, COUNTER_TIME, Instant, read_cycle, read_instret, read_time,
+};
+use user_lib::{exit, fork, println, set_user_counters, sleep, waitpid};
+
+extern crate user_lib;
+
+const ALL_COUNTERS: usize = COUNTER_CYCLE | COUNTER_TIME | COUNTER_INSTRET;
+const SLEEP_MS: usize = 50;
+const KILLED_EXIT_CODE: i32 = -1;
+
+#[unsafe(no_mangle)]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    println!("Test counters.");
+
+    let start = Instant::now();
+    let (cycle, instret) = (read_cycle(), read_instret());
+    sleep(SLEEP_MS);
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(SLEEP_MS as u64));
+    assert!(read_cycle() >= cycle);
+    assert!(read_instret() > instret);
+    println!("Slept for {:?} by the time counter.", elapsed);
+
+    assert_eq!(set_user_counters(1 << 3), -1, "the mask should be invalid");
+    assert_eq!(set_user_counters(ALL_COUNTERS), ALL_COUNTERS as isize);
+
+    // The child denies itself the time counter, so reading
+    // it should get the child killed.
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(set_user_counters(COUNTER_CYCLE), ALL_COUNTERS as isize);
+        let _ = read_cycle();
+        let _ = read_time();
+        exit(0);
+    }
+
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), pid);
+    assert_eq!(exit_code, KILLED_EXIT_CODE, "the child should be killed");
+
+    println!("Test counters OK!");
+    0
+}
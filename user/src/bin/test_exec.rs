@@ -0,0 +1,30 @@
+#![no_std]
+#![no_main]
+
+use user_lib::{exec, fork, println, yield_now};
+
+extern crate user_lib;
+
+#[unsafe(no_mangle)]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    println!("Test exec.");
+
+    let result = exec("no_such_app", &["no_such_app"]);
+    assert_eq!(result, -1, "exec an unknown app should fail");
+
+    let pid = fork();
+    assert!(pid >= 0, "fork should succeed");
+
+    if pid == 0 {
+        println!("Child: executes 00_helloworld, which should say hello.");
+        exec("00_helloworld", &["00_helloworld"]);
+        println!("Test exec failed if you see this line!");
+        return -1;
+    }
+
+    for _ in 0..5 {
+        yield_now();
+    }
+    println!("Test exec OK!");
+    0
+}
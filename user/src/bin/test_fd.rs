@@ -0,0 +1,65 @@
+#![no_std]
+#![no_main]
+
+use user_lib::fs::{O_RDONLY, O_RDWR, O_WRONLY, S_IFCHR, S_IFMT, STDOUT, Stat};
+use user_lib::{close, dup, dup2, exit, fork, fstat, open, println, read, waitpid, write};
+
+extern crate user_lib;
+
+const HIGH_FD: usize = 10;
+
+#[unsafe(no_mangle)]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    println!("Test fd.");
+
+    assert_eq!(open("/no/such/file", O_RDONLY), -1);
+    assert_eq!(open("/dev/null", 0x1000), -1, "the flags should be invalid");
+
+    // The lowest free descriptor follows stdin, stdout and
+    // stderr.
+    let null_fd = open("/dev/null", O_WRONLY);
+    assert_eq!(null_fd, 3);
+    let null_fd = null_fd as usize;
+    assert_eq!(write(null_fd, b"discarded"), 9);
+    let mut buf = [0xffu8; 16];
+    assert_eq!(read(null_fd, &mut buf), -1, "the fd should be write-only");
+
+    let zero_fd = open("/dev/zero", O_RDWR) as usize;
+    assert_eq!(read(zero_fd, &mut buf), buf.len() as isize);
+    assert!(buf.iter().all(|&b| b == 0));
+
+    let mut stat = Stat::default();
+    assert_eq!(fstat(zero_fd, &mut stat), 0);
+    assert_eq!(stat.mode & S_IFMT, S_IFCHR);
+
+    // The duplicated descriptors share the same file.
+    let dup_fd = dup(STDOUT);
+    assert_eq!(dup_fd, 5);
+    assert_eq!(write(dup_fd as usize, b"Written through dup.\n"), 21);
+    assert_eq!(dup2(STDOUT, HIGH_FD), HIGH_FD as isize);
+    assert_eq!(write(HIGH_FD, b"Written through dup2.\n"), 22);
+    assert_eq!(dup2(zero_fd, HIGH_FD), HIGH_FD as isize);
+    assert_eq!(read(HIGH_FD, &mut buf), buf.len() as isize);
+
+    // A child inherits the open descriptors.
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(write(dup_fd as usize, b"Written by the child.\n"), 22);
+        assert_eq!(close(dup_fd as usize), 0);
+        exit(0);
+    }
+    let mut exit_code = -1;
+    assert_eq!(waitpid(pid, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+
+    // Closing in the child does not affect the parent.
+    assert_eq!(close(dup_fd as usize), 0);
+    assert_eq!(close(dup_fd as usize), -1);
+    assert_eq!(write(dup_fd as usize, b"closed"), -1);
+    assert_eq!(close(null_fd), 0);
+    assert_eq!(close(zero_fd), 0);
+    assert_eq!(close(HIGH_FD), 0);
+
+    println!("Test fd OK!");
+    0
+}
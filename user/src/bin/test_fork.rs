@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+use user_lib::{fork, println, yield_now};
+
+extern crate user_lib;
+
+static mut DATA_VALUE: usize = 2025;
+
+#[unsafe(no_mangle)]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    let mut stack_value = 77;
+
+    println!("Test fork.");
+    let pid = fork();
+    assert!(pid >= 0, "fork should succeed");
+
+    if pid == 0 {
+        println!("Child: writes to the copy-on-write pages.");
+        unsafe { (&raw mut DATA_VALUE).write_volatile(1) };
+        stack_value = 2;
+        assert_eq!(unsafe { (&raw const DATA_VALUE).read_volatile() }, 1);
+        assert_eq!(stack_value, 2);
+        println!("Child: ok.");
+        return 0;
+    }
+
+    println!("Parent: forked child {}.", pid);
+    for _ in 0..5 {
+        yield_now();
+    }
+    assert_eq!(unsafe { (&raw const DATA_VALUE).read_volatile() }, 2025);
+    assert_eq!(stack_value, 77);
+
+    unsafe { (&raw mut DATA_VALUE).write_volatile(3) };
+    assert_eq!(unsafe { (&raw const DATA_VALUE).read_volatile() }, 3);
+    println!("Test fork OK!");
+    0
+}
@@ -0,0 +1,63 @@
+#![no_std]
+#![no_main]
+
+use user_lib::fs::{O_CREAT, O_RDONLY, O_RDWR, O_TRUNC, O_WRONLY, S_IFMT, S_IFREG, Stat};
+use user_lib::{close, fstat, open, println, read, write};
+
+extern crate user_lib;
+
+const PATH: &str = "/mnt/test_fs.txt";
+const GREETING: &[u8] = b"Hello, easy-fs!";
+/// Longer than the direct blocks of an inode, so that the
+/// indirect block is used too.
+const LARGE_LEN: usize = 16 * 1024;
+
+#[unsafe(no_mangle)]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    println!("Test fs.");
+
+    let large: [u8; LARGE_LEN] = core::array::from_fn(|i| (i % 251) as u8);
+
+    let fd = open(PATH, O_WRONLY | O_CREAT | O_TRUNC);
+    assert!(fd >= 0, "the file should be created");
+    let fd = fd as usize;
+    assert_eq!(write(fd, GREETING), GREETING.len() as isize);
+    assert_eq!(write(fd, &large), LARGE_LEN as isize);
+    let mut buf = [0u8; LARGE_LEN];
+    assert_eq!(read(fd, &mut buf), -1, "the fd should be write-only");
+    assert_eq!(close(fd), 0);
+
+    // The content persists after the file is closed, and
+    // is read from the start.
+    let fd = open(PATH, O_RDONLY) as usize;
+    let mut stat = Stat::default();
+    assert_eq!(fstat(fd, &mut stat), 0);
+    assert_eq!(stat.mode & S_IFMT, S_IFREG);
+    assert_eq!(stat.size, (GREETING.len() + LARGE_LEN) as u64);
+
+    assert_eq!(
+        read(fd, &mut buf[..GREETING.len()]),
+        GREETING.len() as isize
+    );
+    assert_eq!(&buf[..GREETING.len()], GREETING);
+    let mut read_len = 0;
+    while read_len < LARGE_LEN {
+        let len = read(fd, &mut buf[read_len..]);
+        assert!(len > 0, "the file should not end early");
+        read_len += len as usize;
+    }
+    assert!(buf == large, "the content should be read back");
+    assert_eq!(read(fd, &mut buf), 0, "the file should end");
+    assert_eq!(write(fd, GREETING), -1, "the fd should be read-only");
+    assert_eq!(close(fd), 0);
+
+    // Truncating discards the content.
+    let fd = open(PATH, O_RDWR | O_TRUNC) as usize;
+    assert_eq!(fstat(fd, &mut stat), 0);
+    assert_eq!(stat.size, 0);
+    assert_eq!(read(fd, &mut buf), 0);
+    assert_eq!(close(fd), 0);
+
+    println!("Test fs OK!");
+    0
+}
@@ -0,0 +1,153 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+use user_lib::sync::{Condvar, Mutex, Once};
+use user_lib::task::{TaskState, join_threads, spawn_threads, wait_task_state};
+use user_lib::time::TimeSpec;
+use user_lib::{exit, futex_wait, futex_wake, get_time, println, thread_create, yield_now};
+
+extern crate user_lib;
+
+const TOTAL_THREADS: usize = 4;
+const ADDS_PER_THREAD: usize = 1_000;
+const TOTAL_ITEMS: usize = 100;
+const TIMEOUT_MS: usize = 20;
+
+/// The futex word of [test_wake].
+static WORD: AtomicU32 = AtomicU32::new(0);
+/// A plain counter, which only the [Mutex] protects.
+static COUNTER: Mutex<usize> = Mutex::new(0);
+/// The (produced, consumed) items of [test_condvar].
+static ITEMS: Mutex<(usize, usize)> = Mutex::new((0, 0));
+static ITEMS_READY: Condvar = Condvar::new();
+static INIT: Once = Once::new();
+static INIT_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+#[unsafe(no_mangle)]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    println!("Test futex.");
+
+    test_invalid();
+    test_timeout();
+    test_wake();
+    test_mutex();
+    test_condvar();
+    test_once();
+
+    println!("Test futex OK!");
+    0
+}
+
+fn test_invalid() {
+    let word = AtomicU32::new(1);
+    assert_eq!(futex_wait(&word, 0, None), -2, "the word is not 0");
+    assert_eq!(futex_wake(&word, 1), 0, "no thread is waiting");
+
+    let invalid = TimeSpec {
+        sec: 0,
+        nsec: 1 << 40,
+    };
+    assert_eq!(futex_wait(&word, 1, Some(&invalid)), -1);
+}
+
+fn test_timeout() {
+    let word = AtomicU32::new(0);
+    let start = get_time();
+    let timeout = TimeSpec::from_millis(TIMEOUT_MS);
+    assert_eq!(futex_wait(&word, 0, Some(&timeout)), -3);
+    assert!(get_time() - start >= TIMEOUT_MS as isize);
+}
+
+/// Wakes a thread once the kernel reports it blocked on
+/// [WORD].
+fn test_wake() {
+    let tid = thread_create(wait_word_and_exit, 0);
+    assert!(tid > 0, "thread_create should succeed");
+    wait_task_state(tid, TaskState::Blocked);
+
+    WORD.store(1, Ordering::Relaxed);
+    assert_eq!(futex_wake(&WORD, usize::MAX), 1);
+    join_threads(&[tid]);
+}
+
+extern "C" fn wait_word_and_exit(_arg: usize) -> ! {
+    while WORD.load(Ordering::Relaxed) == 0 {
+        futex_wait(&WORD, 0, None);
+    }
+    exit(0);
+    unreachable!()
+}
+
+/// Runs threads that yield while holding the lock, so that
+/// the others block on it.
+fn test_mutex() {
+    let tids = spawn_threads::<TOTAL_THREADS>(add_and_exit);
+    join_threads(&tids);
+    assert_eq!(*COUNTER.lock(), TOTAL_THREADS * ADDS_PER_THREAD);
+    assert!(COUNTER.try_lock().is_some());
+}
+
+extern "C" fn add_and_exit(_arg: usize) -> ! {
+    for i in 0..ADDS_PER_THREAD {
+        let mut counter = COUNTER.lock();
+        let value = *counter;
+        if i % 100 == 0 {
+            yield_now();
+        }
+        *counter = value + 1;
+    }
+    exit(0);
+    unreachable!()
+}
+
+/// Consumes the items produced by the main thread in other
+/// threads, which wait on a [Condvar] while there are none.
+fn test_condvar() {
+    let tids = spawn_threads::<TOTAL_THREADS>(consume_and_exit);
+    for _ in 0..TOTAL_ITEMS {
+        ITEMS.lock().0 += 1;
+        ITEMS_READY.notify_one();
+        yield_now();
+    }
+    join_threads(&tids);
+    assert_eq!(*ITEMS.lock(), (0, TOTAL_ITEMS));
+}
+
+extern "C" fn consume_and_exit(_arg: usize) -> ! {
+    let mut items = ITEMS.lock();
+    loop {
+        while items.0 == 0 && items.1 < TOTAL_ITEMS {
+            items = ITEMS_READY.wait(items);
+        }
+        if items.1 == TOTAL_ITEMS {
+            break;
+        }
+        items.0 -= 1;
+        items.1 += 1;
+    }
+    drop(items);
+
+    // Lets the others still waiting see that all is done.
+    ITEMS_READY.notify_all();
+    exit(0);
+    unreachable!()
+}
+
+fn test_once() {
+    let tids = spawn_threads::<TOTAL_THREADS>(init_and_exit);
+    join_threads(&tids);
+    assert!(INIT.is_completed());
+    assert_eq!(INIT_COUNT.load(Ordering::Relaxed), 1);
+}
+
+extern "C" fn init_and_exit(_arg: usize) -> ! {
+    INIT.call_once(|| {
+        yield_now();
+        INIT_COUNT.fetch_add(1, Ordering::Relaxed);
+    });
+    assert_eq!(INIT_COUNT.load(Ordering::Relaxed), 1);
+    exit(0);
+    unreachable!()
+}
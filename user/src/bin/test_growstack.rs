@@ -13,7 +13,7 @@ const USER_SPACE_END: usize = 0x40_0000_0000 - PAGE_SIZE_BYTES;
 const USER_STACK_MAX_SIZE_BYTES: usize = 8 << 20; // 8 MiB
 
 #[unsafe(no_mangle)]
-fn main() -> i32 {
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
     println!("Test growing of user stack.");
 
     println!("Try to read an address that triggers stack grow.");
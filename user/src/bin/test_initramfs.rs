@@ -0,0 +1,62 @@
+#![no_std]
+#![no_main]
+
+use user_lib::fs::{O_CREAT, O_RDONLY, O_RDWR, O_WRONLY, S_IFMT, S_IFREG, Stat};
+use user_lib::{close, exec, fork, fstat, open, println, read, waitpid};
+
+extern crate user_lib;
+
+const MOTD_PATH: &str = "/etc/motd.txt";
+/// The same file that the build.rs packs into the initramfs.
+const MOTD: &[u8] = include_bytes!("../../initramfs/etc/motd.txt");
+const ELF_MAGIC: &[u8] = b"\x7fELF";
+
+#[unsafe(no_mangle)]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    println!("Test initramfs.");
+
+    let fd = open(MOTD_PATH, O_RDONLY);
+    assert!(fd >= 0, "the data file should be in the initramfs");
+    let fd = fd as usize;
+    let mut stat = Stat::default();
+    assert_eq!(fstat(fd, &mut stat), 0);
+    assert_eq!(stat.mode & S_IFMT, S_IFREG);
+    assert_eq!(stat.size, MOTD.len() as u64);
+    let mut buf = [0u8; 256];
+    assert_eq!(read(fd, &mut buf), MOTD.len() as isize);
+    assert_eq!(&buf[..MOTD.len()], MOTD);
+    assert_eq!(read(fd, &mut buf), 0, "the read should reach EOF");
+    assert_eq!(close(fd), 0);
+
+    let fd = open("/bin/initproc", O_RDONLY);
+    assert!(fd >= 0, "the apps should be in /bin");
+    let fd = fd as usize;
+    assert_eq!(
+        read(fd, &mut buf[..ELF_MAGIC.len()]),
+        ELF_MAGIC.len() as isize
+    );
+    assert_eq!(&buf[..ELF_MAGIC.len()], ELF_MAGIC);
+    assert_eq!(close(fd), 0);
+
+    // The initramfs is read-only.
+    assert!(open(MOTD_PATH, O_WRONLY) < 0);
+    assert!(open(MOTD_PATH, O_RDWR) < 0);
+    assert!(open("/etc/new.txt", O_WRONLY | O_CREAT) < 0);
+    assert!(open("/etc", O_RDONLY) < 0, "a directory is not a file");
+    assert!(open("/etc/no_such_file", O_RDONLY) < 0);
+
+    // Apps can be executed by their absolute paths.
+    let pid = fork();
+    assert!(pid >= 0, "fork should succeed");
+    if pid == 0 {
+        exec("/bin/00_helloworld", &["00_helloworld"]);
+        println!("Test initramfs failed if you see this line!");
+        return -1;
+    }
+    let mut exit_code = -1;
+    assert_eq!(waitpid(pid, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+
+    println!("Test initramfs OK!");
+    0
+}
@@ -13,7 +13,7 @@ const PROT_READ: usize = 2;
 const PROT_WRITE: usize = 4;
 
 #[unsafe(no_mangle)]
-pub fn main() -> i32 {
+pub fn main(_argc: usize, _argv: &[&str]) -> i32 {
     // Try to map an area that is not in user space
     let addr = USER_SPACE_END;
     let len = 1;
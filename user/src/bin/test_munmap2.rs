@@ -11,7 +11,7 @@ const USER_SPACE_END: usize = 0x40_0000_0000 - PAGE_SIZE_BYTES;
 const USER_STACK_MAX_SIZE_BYTES: usize = 8 << 20; // 8 MiB
 
 #[unsafe(no_mangle)]
-pub fn main() -> i32 {
+pub fn main(_argc: usize, _argv: &[&str]) -> i32 {
     println!("Try to unmap the user stack. Kernel should kill this app due to page fault.");
     let len = USER_STACK_MAX_SIZE_BYTES;
     let addr = USER_SPACE_END - len;
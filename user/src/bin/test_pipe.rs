@@ -0,0 +1,159 @@
+#![no_std]
+#![no_main]
+
+use user_lib::fs::{S_IFIFO, S_IFMT, STDOUT, Stat};
+use user_lib::{close, dup2, exec, exit, fork, fstat, pipe, println, read, waitpid, write};
+
+extern crate user_lib;
+
+/// More than a pipe holds, so that the writer blocks.
+const LARGE_LEN: usize = 10 * 1024;
+
+#[unsafe(no_mangle)]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    println!("Test pipe.");
+
+    test_simple();
+    test_blocking();
+    test_closed_reader();
+    test_capture();
+    test_capture_exec();
+
+    println!("Test pipe OK!");
+    0
+}
+
+fn test_simple() {
+    let mut fds = [0; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    let [read_fd, write_fd] = fds;
+
+    assert_eq!(write(write_fd, b"hello"), 5);
+    let mut stat = Stat::default();
+    assert_eq!(fstat(read_fd, &mut stat), 0);
+    assert_eq!(stat.mode & S_IFMT, S_IFIFO);
+    assert_eq!(stat.size, 5);
+
+    let mut buf = [0u8; 16];
+    assert_eq!(read(read_fd, &mut buf), 5, "a read returns what is there");
+    assert_eq!(&buf[..5], b"hello");
+    assert_eq!(read(write_fd, &mut buf), -1, "the write end is write-only");
+    assert_eq!(write(read_fd, b"x"), -1, "the read end is read-only");
+
+    assert_eq!(close(write_fd), 0);
+    assert_eq!(read(read_fd, &mut buf), 0, "no writer is left");
+    assert_eq!(close(read_fd), 0);
+}
+
+/// Passes more data than the pipe holds from a child, and
+/// reads until the child exits.
+fn test_blocking() {
+    let mut fds = [0; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    let [read_fd, write_fd] = fds;
+
+    let pid = fork();
+    assert!(pid >= 0, "fork should succeed");
+    if pid == 0 {
+        assert_eq!(close(read_fd), 0);
+        let data: [u8; LARGE_LEN] = core::array::from_fn(|i| (i % 251) as u8);
+        assert_eq!(write(write_fd, &data), LARGE_LEN as isize);
+        exit(0);
+    }
+    assert_eq!(close(write_fd), 0);
+
+    let mut buf = [0u8; LARGE_LEN + 1];
+    let len = read_all(read_fd, &mut buf);
+    assert_eq!(len, LARGE_LEN);
+    assert!(
+        buf[..len]
+            .iter()
+            .enumerate()
+            .all(|(i, &b)| b == (i % 251) as u8)
+    );
+    assert_eq!(close(read_fd), 0);
+    wait_ok(pid);
+}
+
+fn test_closed_reader() {
+    let mut fds = [0; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    let [read_fd, write_fd] = fds;
+
+    assert_eq!(close(read_fd), 0);
+    assert_eq!(write(write_fd, b"lost"), -1, "no reader is left");
+    assert_eq!(close(write_fd), 0);
+}
+
+/// Reads what a child prints to its stdout.
+fn test_capture() {
+    let mut fds = [0; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    let [read_fd, write_fd] = fds;
+
+    let pid = fork();
+    assert!(pid >= 0, "fork should succeed");
+    if pid == 0 {
+        assert_eq!(dup2(write_fd, STDOUT), STDOUT as isize);
+        assert_eq!(close(read_fd), 0);
+        assert_eq!(close(write_fd), 0);
+        println!("Captured {}.", 42);
+        exit(0);
+    }
+    assert_eq!(close(write_fd), 0);
+
+    let mut buf = [0u8; 64];
+    let len = read_all(read_fd, &mut buf);
+    assert_eq!(&buf[..len], b"Captured 42.\n");
+    assert_eq!(close(read_fd), 0);
+    wait_ok(pid);
+}
+
+/// Reads what another app prints, since exec keeps the open
+/// files.
+fn test_capture_exec() {
+    let mut fds = [0; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    let [read_fd, write_fd] = fds;
+
+    let pid = fork();
+    assert!(pid >= 0, "fork should succeed");
+    if pid == 0 {
+        assert_eq!(dup2(write_fd, STDOUT), STDOUT as isize);
+        assert_eq!(close(read_fd), 0);
+        assert_eq!(close(write_fd), 0);
+        exec("00_helloworld", &["00_helloworld"]);
+        exit(-1);
+    }
+    assert_eq!(close(write_fd), 0);
+
+    let mut buf = [0u8; 64];
+    let len = read_all(read_fd, &mut buf);
+    assert_eq!(
+        &buf[..len],
+        b"h\ne\nl\nl\no\n \nw\no\nr\nl\nd\n!\n!\n!\n!\n!\n"
+    );
+    assert_eq!(close(read_fd), 0);
+    wait_ok(pid);
+}
+
+/// Reads the `fd` into the `buf` until the end of the file
+/// or the `buf` is full, and returns the length read.
+fn read_all(fd: usize, buf: &mut [u8]) -> usize {
+    let mut len = 0;
+    while len < buf.len() {
+        let read_len = read(fd, &mut buf[len..]);
+        assert!(read_len >= 0);
+        if read_len == 0 {
+            break;
+        }
+        len += read_len as usize;
+    }
+    len
+}
+
+fn wait_ok(pid: isize) {
+    let mut exit_code = -1;
+    assert_eq!(waitpid(pid, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+}
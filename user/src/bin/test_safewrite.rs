@@ -36,7 +36,7 @@ unsafe fn stack_range() -> (usize, usize) {
 }
 
 #[unsafe(no_mangle)]
-pub fn main() -> i32 {
+pub fn main(_argc: usize, _argv: &[&str]) -> i32 {
     assert_eq!(
         write(STDOUT, unsafe {
             #[allow(invalid_null_arguments)]
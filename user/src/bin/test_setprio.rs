@@ -0,0 +1,50 @@
+#![no_std]
+#![no_main]
+
+use user_lib::task::{SchedPolicy, TaskInfo, TaskState};
+use user_lib::{exit, fork, get_task_info, println, set_priority, waitpid, yield_now};
+
+extern crate user_lib;
+
+#[unsafe(no_mangle)]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    println!("Test set_priority.");
+
+    assert_eq!(set_priority(-1), -1, "negative priority should fail");
+    assert_eq!(set_priority(1), -1, "priority below 2 should fail");
+    assert_eq!(set_priority(8), 8, "valid priority should succeed");
+
+    let pid = fork();
+    assert!(pid >= 0, "fork should succeed");
+
+    if pid == 0 {
+        assert_eq!(set_priority(4), 4);
+        for _ in 0..10 {
+            yield_now();
+        }
+        exit(0);
+    }
+
+    // The child is kept as a zombie until it is reaped.
+    let mut info = TaskInfo::new_placeholder();
+    loop {
+        assert_eq!(get_task_info(pid as usize, &raw mut info), 0);
+        if info.state == TaskState::Zombie {
+            break;
+        }
+        yield_now();
+    }
+
+    println!("Child: {:?}", info.stastics);
+    match info.stastics.sched_policy {
+        SchedPolicy::RoundRobin => assert_eq!(info.stastics.sched_pass, 0),
+        SchedPolicy::Stride => assert!(info.stastics.sched_pass > 0),
+    }
+
+    let mut exit_code = -1;
+    assert_eq!(waitpid(pid, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+
+    println!("Test set_priority OK!");
+    0
+}
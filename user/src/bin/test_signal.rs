@@ -0,0 +1,232 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use user_lib::signal::{
+    SIG_BLOCK, SIG_DFL, SIG_UNBLOCK, SIGHUP, SIGILL, SIGKILL, SIGSEGV, SIGTERM, SIGUSR1, SIGUSR2,
+    SigAction, sig_bit,
+};
+use user_lib::task::{TaskState, wait_task_state};
+use user_lib::{
+    close, exit, fork, kill, pipe, println, read, sigaction, sigprocmask, sleep, waitpid, write,
+    yield_now,
+};
+
+extern crate user_lib;
+
+const KILLED_EXIT_CODE: i32 = -1;
+/// Added to the signal number by [exit_on_signal].
+const SIGNAL_EXIT_CODE_BASE: i32 = 100;
+
+/// The signals received by [record_signal], as a mask.
+static RECEIVED: AtomicU64 = AtomicU64::new(0);
+
+#[unsafe(no_mangle)]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    println!("Test signal.");
+
+    test_invalid();
+    test_handler();
+    test_ignore();
+    test_default();
+    test_fault(SIGSEGV, || unsafe {
+        core::ptr::null_mut::<u8>().write_volatile(0)
+    });
+    test_fault(SIGILL, || unsafe { core::arch::asm!("unimp") });
+    test_blocked_fault();
+    test_interrupt();
+
+    println!("Test signal OK!");
+    0
+}
+
+fn test_invalid() {
+    let action = SigAction::new(record_signal);
+    assert_eq!(sigaction(SIGKILL, Some(&action), None), -1);
+    assert_eq!(sigaction(0, Some(&action), None), -1);
+    assert_eq!(sigaction(64, None, None), -1);
+    assert_eq!(kill(0, SIGUSR1), -1, "process groups are not supported");
+    assert_eq!(kill(1 << 20, SIGUSR1), -1, "the task should not exist");
+    assert_eq!(sigprocmask(7, Some(0), None), -1);
+
+    let mut prev_action = SigAction::ignore();
+    assert_eq!(sigaction(SIGKILL, None, Some(&mut prev_action)), 0);
+    assert_eq!(prev_action.handler, SIG_DFL);
+}
+
+/// Calls a handler in a child for each signal sent, where
+/// the blocked one waits until it is unblocked.
+fn test_handler() {
+    let (pid, read_fd) = fork_ready(|| {
+        let action = SigAction::new(record_signal);
+        assert_eq!(sigaction(SIGUSR1, Some(&action), None), 0);
+        assert_eq!(sigaction(SIGUSR2, Some(&action), None), 0);
+        let mut prev_set = u64::MAX;
+        assert_eq!(
+            sigprocmask(SIG_BLOCK, Some(sig_bit(SIGUSR2)), Some(&mut prev_set)),
+            0
+        );
+        assert_eq!(prev_set, 0);
+    });
+    if pid == 0 {
+        // The registers are restored after each handler, so
+        // the loop goes on as if nothing happened.
+        while RECEIVED.load(Ordering::Relaxed) & sig_bit(SIGUSR1) == 0 {
+            yield_now();
+        }
+        assert_eq!(RECEIVED.load(Ordering::Relaxed), sig_bit(SIGUSR1));
+
+        // The pending signal arrives once it is unblocked.
+        assert_eq!(sigprocmask(SIG_UNBLOCK, Some(sig_bit(SIGUSR2)), None), 0);
+        assert_eq!(
+            RECEIVED.load(Ordering::Relaxed),
+            sig_bit(SIGUSR1) | sig_bit(SIGUSR2)
+        );
+        exit(0);
+    }
+
+    assert_eq!(kill(pid, SIGUSR2), 0);
+    assert_eq!(kill(pid, SIGUSR1), 0);
+    assert_eq!(wait_exit_code(pid), 0);
+    assert_eq!(close(read_fd), 0);
+}
+
+/// Discards an ignored signal, while a lower-numbered one
+/// would be handled first.
+fn test_ignore() {
+    let (pid, read_fd) = fork_ready(|| {
+        assert_eq!(sigaction(SIGHUP, Some(&SigAction::ignore()), None), 0);
+        let action = SigAction::new(exit_on_signal);
+        assert_eq!(sigaction(SIGUSR1, Some(&action), None), 0);
+    });
+    if pid == 0 {
+        loop {
+            yield_now();
+        }
+    }
+
+    assert_eq!(kill(pid, SIGHUP), 0);
+    assert_eq!(kill(pid, SIGUSR1), 0);
+    assert_eq!(wait_exit_code(pid), SIGNAL_EXIT_CODE_BASE + SIGUSR1 as i32);
+    assert_eq!(close(read_fd), 0);
+}
+
+/// Terminates the children by default, and always by
+/// SIGKILL.
+fn test_default() {
+    for signum in [SIGTERM, SIGKILL] {
+        let (pid, read_fd) = fork_ready(|| {
+            assert_eq!(sigaction(SIGUSR1, Some(&SigAction::ignore()), None), 0);
+        });
+        if pid == 0 {
+            loop {
+                yield_now();
+            }
+        }
+
+        assert_eq!(kill(pid, 0), 0, "the child should exist");
+        assert_eq!(kill(pid, SIGUSR1), 0);
+        assert_eq!(kill(pid, signum), 0);
+        assert_eq!(wait_exit_code(pid), KILLED_EXIT_CODE);
+        assert_eq!(close(read_fd), 0);
+    }
+}
+
+/// Catches the signal `signum` raised by the `fault` of a
+/// child.
+fn test_fault(signum: usize, fault: fn()) {
+    let pid = fork();
+    assert!(pid >= 0, "fork should succeed");
+    if pid == 0 {
+        let action = SigAction::new(exit_on_signal);
+        assert_eq!(sigaction(signum, Some(&action), None), 0);
+        fault();
+        exit(0);
+    }
+    assert_eq!(wait_exit_code(pid), SIGNAL_EXIT_CODE_BASE + signum as i32);
+}
+
+/// Terminates a child whose fault raises a blocked signal,
+/// since it cannot go on.
+fn test_blocked_fault() {
+    let pid = fork();
+    assert!(pid >= 0, "fork should succeed");
+    if pid == 0 {
+        let action = SigAction::new(exit_on_signal);
+        assert_eq!(sigaction(SIGSEGV, Some(&action), None), 0);
+        assert_eq!(sigprocmask(SIG_BLOCK, Some(sig_bit(SIGSEGV)), None), 0);
+        unsafe { core::ptr::null_mut::<u8>().write_volatile(0) };
+        exit(0);
+    }
+    assert_eq!(wait_exit_code(pid), KILLED_EXIT_CODE);
+}
+
+/// Wakes a child blocked on a pipe and then sleeping with
+/// a signal, which makes each syscall fail once handled.
+/// SIGKILL ends the last sleep.
+fn test_interrupt() {
+    let (pid, read_fd) = fork_ready(|| {
+        let action = SigAction::new(record_signal);
+        assert_eq!(sigaction(SIGUSR1, Some(&action), None), 0);
+    });
+    if pid == 0 {
+        let mut fds = [0; 2];
+        assert_eq!(pipe(&mut fds), 0);
+        let mut buf = [0u8; 1];
+        assert_eq!(read(fds[0], &mut buf), -1, "nothing is written");
+        assert_eq!(RECEIVED.load(Ordering::Relaxed), sig_bit(SIGUSR1));
+        assert_eq!(sleep(60_000), -1, "the sleep should be interrupted");
+        sleep(60_000);
+        exit(0);
+    }
+
+    wait_task_state(pid, TaskState::Blocked);
+    assert_eq!(kill(pid, SIGUSR1), 0);
+    wait_task_state(pid, TaskState::Sleeping);
+    assert_eq!(kill(pid, SIGUSR1), 0);
+    wait_task_state(pid, TaskState::Sleeping);
+    assert_eq!(kill(pid, SIGKILL), 0);
+    assert_eq!(wait_exit_code(pid), KILLED_EXIT_CODE);
+    assert_eq!(close(read_fd), 0);
+}
+
+extern "C" fn record_signal(signum: usize) {
+    RECEIVED.fetch_or(sig_bit(signum), Ordering::Relaxed);
+}
+
+extern "C" fn exit_on_signal(signum: usize) {
+    exit(SIGNAL_EXIT_CODE_BASE + signum as i32);
+}
+
+/// Forks a child that runs the `setup` and then reports it
+/// through a pipe, which the parent waits for. Returns the
+/// result of fork and the read end of the pipe in the
+/// parent.
+fn fork_ready(setup: fn()) -> (isize, usize) {
+    let mut fds = [0; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    let [read_fd, write_fd] = fds;
+
+    let pid = fork();
+    assert!(pid >= 0, "fork should succeed");
+    if pid == 0 {
+        assert_eq!(close(read_fd), 0);
+        setup();
+        assert_eq!(write(write_fd, b"r"), 1);
+        assert_eq!(close(write_fd), 0);
+        return (pid, read_fd);
+    }
+
+    assert_eq!(close(write_fd), 0);
+    let mut buf = [0u8; 1];
+    assert_eq!(read(read_fd, &mut buf), 1, "the child should be ready");
+    (pid, read_fd)
+}
+
+/// Reaps the child `pid` and returns its exit code.
+fn wait_exit_code(pid: isize) -> i32 {
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), pid);
+    exit_code
+}
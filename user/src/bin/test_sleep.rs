@@ -0,0 +1,57 @@
+#![no_std]
+#![no_main]
+
+use user_lib::task::{TaskInfo, TaskState};
+use user_lib::time::TimeSpec;
+use user_lib::{exit, fork, get_task_info, nanosleep, println, sleep, wait, yield_now};
+
+extern crate user_lib;
+
+const TOTAL_CHILDREN: usize = 3;
+const SLEEP_STEP_MS: usize = 100;
+
+#[unsafe(no_mangle)]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    println!("Test sleep.");
+
+    let invalid = TimeSpec {
+        sec: 0,
+        nsec: 1_000_000_000,
+    };
+    assert_eq!(nanosleep(&invalid), -1, "nsec should be less than a second");
+    assert_eq!(sleep(0), 0);
+
+    // The children sleep in the reverse order they are
+    // forked, so they should exit in the order they wake.
+    let mut pids = [0; TOTAL_CHILDREN];
+    for (i, pid) in pids.iter_mut().enumerate() {
+        *pid = fork();
+        assert!(*pid >= 0, "fork should succeed");
+
+        if *pid == 0 {
+            assert_eq!(sleep((TOTAL_CHILDREN - i) * SLEEP_STEP_MS), 0);
+            exit(i as i32);
+        }
+    }
+
+    let mut info = TaskInfo::new_placeholder();
+    assert_eq!(get_task_info(pids[0] as usize, &raw mut info), 0);
+    while info.state != TaskState::Sleeping && info.state != TaskState::Zombie {
+        yield_now();
+        assert_eq!(get_task_info(pids[0] as usize, &raw mut info), 0);
+    }
+    assert_eq!(
+        info.state,
+        TaskState::Sleeping,
+        "the child should be asleep"
+    );
+
+    for i in (0..TOTAL_CHILDREN).rev() {
+        let mut exit_code = -1;
+        assert_eq!(wait(&mut exit_code), pids[i]);
+        assert_eq!(exit_code, i as i32);
+    }
+
+    println!("Test sleep OK!");
+    0
+}
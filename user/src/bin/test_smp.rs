@@ -0,0 +1,58 @@
+#![no_std]
+#![no_main]
+
+use user_lib::task::{TaskInfo, TaskState};
+use user_lib::{exit, fork, get_task_info, println, waitpid, yield_now};
+
+extern crate user_lib;
+
+const TOTAL_CHILDREN: usize = 8;
+const MAX_HARTS: usize = 8;
+
+#[unsafe(no_mangle)]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    println!("Test smp.");
+
+    let mut pids = [0; TOTAL_CHILDREN];
+    for (i, pid) in pids.iter_mut().enumerate() {
+        *pid = fork();
+        assert!(*pid >= 0, "fork should succeed");
+
+        if *pid == 0 {
+            // Keep the hart busy long enough to be preempted
+            // and possibly stolen by another hart.
+            let mut sum = 0usize;
+            for j in 0..5_000_000 {
+                sum = sum.wrapping_add(j ^ i);
+                unsafe { (&raw mut sum).write_volatile(sum) };
+            }
+            exit(0);
+        }
+    }
+
+    let mut used_harts = [false; MAX_HARTS];
+    for pid in pids {
+        // The child is kept as a zombie until it is reaped.
+        let mut info = TaskInfo::new_placeholder();
+        loop {
+            assert_eq!(get_task_info(pid as usize, &raw mut info), 0);
+            if info.state == TaskState::Zombie {
+                break;
+            }
+            yield_now();
+        }
+
+        let hart_id = info.stastics.last_hart_id;
+        assert!(hart_id < MAX_HARTS, "hart ID should be valid");
+        used_harts[hart_id] = true;
+
+        let mut exit_code = -1;
+        assert_eq!(waitpid(pid, &mut exit_code), pid);
+        assert_eq!(exit_code, 0);
+    }
+
+    let total_used = used_harts.iter().filter(|&&used| used).count();
+    println!("Children last ran on {} hart(s).", total_used);
+    println!("Test smp OK!");
+    0
+}
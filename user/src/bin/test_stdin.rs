@@ -0,0 +1,82 @@
+#![no_std]
+#![no_main]
+
+use user_lib::fs::{O_RDONLY, S_IFCHR, S_IFMT, STDIN, Stat};
+use user_lib::{
+    close, dup2, exit, fork, fstat, get_time, open, pipe, println, read, sleep, waitpid, write,
+};
+
+extern crate user_lib;
+
+/// How long the writer waits before feeding the reader.
+const DELAY_MS: usize = 50;
+
+#[unsafe(no_mangle)]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    println!("Test stdin.");
+
+    test_console();
+    test_null();
+    test_blocked_reader();
+
+    println!("Test stdin OK!");
+    0
+}
+
+fn test_console() {
+    let mut stat = Stat::default();
+    assert_eq!(fstat(STDIN, &mut stat), 0);
+    assert_eq!(stat.mode & S_IFMT, S_IFCHR);
+
+    // An empty read returns at once without waiting for input.
+    assert_eq!(read(STDIN, &mut []), 0);
+    assert_eq!(write(STDIN, b"x"), -1, "stdin should be read-only");
+}
+
+/// Reading the console blocks until a key is typed, so the
+/// reads below go to stdin redirected elsewhere to keep the
+/// test unattended.
+fn test_null() {
+    let null_fd = open("/dev/null", O_RDONLY) as usize;
+    assert_eq!(dup2(null_fd, STDIN), STDIN as isize);
+    assert_eq!(close(null_fd), 0);
+    let mut buf = [0u8; 8];
+    assert_eq!(
+        read(STDIN, &mut buf),
+        0,
+        "the redirected stdin should be empty"
+    );
+}
+
+/// Reads stdin redirected to a pipe, which a child writes
+/// only after a delay, so that the reader blocks until then.
+fn test_blocked_reader() {
+    let mut fds = [0; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    let [read_fd, write_fd] = fds;
+    assert_eq!(dup2(read_fd, STDIN), STDIN as isize);
+    assert_eq!(close(read_fd), 0);
+
+    let start = get_time();
+    let pid = fork();
+    assert!(pid >= 0, "fork should succeed");
+    if pid == 0 {
+        sleep(DELAY_MS);
+        assert_eq!(write(write_fd, b"input"), 5);
+        exit(0);
+    }
+    assert_eq!(close(write_fd), 0);
+
+    let mut buf = [0u8; 8];
+    assert_eq!(read(STDIN, &mut buf), 5);
+    assert_eq!(&buf[..5], b"input");
+    assert!(
+        get_time() - start >= DELAY_MS as isize,
+        "the read should wait for the input"
+    );
+    assert_eq!(read(STDIN, &mut buf), 0, "no writer is left");
+
+    let mut exit_code = -1;
+    assert_eq!(waitpid(pid, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+}
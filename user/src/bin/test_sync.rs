@@ -0,0 +1,213 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use user_lib::sync::DEADLOCK;
+use user_lib::task::{TaskState, join_threads, spawn_threads, wait_task_state};
+use user_lib::{
+    condvar_create, condvar_signal, condvar_wait, enable_deadlock_detect, exit, fork, mutex_create,
+    mutex_lock, mutex_unlock, println, semaphore_create, semaphore_down, semaphore_up,
+    thread_create, waitpid, yield_now,
+};
+
+extern crate user_lib;
+
+const TOTAL_THREADS: usize = 4;
+const ADDS_PER_THREAD: usize = 1_000;
+const ITEMS_PER_THREAD: usize = 25;
+
+/// The kernel objects used by the threads, by ID.
+static MUTEX_ID: AtomicUsize = AtomicUsize::new(0);
+static OTHER_MUTEX_ID: AtomicUsize = AtomicUsize::new(0);
+static SEM_ID: AtomicUsize = AtomicUsize::new(0);
+static CONDVAR_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Only updated with a load followed by a store, so the
+/// updates are lost unless a lock protects them.
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+static IS_READY: AtomicBool = AtomicBool::new(false);
+
+#[unsafe(no_mangle)]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    println!("Test sync.");
+
+    test_mutex();
+    test_semaphore();
+    test_condvar();
+    test_deadlock();
+
+    println!("Test sync OK!");
+    0
+}
+
+/// Runs threads that yield while holding the mutex, so that
+/// the others block on it.
+fn test_mutex() {
+    let mutex_id = mutex_create() as usize;
+    assert_eq!(mutex_lock(mutex_id + 1), -1, "the mutex should not exist");
+    assert_eq!(mutex_unlock(mutex_id), -1, "the mutex is not held");
+
+    MUTEX_ID.store(mutex_id, Ordering::Relaxed);
+    COUNTER.store(0, Ordering::Relaxed);
+    join_threads(&spawn_threads::<TOTAL_THREADS>(add_and_exit));
+    assert_eq!(
+        COUNTER.load(Ordering::Relaxed),
+        TOTAL_THREADS * ADDS_PER_THREAD
+    );
+}
+
+extern "C" fn add_and_exit(_arg: usize) -> ! {
+    let mutex_id = MUTEX_ID.load(Ordering::Relaxed);
+    for i in 0..ADDS_PER_THREAD {
+        assert_eq!(mutex_lock(mutex_id), 0);
+        let value = COUNTER.load(Ordering::Relaxed);
+        if i % 100 == 0 {
+            yield_now();
+        }
+        COUNTER.store(value + 1, Ordering::Relaxed);
+        assert_eq!(mutex_unlock(mutex_id), 0);
+    }
+    exit(0);
+    unreachable!()
+}
+
+/// Consumes the items produced by the main thread in other
+/// threads, which block on a semaphore while there are none.
+fn test_semaphore() {
+    let sem_id = semaphore_create(0) as usize;
+    assert_eq!(
+        semaphore_up(sem_id + 1),
+        -1,
+        "the semaphore should not exist"
+    );
+
+    SEM_ID.store(sem_id, Ordering::Relaxed);
+    COUNTER.store(0, Ordering::Relaxed);
+    let tids = spawn_threads::<TOTAL_THREADS>(consume_and_exit);
+    for _ in 0..TOTAL_THREADS * ITEMS_PER_THREAD {
+        assert_eq!(semaphore_up(sem_id), 0);
+    }
+    join_threads(&tids);
+    assert_eq!(
+        COUNTER.load(Ordering::Relaxed),
+        TOTAL_THREADS * ITEMS_PER_THREAD
+    );
+}
+
+extern "C" fn consume_and_exit(_arg: usize) -> ! {
+    for _ in 0..ITEMS_PER_THREAD {
+        assert_eq!(semaphore_down(SEM_ID.load(Ordering::Relaxed)), 0);
+        COUNTER.fetch_add(1, Ordering::Relaxed);
+    }
+    exit(0);
+    unreachable!()
+}
+
+/// Wakes a thread waiting on a condition variable once the
+/// flag it checks under the mutex is set.
+fn test_condvar() {
+    let mutex_id = mutex_create() as usize;
+    let condvar_id = condvar_create() as usize;
+    assert_eq!(
+        condvar_signal(condvar_id + 1),
+        -1,
+        "the condvar should not exist"
+    );
+    assert_eq!(
+        condvar_wait(condvar_id, mutex_id),
+        -1,
+        "the mutex is not held"
+    );
+    assert_eq!(condvar_signal(condvar_id), 0, "no thread is waiting");
+
+    MUTEX_ID.store(mutex_id, Ordering::Relaxed);
+    CONDVAR_ID.store(condvar_id, Ordering::Relaxed);
+    let tid = thread_create(wait_ready_and_exit, 0);
+    assert!(tid > 0, "thread_create should succeed");
+    wait_task_state(tid, TaskState::Blocked);
+
+    assert_eq!(mutex_lock(mutex_id), 0);
+    IS_READY.store(true, Ordering::Relaxed);
+    assert_eq!(condvar_signal(condvar_id), 0);
+    assert_eq!(mutex_unlock(mutex_id), 0);
+    join_threads(&[tid]);
+}
+
+extern "C" fn wait_ready_and_exit(_arg: usize) -> ! {
+    let mutex_id = MUTEX_ID.load(Ordering::Relaxed);
+    assert_eq!(mutex_lock(mutex_id), 0);
+    while !IS_READY.load(Ordering::Relaxed) {
+        assert_eq!(
+            condvar_wait(CONDVAR_ID.load(Ordering::Relaxed), mutex_id),
+            0
+        );
+    }
+    assert_eq!(mutex_unlock(mutex_id), 0);
+    exit(0);
+    unreachable!()
+}
+
+/// Fails the acquires that could never end in a child with
+/// the deadlock detection enabled.
+fn test_deadlock() {
+    let pid = fork();
+    assert!(pid >= 0, "fork should succeed");
+    if pid == 0 {
+        assert_eq!(enable_deadlock_detect(true), 0);
+
+        let mutex_id = mutex_create() as usize;
+        assert_eq!(mutex_lock(mutex_id), 0);
+        assert_eq!(
+            mutex_lock(mutex_id),
+            DEADLOCK,
+            "the mutex is held by itself"
+        );
+        assert_eq!(mutex_unlock(mutex_id), 0);
+
+        let sem_id = semaphore_create(1) as usize;
+        assert_eq!(semaphore_down(sem_id), 0);
+        assert_eq!(semaphore_down(sem_id), DEADLOCK);
+        assert_eq!(semaphore_up(sem_id), 0);
+
+        // The thread holds the other mutex and waits for the
+        // one held here.
+        MUTEX_ID.store(mutex_id, Ordering::Relaxed);
+        OTHER_MUTEX_ID.store(mutex_create() as usize, Ordering::Relaxed);
+        assert_eq!(mutex_lock(mutex_id), 0);
+        let tid = thread_create(lock_both_and_exit, 0);
+        assert!(tid > 0, "thread_create should succeed");
+        wait_task_state(tid, TaskState::Blocked);
+        assert_eq!(mutex_lock(OTHER_MUTEX_ID.load(Ordering::Relaxed)), DEADLOCK);
+        assert_eq!(mutex_unlock(mutex_id), 0);
+        join_threads(&[tid]);
+
+        // The thread exits holding the mutex.
+        let mutex_id = mutex_create() as usize;
+        MUTEX_ID.store(mutex_id, Ordering::Relaxed);
+        join_threads(&spawn_threads::<1>(lock_and_exit));
+        assert_eq!(mutex_lock(mutex_id), DEADLOCK, "the holder has exited");
+        exit(0);
+    }
+
+    let mut exit_code = -1;
+    assert_eq!(waitpid(pid, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+}
+
+extern "C" fn lock_and_exit(_arg: usize) -> ! {
+    assert_eq!(mutex_lock(MUTEX_ID.load(Ordering::Relaxed)), 0);
+    exit(0);
+    unreachable!()
+}
+
+extern "C" fn lock_both_and_exit(_arg: usize) -> ! {
+    let mutex_id = MUTEX_ID.load(Ordering::Relaxed);
+    let other_mutex_id = OTHER_MUTEX_ID.load(Ordering::Relaxed);
+    assert_eq!(mutex_lock(other_mutex_id), 0);
+    assert_eq!(mutex_lock(mutex_id), 0);
+    assert_eq!(mutex_unlock(mutex_id), 0);
+    assert_eq!(mutex_unlock(other_mutex_id), 0);
+    exit(0);
+    unreachable!()
+}
@@ -0,0 +1,201 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use user_lib::signal::{SIG_IGN, SIGUSR1, SigAction};
+use user_lib::task::spawn_threads;
+use user_lib::{
+    close, exit, fork, gettid, pipe, println, read, sigaction, thread_create, waitpid, waittid,
+    write, yield_now,
+};
+
+extern crate user_lib;
+
+const TOTAL_THREADS: usize = 4;
+const ADDS_PER_THREAD: usize = 10_000;
+/// More than the user stacks a process can have at once,
+/// so that the stacks of the exited threads are reused.
+const TOTAL_SEQUENTIAL_THREADS: usize = 80;
+/// Added to the argument of [add_and_exit] for its exit
+/// code.
+const THREAD_EXIT_CODE_BASE: usize = 10;
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+/// The address of a local variable of each thread.
+static STACK_ADDRS: [AtomicUsize; TOTAL_THREADS] = [const { AtomicUsize::new(0) }; TOTAL_THREADS];
+/// The write end of the pipe for [write_and_exit].
+static WRITE_FD: AtomicUsize = AtomicUsize::new(0);
+/// The read end of the pipe for [read_forever].
+static READ_FD: AtomicUsize = AtomicUsize::new(0);
+
+#[unsafe(no_mangle)]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    println!("Test thread.");
+
+    test_invalid();
+    test_shared_memory();
+    test_shared_files();
+    test_sequential();
+    test_shared_sigaction();
+    test_process_exit();
+
+    println!("Test thread OK!");
+    0
+}
+
+fn test_invalid() {
+    let mut exit_code = 0;
+    assert!(gettid() > 0);
+    assert_eq!(
+        waittid(gettid(), &mut exit_code),
+        -1,
+        "cannot wait for itself"
+    );
+    assert_eq!(
+        waittid(1 << 20, &mut exit_code),
+        -1,
+        "the thread should not exist"
+    );
+    assert_eq!(waittid(-1, &mut exit_code), -1);
+}
+
+/// Runs threads that add to the same counter on their own
+/// stacks.
+fn test_shared_memory() {
+    let tids = spawn_threads::<TOTAL_THREADS>(add_and_exit);
+    for (i, &tid) in tids.iter().enumerate() {
+        let mut exit_code = 0;
+        assert_eq!(waittid(tid, &mut exit_code), tid);
+        assert_eq!(exit_code, (THREAD_EXIT_CODE_BASE + i) as i32);
+        assert_eq!(waittid(tid, &mut exit_code), -1, "the thread is reaped");
+    }
+    assert_eq!(
+        COUNTER.load(Ordering::Relaxed),
+        TOTAL_THREADS * ADDS_PER_THREAD
+    );
+
+    let local = 0usize;
+    let main_addr = (&raw const local).addr();
+    for (i, addr) in STACK_ADDRS.iter().enumerate() {
+        let addr = addr.load(Ordering::Relaxed);
+        assert_ne!(addr, main_addr);
+        assert!(
+            STACK_ADDRS[..i]
+                .iter()
+                .all(|other| other.load(Ordering::Relaxed) != addr),
+            "each thread should have its own stack"
+        );
+    }
+}
+
+extern "C" fn add_and_exit(i: usize) -> ! {
+    let local = 0usize;
+    STACK_ADDRS[i].store((&raw const local).addr(), Ordering::Relaxed);
+    for _ in 0..ADDS_PER_THREAD {
+        COUNTER.fetch_add(1, Ordering::Relaxed);
+    }
+    exit((THREAD_EXIT_CODE_BASE + i) as i32);
+    unreachable!()
+}
+
+/// Writes through a pipe opened by the main thread, since
+/// the threads share the open files.
+fn test_shared_files() {
+    let mut fds = [0; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    let [read_fd, write_fd] = fds;
+    WRITE_FD.store(write_fd, Ordering::Relaxed);
+
+    let tid = thread_create(write_and_exit, 0);
+    assert!(tid > 0, "thread_create should succeed");
+    let mut exit_code = -1;
+    assert_eq!(waittid(tid, &mut exit_code), tid);
+    assert_eq!(exit_code, 0);
+
+    let mut buf = [0u8; 16];
+    assert_eq!(read(read_fd, &mut buf), 6);
+    assert_eq!(&buf[..6], b"thread");
+    assert_eq!(close(read_fd), 0);
+    assert_eq!(close(write_fd), 0);
+}
+
+extern "C" fn write_and_exit(_arg: usize) -> ! {
+    assert_eq!(write(WRITE_FD.load(Ordering::Relaxed), b"thread"), 6);
+    exit(0);
+    unreachable!()
+}
+
+/// Creates and waits for threads one by one, each of which
+/// gets the user stack of the previous one.
+fn test_sequential() {
+    for i in 0..TOTAL_SEQUENTIAL_THREADS {
+        let tid = thread_create(exit_with_arg, i);
+        assert!(tid > 0, "thread_create should succeed");
+        let mut exit_code = -1;
+        assert_eq!(waittid(tid, &mut exit_code), tid);
+        assert_eq!(exit_code, i as i32);
+    }
+}
+
+extern "C" fn exit_with_arg(arg: usize) -> ! {
+    exit(arg as i32);
+    unreachable!()
+}
+
+/// Sees the signal action set by another thread, since the
+/// threads share them.
+fn test_shared_sigaction() {
+    let tid = thread_create(ignore_and_exit, 0);
+    assert!(tid > 0, "thread_create should succeed");
+    let mut exit_code = -1;
+    assert_eq!(waittid(tid, &mut exit_code), tid);
+    assert_eq!(exit_code, 0);
+
+    let mut prev_action = SigAction::default();
+    assert_eq!(
+        sigaction(SIGUSR1, Some(&SigAction::default()), Some(&mut prev_action)),
+        0
+    );
+    assert_eq!(prev_action.handler, SIG_IGN);
+}
+
+extern "C" fn ignore_and_exit(_arg: usize) -> ! {
+    assert_eq!(sigaction(SIGUSR1, Some(&SigAction::ignore()), None), 0);
+    exit(0);
+    unreachable!()
+}
+
+/// Ends a child process whose other threads never exit by
+/// themselves, when its main thread exits, including one
+/// blocked on a pipe.
+fn test_process_exit() {
+    let pid = fork();
+    assert!(pid >= 0, "fork should succeed");
+    if pid == 0 {
+        let mut fds = [0; 2];
+        assert_eq!(pipe(&mut fds), 0);
+        READ_FD.store(fds[0], Ordering::Relaxed);
+        assert!(thread_create(spin_forever, 0) > 0);
+        assert!(thread_create(read_forever, 0) > 0);
+        yield_now();
+        exit(7);
+    }
+
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), pid);
+    assert_eq!(exit_code, 7);
+}
+
+extern "C" fn spin_forever(_arg: usize) -> ! {
+    loop {
+        yield_now();
+    }
+}
+
+extern "C" fn read_forever(_arg: usize) -> ! {
+    let mut buf = [0u8; 1];
+    loop {
+        read(READ_FD.load(Ordering::Relaxed), &mut buf);
+    }
+}
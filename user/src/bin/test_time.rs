@@ -0,0 +1,41 @@
+#![no_std]
+#![no_main]
+
+use user_lib::time::{CLOCK_MONOTONIC, CLOCK_REALTIME, TimeSpec, TimeVal};
+use user_lib::{clock_gettime, get_time, gettimeofday, println, sleep};
+
+extern crate user_lib;
+
+const SLEEP_MS: usize = 100;
+
+#[unsafe(no_mangle)]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    println!("Test time.");
+
+    let mut start = TimeSpec::default();
+    assert_eq!(clock_gettime(CLOCK_MONOTONIC, &mut start), 0);
+    assert!(start.nsec < 1_000_000_000);
+    assert_eq!(clock_gettime(usize::MAX, &mut start), -1);
+    assert_eq!(clock_gettime(CLOCK_MONOTONIC, &mut start), 0);
+
+    sleep(SLEEP_MS);
+    let mut end = TimeSpec::default();
+    assert_eq!(clock_gettime(CLOCK_MONOTONIC, &mut end), 0);
+    assert!(end >= start, "the monotonic clock should not go backwards");
+    let elapsed_ms = end.as_millis() - start.as_millis();
+    assert!(elapsed_ms >= SLEEP_MS, "slept only {} ms", elapsed_ms);
+
+    let now_ms = get_time();
+    assert!(now_ms >= end.as_millis() as isize);
+
+    let mut real_time = TimeSpec::default();
+    assert_eq!(clock_gettime(CLOCK_REALTIME, &mut real_time), 0);
+    let mut time_of_day = TimeVal::default();
+    assert_eq!(gettimeofday(&mut time_of_day), 0);
+    assert!(time_of_day.usec < 1_000_000);
+    assert!(time_of_day.sec >= real_time.sec);
+    println!("Real time: {} seconds since the epoch.", real_time.sec);
+
+    println!("Test time OK!");
+    0
+}
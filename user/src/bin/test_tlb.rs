@@ -0,0 +1,63 @@
+#![no_std]
+#![no_main]
+
+use user_lib::{exit, fork, mmap, munmap, println, waitpid, yield_now};
+
+extern crate user_lib;
+
+const PAGE_SIZE_BYTES: usize = 4096;
+const PROT_READ: usize = 2;
+const PROT_WRITE: usize = 4;
+
+const TOTAL_CHILDREN: usize = 8;
+const TOTAL_ROUNDS: usize = 64;
+const TOTAL_PAGES: usize = 4;
+const AREA_START: usize = 0x8000_0000;
+
+#[unsafe(no_mangle)]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    println!("Test tlb.");
+
+    let mut pids = [0; TOTAL_CHILDREN];
+    for (i, pid) in pids.iter_mut().enumerate() {
+        *pid = fork();
+        assert!(*pid >= 0, "fork should succeed");
+
+        if *pid == 0 {
+            exit(remap_rounds(i));
+        }
+    }
+
+    for pid in pids {
+        let mut exit_code = -1;
+        assert_eq!(waitpid(pid, &mut exit_code), pid);
+        assert_eq!(exit_code, 0, "child {} should see fresh pages", pid);
+    }
+
+    println!("Test tlb OK!");
+    0
+}
+
+/// Repeatedly maps, fills and unmaps the same area while
+/// yielding in between, so that the task may move between
+/// harts. A remapped page must read as zero; otherwise a
+/// stale translation to the freed page is still in use.
+fn remap_rounds(child: usize) -> i32 {
+    let len = TOTAL_PAGES * PAGE_SIZE_BYTES;
+    for round in 0..TOTAL_ROUNDS {
+        assert_eq!(mmap(AREA_START, len, PROT_READ | PROT_WRITE), 0);
+        for va in (AREA_START..AREA_START + len).step_by(PAGE_SIZE_BYTES) {
+            let value = unsafe { (va as *const usize).read_volatile() };
+            if value != 0 {
+                println!("Child {}: Stale page {:#x} in round {}", child, va, round);
+                return -1;
+            }
+            unsafe { (va as *mut usize).write_volatile((child << 32) | (round + 1)) };
+        }
+
+        yield_now();
+        assert_eq!(munmap(AREA_START, len), 0);
+        yield_now();
+    }
+    0
+}
@@ -0,0 +1,198 @@
+#![no_std]
+#![no_main]
+
+use user_lib::fs::{
+    Dirent, O_CREAT, O_RDONLY, O_RDWR, O_WRONLY, S_IFCHR, S_IFDIR, S_IFMT, S_IFREG, Stat,
+};
+use user_lib::{
+    chdir, close, exit, fork, fstat, getdents, mkdir, open, println, read, rmdir, unlink, waitpid,
+    write,
+};
+
+extern crate user_lib;
+
+/// The directories are named after the test, since the
+/// other tests run at the same time.
+const TMP_DIR: &str = "/tmp/test_vfs";
+const DISK_DIR: &str = "/mnt/test_vfs";
+/// Longer than a page, so that tmpfs uses several pages.
+const LARGE_LEN: usize = 10 * 1024;
+/// More than the entries of any directory listed here.
+const MAX_ENTRIES: usize = 8;
+
+#[unsafe(no_mangle)]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    println!("Test vfs.");
+
+    test_dir(TMP_DIR);
+    test_dir(DISK_DIR);
+    test_paths();
+    test_unlink_open();
+    test_read_only();
+
+    println!("Test vfs OK!");
+    0
+}
+
+/// Creates, lists and removes the entries of the `dir`.
+fn test_dir(dir: &str) {
+    println!("Testing {}.", dir);
+    // Clean up a previous run on the disk.
+    let _ = chdir(dir);
+    let _ = unlink("sub/file");
+    let _ = rmdir("sub");
+    let _ = unlink("file");
+    let _ = chdir("/");
+    let _ = rmdir(dir);
+
+    assert_eq!(mkdir(dir), 0);
+    assert_eq!(mkdir(dir), -1, "the directory already exists");
+    assert_eq!(chdir(dir), 0);
+    assert_eq!(mkdir("sub"), 0);
+    assert_eq!(mkdir("sub/"), -1);
+
+    let large: [u8; LARGE_LEN] = core::array::from_fn(|i| (i % 253) as u8);
+    write_file("file", &large);
+    write_file("sub/file", b"in sub");
+    let fd = open("file", O_RDWR | O_CREAT);
+    assert!(fd >= 0, "the file should be opened as is");
+    assert_eq!(close(fd as usize), 0);
+
+    let mut buf = [0u8; LARGE_LEN];
+    assert_eq!(read_file("./sub/../file", &mut buf), LARGE_LEN);
+    assert_eq!(buf, large);
+    assert_eq!(read_file("sub/./file", &mut buf), 6);
+    assert_eq!(&buf[..6], b"in sub");
+    assert_eq!(open("file/", O_RDONLY), -1, "a file is not a directory");
+
+    let mut dirents = [Dirent::new(); MAX_ENTRIES];
+    let entries = list_dir(".", &mut dirents);
+    assert_eq!(entries.len(), 2);
+    assert!(contains(entries, "file", S_IFREG));
+    assert!(contains(entries, "sub", S_IFDIR));
+
+    assert_eq!(open("sub", O_WRONLY), -1, "a directory cannot be written");
+    assert_eq!(unlink("sub"), -1, "unlink cannot remove a directory");
+    assert_eq!(rmdir("file"), -1, "rmdir cannot remove a file");
+    assert_eq!(rmdir("sub"), -1, "the directory is not empty");
+    assert_eq!(unlink("sub/file"), 0);
+    assert_eq!(rmdir("sub"), 0);
+    assert_eq!(unlink("file"), 0);
+    assert_eq!(unlink("file"), -1, "the file is removed");
+    assert_eq!(list_dir(".", &mut dirents).len(), 0);
+
+    assert_eq!(chdir(".."), 0);
+    assert_eq!(rmdir(dir), 0);
+    assert_eq!(chdir("/"), 0);
+}
+
+/// Resolves `.` and `..` across the mount points, and keeps
+/// the cwd of a task to itself.
+fn test_paths() {
+    let mut buf = [0u8; 4];
+    assert_eq!(read_file("/mnt/../bin/./initproc", &mut buf), 4);
+    assert_eq!(&buf, b"\x7fELF");
+    assert_eq!(read_file("/../../bin/initproc", &mut buf), 4);
+
+    let mut dirents = [Dirent::new(); MAX_ENTRIES];
+    let entries = list_dir("/", &mut dirents);
+    for dir in ["bin", "dev", "mnt", "tmp"] {
+        assert!(contains(entries, dir, S_IFDIR));
+    }
+    let entries = list_dir("/dev", &mut dirents);
+    assert!(contains(entries, "null", S_IFCHR));
+    assert!(contains(entries, "zero", S_IFCHR));
+
+    assert_eq!(chdir("/tmp"), 0);
+    let pid = fork();
+    assert!(pid >= 0, "fork should succeed");
+    if pid == 0 {
+        // The child starts in the cwd of the parent.
+        assert_eq!(read_file("../bin/initproc", &mut buf), 4);
+        assert_eq!(chdir("/bin"), 0);
+        exit(0);
+    }
+    let mut exit_code = -1;
+    assert_eq!(waitpid(pid, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(read_file("../bin/initproc", &mut buf), 4);
+
+    assert_eq!(chdir("/bin/initproc"), -1, "a file is not a directory");
+    assert_eq!(chdir("/no_such_dir"), -1);
+    assert_eq!(chdir("/"), 0);
+}
+
+/// Keeps the data of an open file after it is removed.
+fn test_unlink_open() {
+    const PATH: &str = "/tmp/test_vfs_unlinked";
+    let fd = open(PATH, O_RDWR | O_CREAT);
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    assert_eq!(unlink(PATH), 0);
+    assert_eq!(open(PATH, O_RDONLY), -1, "the file is removed");
+
+    assert_eq!(write(fd, b"still here"), 10);
+    let mut stat = Stat::default();
+    assert_eq!(fstat(fd, &mut stat), 0);
+    assert_eq!(stat.mode & S_IFMT, S_IFREG);
+    assert_eq!(stat.size, 10);
+    assert_eq!(close(fd), 0);
+}
+
+fn test_read_only() {
+    assert_eq!(mkdir("/bin/new_dir"), -1, "the initramfs is read-only");
+    assert_eq!(unlink("/bin/initproc"), -1, "the initramfs is read-only");
+    assert_eq!(mkdir("/dev/new_dir"), -1);
+    assert_eq!(rmdir("/tmp"), -1, "a mount point cannot be removed");
+    assert_eq!(rmdir("/"), -1);
+}
+
+fn write_file(path: &str, content: &[u8]) {
+    let fd = open(path, O_WRONLY | O_CREAT);
+    assert!(fd >= 0, "the file should be created");
+    assert_eq!(write(fd as usize, content), content.len() as isize);
+    assert_eq!(close(fd as usize), 0);
+}
+
+/// Reads the file at the `path` into the `buf` until it is
+/// full, and returns the length read.
+fn read_file(path: &str, buf: &mut [u8]) -> usize {
+    let fd = open(path, O_RDONLY);
+    assert!(fd >= 0, "the file should exist");
+    let mut len = 0;
+    while len < buf.len() {
+        let read_len = read(fd as usize, &mut buf[len..]);
+        assert!(read_len >= 0);
+        if read_len == 0 {
+            break;
+        }
+        len += read_len as usize;
+    }
+    assert_eq!(close(fd as usize), 0);
+    len
+}
+
+/// Reads the entries of the directory at the `path` into
+/// the `dirents` in several calls, and returns them.
+fn list_dir<'a>(path: &str, dirents: &'a mut [Dirent]) -> &'a [Dirent] {
+    let fd = open(path, O_RDONLY);
+    assert!(fd >= 0, "the directory should exist");
+    let mut len = 0;
+    loop {
+        let end = (len + 2).min(dirents.len());
+        let read_len = getdents(fd as usize, &mut dirents[len..end]);
+        assert!(read_len >= 0);
+        if read_len == 0 {
+            break;
+        }
+        len += read_len as usize;
+    }
+    assert_eq!(close(fd as usize), 0);
+    &dirents[..len]
+}
+
+fn contains(entries: &[Dirent], name: &str, file_type: u32) -> bool {
+    entries
+        .iter()
+        .any(|entry| entry.get_name() == name && entry.file_type == file_type)
+}
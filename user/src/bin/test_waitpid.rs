@@ -0,0 +1,70 @@
+#![no_std]
+#![no_main]
+
+use user_lib::{exit, fork, println, wait, waitpid, yield_now};
+
+extern crate user_lib;
+
+const TOTAL_CHILDREN: usize = 4;
+
+#[unsafe(no_mangle)]
+fn main(_argc: usize, _argv: &[&str]) -> i32 {
+    println!("Test waitpid.");
+
+    let mut exit_code = 0;
+    assert_eq!(
+        wait(&mut exit_code),
+        -1,
+        "wait without children should fail"
+    );
+
+    let mut pids = [0; TOTAL_CHILDREN];
+    for i in 0..TOTAL_CHILDREN {
+        let pid = fork();
+        assert!(pid >= 0, "fork should succeed");
+        if pid == 0 {
+            for _ in 0..i {
+                yield_now();
+            }
+            exit(100 + i as i32);
+        }
+        pids[i] = pid;
+    }
+
+    // Wait for a specific child
+    let pid = waitpid(pids[2], &mut exit_code);
+    assert_eq!(pid, pids[2]);
+    assert_eq!(exit_code, 102);
+    assert_eq!(
+        waitpid(pids[2], &mut exit_code),
+        -1,
+        "reaped child should be gone"
+    );
+
+    // Wait for the rest in any order
+    for _ in 0..TOTAL_CHILDREN - 1 {
+        let pid = wait(&mut exit_code);
+        let i = pids.iter().position(|&p| p == pid).expect("unknown child");
+        assert_ne!(i, 2);
+        assert_eq!(exit_code, 100 + i as i32);
+    }
+    assert_eq!(wait(&mut exit_code), -1, "all children should be reaped");
+
+    // Leave an orphan to the init task
+    let pid = fork();
+    if pid == 0 {
+        if fork() == 0 {
+            for _ in 0..5 {
+                yield_now();
+            }
+            println!("Orphan: exits and should be reaped by initproc.");
+            exit(7);
+        }
+        exit(0);
+    }
+    assert_eq!(waitpid(pid, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+
+    println!("Test waitpid OK!");
+    0
+}
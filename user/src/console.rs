@@ -1,12 +1,12 @@
 use core::fmt::{self, Write};
 
-const FD_STDOUT: usize = 1;
+use crate::fs::{STDIN, STDOUT};
 
 pub struct Stdout;
 
 impl fmt::Write for Stdout {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        crate::write(FD_STDOUT, s.as_bytes());
+        crate::write(STDOUT, s.as_bytes());
         Ok(())
     }
 }
@@ -17,6 +17,16 @@ impl Stdout {
     }
 }
 
+/// Reads a byte from stdin, blocking until one arrives.
+/// Returns [None] at the end of the file or on failure.
+pub fn getchar() -> Option<u8> {
+    let mut c = [0u8; 1];
+    match crate::read(STDIN, &mut c) {
+        1 => Some(c[0]),
+        _ => None,
+    }
+}
+
 #[macro_export]
 macro_rules! print {
     ($fmt:literal $(, $($args:tt)+)?) => {
@@ -0,0 +1,90 @@
+use core::ffi::CStr;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+// Keys of the auxiliary vector, see [here].
+//
+// [here]: https://github.com/torvalds/linux/blob/master/include/uapi/linux/auxvec.h
+pub const AT_NULL: usize = 0;
+pub const AT_PHDR: usize = 3;
+pub const AT_PHENT: usize = 4;
+pub const AT_PHNUM: usize = 5;
+pub const AT_PAGESZ: usize = 6;
+pub const AT_ENTRY: usize = 9;
+pub const AT_RANDOM: usize = 25;
+/// The frequency of the time counter in Hz, which is a key
+/// specific to this kernel.
+pub const AT_TIMEBASE_FREQ: usize = 0x1000;
+
+static ENVP: AtomicUsize = AtomicUsize::new(0);
+static AUXV: AtomicUsize = AtomicUsize::new(0);
+
+/// Records the `envp` and the auxiliary vector following it.
+///
+/// # Safety
+///
+/// `envp` must be the one passed by the kernel to `_start`.
+pub(crate) unsafe fn init(envp: *const *const u8) {
+    let mut p = envp;
+    while !unsafe { p.read() }.is_null() {
+        p = unsafe { p.add(1) };
+    }
+
+    ENVP.store(envp.addr(), Ordering::Relaxed);
+    AUXV.store(unsafe { p.add(1) }.addr(), Ordering::Relaxed);
+}
+
+/// Returns the string starting at `ptr` up to the null
+/// terminator, or an empty string if it is not valid UTF-8.
+///
+/// # Safety
+///
+/// `ptr` must point to a null-terminated string that is
+/// never modified.
+pub(crate) unsafe fn parse_c_str(ptr: *const u8) -> &'static str {
+    unsafe { CStr::from_ptr(ptr as *const _) }
+        .to_str()
+        .unwrap_or("")
+}
+
+/// Returns the value of the auxiliary vector entry `key`,
+/// or [None] if there is no such entry.
+pub fn getauxval(key: usize) -> Option<usize> {
+    let mut p = AUXV.load(Ordering::Relaxed) as *const [usize; 2];
+    if p.is_null() {
+        return None;
+    }
+
+    loop {
+        let [k, v] = unsafe { p.read() };
+        if k == AT_NULL {
+            return None;
+        }
+        if k == key {
+            return Some(v);
+        }
+        p = unsafe { p.add(1) };
+    }
+}
+
+/// Returns the value of the environment variable `name`,
+/// or [None] if it is not set.
+pub fn getenv(name: &str) -> Option<&'static str> {
+    let mut p = ENVP.load(Ordering::Relaxed) as *const *const u8;
+    if p.is_null() {
+        return None;
+    }
+
+    loop {
+        let env = unsafe { p.read() };
+        if env.is_null() {
+            return None;
+        }
+        let value = unsafe { parse_c_str(env) }
+            .strip_prefix(name)
+            .and_then(|rest| rest.strip_prefix('='));
+        if value.is_some() {
+            return value;
+        }
+        p = unsafe { p.add(1) };
+    }
+}
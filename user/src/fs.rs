@@ -0,0 +1,79 @@
+pub const STDIN: usize = 0;
+pub const STDOUT: usize = 1;
+pub const STDERR: usize = 2;
+
+// Flags of [open], which mirror the kernel's.
+//
+// [open]: crate::open
+pub const O_RDONLY: usize = 0;
+pub const O_WRONLY: usize = 1;
+pub const O_RDWR: usize = 2;
+pub const O_CREAT: usize = 0o100;
+pub const O_TRUNC: usize = 0o1000;
+
+/// The flag of unlink to remove a directory instead.
+pub(crate) const AT_REMOVEDIR: usize = 0x200;
+
+/// The longest name of a directory entry in bytes.
+pub const MAX_NAME_LEN: usize = 255;
+
+// File types in [Stat::mode].
+pub const S_IFMT: u32 = 0o170000;
+pub const S_IFIFO: u32 = 0o010000;
+pub const S_IFCHR: u32 = 0o020000;
+pub const S_IFDIR: u32 = 0o040000;
+pub const S_IFREG: u32 = 0o100000;
+
+/// The status of a file, which mirrors the kernel's.
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct Stat {
+    /// The ID of the device containing the file.
+    pub dev: u64,
+    /// The inode number of the file.
+    pub ino: u64,
+    /// The file type and permission bits.
+    pub mode: u32,
+    /// The number of hard links to the file.
+    pub nlink: u32,
+    /// The size of the file in bytes.
+    pub size: u64,
+}
+
+/// An entry of a directory read by [getdents], which
+/// mirrors the kernel's.
+///
+/// [getdents]: crate::getdents
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Dirent {
+    /// The inode number of the entry.
+    pub ino: u64,
+    /// The file type bits, as in [Stat::mode].
+    pub file_type: u32,
+    /// The length of the name in bytes.
+    pub name_len: u32,
+    /// The name followed by NULs.
+    pub name: [u8; MAX_NAME_LEN + 1],
+}
+
+impl Dirent {
+    pub const fn new() -> Self {
+        Self {
+            ino: 0,
+            file_type: 0,
+            name_len: 0,
+            name: [0; MAX_NAME_LEN + 1],
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        core::str::from_utf8(&self.name[..self.name_len as usize]).unwrap_or_default()
+    }
+}
+
+impl Default for Dirent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,18 +1,52 @@
 #![no_std]
 
 pub mod console;
+pub mod env;
+pub mod fs;
 mod lang_items;
+pub mod signal;
+pub mod sync;
 mod syscall;
 pub mod task;
+pub mod time;
 
-use crate::syscall::{sys_exit, sys_mmap, sys_munmap, sys_task_info, sys_write, sys_yield};
+use core::sync::atomic::AtomicU32;
+
+use crate::fs::{AT_REMOVEDIR, Dirent, Stat};
+use crate::signal::SigAction;
+use crate::syscall::{
+    FUTEX_WAIT, FUTEX_WAKE, get_sigreturn_addr, sys_chdir, sys_clock_gettime, sys_close,
+    sys_condvar_create, sys_condvar_signal, sys_condvar_wait, sys_dup, sys_dup2,
+    sys_enable_deadlock_detect, sys_exec, sys_exit, sys_fork, sys_fstat, sys_futex, sys_get_time,
+    sys_getdents, sys_gettid, sys_kill, sys_mkdir, sys_mmap, sys_munmap, sys_mutex_create,
+    sys_mutex_lock, sys_mutex_unlock, sys_nanosleep, sys_open, sys_pipe, sys_read,
+    sys_semaphore_create, sys_semaphore_down, sys_semaphore_up, sys_set_priority,
+    sys_set_user_counters, sys_sigaction, sys_sigprocmask, sys_task_info, sys_thread_create,
+    sys_unlink, sys_waitpid, sys_waittid, sys_write, sys_yield,
+};
 use crate::task::TaskInfo;
+use crate::time::{CLOCK_MONOTONIC, TimeSpec, TimeVal};
+
+/// The maximum number of arguments passed to `main`; the
+/// extra ones are dropped.
+const MAX_ARGS: usize = 32;
 
+/// The kernel passes argc, argv and envp in a0 through a2,
+/// which also lie at the top of the user stack followed by
+/// the auxiliary vector.
 #[unsafe(no_mangle)]
 #[unsafe(link_section = ".text.entry")]
-pub extern "C" fn _start() -> ! {
+pub extern "C" fn _start(argc: usize, argv: *const *const u8, envp: *const *const u8) -> ! {
     clear_bss();
-    unsafe { sys_exit(main()) };
+    unsafe { env::init(envp) };
+
+    let argc = argc.min(MAX_ARGS);
+    let mut args = [""; MAX_ARGS];
+    for (i, arg) in args.iter_mut().enumerate().take(argc) {
+        *arg = unsafe { env::parse_c_str(argv.add(i).read()) };
+    }
+
+    unsafe { sys_exit(main(argc, &args[..argc])) };
     unreachable!()
 }
 
@@ -27,13 +61,97 @@ fn clear_bss() {
 }
 
 unsafe extern "Rust" {
-    unsafe fn main() -> i32;
+    unsafe fn main(argc: usize, argv: &[&'static str]) -> i32;
+}
+
+/// Opens the file at the `path` with the `flags`, where a
+/// relative path starts from the current directory. Returns
+/// the lowest free file descriptor, or -1 on failure.
+pub fn open(path: &str, flags: usize) -> isize {
+    sys_open(path, flags)
+}
+
+/// Closes the `fd`. Returns 0 on success, or -1 if it is
+/// not open.
+pub fn close(fd: usize) -> isize {
+    sys_close(fd)
 }
 
+/// Reads from the `fd` into the `buf`. Returns the number
+/// of bytes read, zero at the end of the file, or -1 on
+/// failure.
+pub fn read(fd: usize, buf: &mut [u8]) -> isize {
+    sys_read(fd, buf)
+}
+
+/// Writes the `buf` to the `fd`. Returns the number of
+/// bytes written, or -1 on failure.
 pub fn write(fd: usize, buf: &[u8]) -> isize {
     sys_write(fd, buf)
 }
 
+/// Duplicates the `fd` as the lowest free file descriptor.
+/// Returns the new descriptor, or -1 on failure.
+pub fn dup(fd: usize) -> isize {
+    sys_dup(fd)
+}
+
+/// Duplicates the `fd` as the `new_fd`, closing the file it
+/// refers to. Returns the `new_fd`, or -1 on failure.
+pub fn dup2(fd: usize, new_fd: usize) -> isize {
+    sys_dup2(fd, new_fd)
+}
+
+/// Creates a pipe and writes the file descriptors of its
+/// read end and write end to `fds`. Returns 0 on success,
+/// or -1 on failure.
+pub fn pipe(fds: &mut [usize; 2]) -> isize {
+    let mut pipe_fds = [0i32; 2];
+    let result = sys_pipe(&mut pipe_fds);
+    if result == 0 {
+        *fds = pipe_fds.map(|fd| fd as usize);
+    }
+    result
+}
+
+/// Writes the status of the `fd` to `stat`. Returns 0 on
+/// success, or -1 on failure.
+pub fn fstat(fd: usize, stat: &mut Stat) -> isize {
+    sys_fstat(fd, stat)
+}
+
+/// Reads the next entries of the directory `fd` into the
+/// `dirents`. Returns the number of entries read, zero at
+/// the end of the directory, or -1 on failure.
+pub fn getdents(fd: usize, dirents: &mut [Dirent]) -> isize {
+    sys_getdents(fd, dirents)
+}
+
+/// Creates an empty directory at the `path`. Returns 0 on
+/// success, or -1 on failure.
+pub fn mkdir(path: &str) -> isize {
+    sys_mkdir(path)
+}
+
+/// Removes the file at the `path`. An open file stays
+/// accessible until it is closed. Returns 0 on success, or
+/// -1 on failure.
+pub fn unlink(path: &str) -> isize {
+    sys_unlink(path, 0)
+}
+
+/// Removes the empty directory at the `path`. Returns 0 on
+/// success, or -1 on failure.
+pub fn rmdir(path: &str) -> isize {
+    sys_unlink(path, AT_REMOVEDIR)
+}
+
+/// Changes the current directory to the `path`. Returns 0
+/// on success, or -1 on failure.
+pub fn chdir(path: &str) -> isize {
+    sys_chdir(path)
+}
+
 pub fn exit(exit_code: i32) -> isize {
     sys_exit(exit_code)
 }
@@ -42,6 +160,241 @@ pub fn yield_now() -> isize {
     sys_yield()
 }
 
+/// Writes the current time of the clock `clock_id` to `tp`.
+/// Returns 0 on success, or -1 if the clock is unsupported.
+pub fn clock_gettime(clock_id: usize, tp: &mut TimeSpec) -> isize {
+    sys_clock_gettime(clock_id, tp)
+}
+
+/// Writes the time since the Unix epoch to `tv`. Returns 0
+/// on success, or -1 on failure.
+pub fn gettimeofday(tv: &mut TimeVal) -> isize {
+    sys_get_time(tv)
+}
+
+/// Returns the milliseconds since system start, or -1 on
+/// failure.
+pub fn get_time() -> isize {
+    let mut time = TimeSpec::default();
+    match clock_gettime(CLOCK_MONOTONIC, &mut time) {
+        0 => time.as_millis() as isize,
+        _ => -1,
+    }
+}
+
+/// Suspends the current task for at least the duration
+/// `req`. Returns 0 on success, or -1 if it is invalid or a
+/// signal interrupts the sleep.
+pub fn nanosleep(req: &TimeSpec) -> isize {
+    sys_nanosleep(req)
+}
+
+/// Suspends the current task for at least `ms` milliseconds.
+pub fn sleep(ms: usize) -> isize {
+    nanosleep(&TimeSpec::from_millis(ms))
+}
+
+/// Sets the priority of the current task, which must be at
+/// least 2. Returns the `priority`, or -1 if it is invalid.
+pub fn set_priority(priority: isize) -> isize {
+    sys_set_priority(priority)
+}
+
+/// Sets which counters of [time] the current task and its
+/// future children can read directly, as a mask of the
+/// `COUNTER_*` bits. Reading another one raises SIGILL.
+/// Returns the previous mask, or -1 if it is invalid.
+pub fn set_user_counters(user_counters: usize) -> isize {
+    sys_set_user_counters(user_counters)
+}
+
+/// Creates a child task. Returns the child's task ID in
+/// the parent, zero in the child, or -1 on failure.
+pub fn fork() -> isize {
+    sys_fork()
+}
+
+/// Replaces the image of the current task with the app
+/// `name`, passing the `args` to its `main`. Does not
+/// return on success, or returns -1 on failure.
+///
+/// By convention, the first argument is the app name.
+pub fn exec(name: &str, args: &[&str]) -> isize {
+    if args.len() > MAX_ARGS {
+        return -1;
+    }
+
+    let mut raw_args = [[0; 2]; MAX_ARGS];
+    for (raw_arg, arg) in raw_args.iter_mut().zip(args) {
+        *raw_arg = [arg.as_ptr() as usize, arg.len()];
+    }
+    sys_exec(name, &raw_args[..args.len()])
+}
+
+/// Waits for any child to exit and reaps it. Returns the
+/// task ID of the reaped child, or -1 if there is no child
+/// or a signal interrupts the wait.
+pub fn wait(exit_code: &mut i32) -> isize {
+    waitpid(-1, exit_code)
+}
+
+/// Waits for the child `pid` to exit and reaps it. Returns
+/// `pid`, or -1 if there is no such child or a signal
+/// interrupts the wait.
+pub fn waitpid(pid: isize, exit_code: &mut i32) -> isize {
+    sys_waitpid(pid, exit_code)
+}
+
+/// Creates a thread in the current process, which runs the
+/// `entry` with the `arg` on its own stack and should end
+/// by calling [exit]. Returns the thread's task ID, or -1
+/// on failure.
+///
+/// Calling [exit] in the main thread makes the whole
+/// process exit.
+pub fn thread_create(entry: extern "C" fn(usize) -> !, arg: usize) -> isize {
+    sys_thread_create(entry as usize, arg)
+}
+
+/// Returns the task ID of the current thread.
+pub fn gettid() -> isize {
+    sys_gettid()
+}
+
+/// Waits for the thread `tid` of the current process to
+/// exit and reaps it. Returns `tid`, or -1 if there is no
+/// such thread other than the current and the main thread,
+/// or a signal interrupts the wait.
+pub fn waittid(tid: isize, exit_code: &mut i32) -> isize {
+    sys_waittid(tid, exit_code)
+}
+
+/// Blocks the current thread if the futex word `addr` is
+/// `val`, until another thread wakes it through the word or
+/// the `timeout` if any passes. Returns 0 once woken, -2 if
+/// the word is not `val`, -3 on timeout, or -1 on failure,
+/// including an interruption by a signal.
+///
+/// The waits may end spuriously, so the callers should check
+/// their condition again.
+pub fn futex_wait(addr: &AtomicU32, val: u32, timeout: Option<&TimeSpec>) -> isize {
+    sys_futex(addr, FUTEX_WAIT, val as usize, timeout)
+}
+
+/// Wakes at most `count` threads blocked on the futex word
+/// `addr`, even in other processes mapping the same page.
+/// Returns the number of threads woken, or -1 on failure.
+pub fn futex_wake(addr: &AtomicU32, count: usize) -> isize {
+    sys_futex(addr, FUTEX_WAKE, count, None)
+}
+
+/// Creates a mutex in the kernel, shared by the threads of
+/// the current process. Returns its ID.
+pub fn mutex_create() -> isize {
+    sys_mutex_create()
+}
+
+/// Locks the mutex `mutex_id`, blocking until it is
+/// unlocked. Returns 0 on success, -1 if it does not exist
+/// or a signal interrupts the wait, or [DEADLOCK] if the
+/// wait could never end.
+///
+/// [DEADLOCK]: sync::DEADLOCK
+pub fn mutex_lock(mutex_id: usize) -> isize {
+    sys_mutex_lock(mutex_id)
+}
+
+/// Unlocks the mutex `mutex_id` held by the current thread.
+/// Returns 0 on success, or -1 on failure.
+pub fn mutex_unlock(mutex_id: usize) -> isize {
+    sys_mutex_unlock(mutex_id)
+}
+
+/// Creates a semaphore with `count` units in the kernel,
+/// shared by the threads of the current process. Returns
+/// its ID.
+pub fn semaphore_create(count: usize) -> isize {
+    sys_semaphore_create(count)
+}
+
+/// Gives back a unit of the semaphore `sem_id`. Returns 0
+/// on success, or -1 if it does not exist.
+pub fn semaphore_up(sem_id: usize) -> isize {
+    sys_semaphore_up(sem_id)
+}
+
+/// Takes a unit of the semaphore `sem_id`, blocking until
+/// there is one. Returns 0 on success, -1 if it does not
+/// exist or a signal interrupts the wait, or [DEADLOCK] if
+/// the wait could never end.
+///
+/// [DEADLOCK]: sync::DEADLOCK
+pub fn semaphore_down(sem_id: usize) -> isize {
+    sys_semaphore_down(sem_id)
+}
+
+/// Creates a condition variable in the kernel, shared by
+/// the threads of the current process. Returns its ID.
+pub fn condvar_create() -> isize {
+    sys_condvar_create()
+}
+
+/// Wakes a thread waiting on the condition variable
+/// `condvar_id`. Returns 0 on success, or -1 on failure.
+pub fn condvar_signal(condvar_id: usize) -> isize {
+    sys_condvar_signal(condvar_id)
+}
+
+/// Unlocks the mutex `mutex_id` and waits until the
+/// condition variable `condvar_id` is signaled, and then
+/// locks the mutex again. Returns 0 on success, -1 on
+/// failure, or [DEADLOCK] if locking again could never end.
+///
+/// [DEADLOCK]: sync::DEADLOCK
+pub fn condvar_wait(condvar_id: usize, mutex_id: usize) -> isize {
+    sys_condvar_wait(condvar_id, mutex_id)
+}
+
+/// Enables or disables the deadlock detection of the
+/// current process, with which the mutexes and semaphores
+/// return [DEADLOCK] instead of blocking forever. It assumes
+/// that only the holders give back the units. Returns 0.
+///
+/// [DEADLOCK]: sync::DEADLOCK
+pub fn enable_deadlock_detect(is_enabled: bool) -> isize {
+    sys_enable_deadlock_detect(is_enabled as usize)
+}
+
+/// Sends the signal `signum` to the task `pid`, or only
+/// checks that the task exists if `signum` is 0. Returns 0
+/// on success, or -1 on failure.
+pub fn kill(pid: isize, signum: usize) -> isize {
+    sys_kill(pid, signum)
+}
+
+/// Sets the action of the signal `signum` to the `action`
+/// if any, and writes the previous one to `prev_action` if
+/// any. Returns 0 on success, or -1 on failure.
+pub fn sigaction(
+    signum: usize,
+    action: Option<&SigAction>,
+    prev_action: Option<&mut SigAction>,
+) -> isize {
+    let action = action.map(|action| SigAction {
+        restorer: get_sigreturn_addr(),
+        ..*action
+    });
+    sys_sigaction(signum, action.as_ref(), prev_action)
+}
+
+/// Blocks, unblocks or sets the blocked signals to the
+/// mask `set` if any, as told by `how`, and writes the
+/// previously blocked ones to `prev_set` if any. Returns 0
+/// on success, or -1 on failure.
+pub fn sigprocmask(how: usize, set: Option<u64>, prev_set: Option<&mut u64>) -> isize {
+    sys_sigprocmask(how, set.as_ref(), prev_set)
+}
+
 pub fn get_task_info(task_id: usize, data: *mut TaskInfo) -> isize {
     sys_task_info(task_id, data)
 }
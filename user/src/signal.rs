@@ -0,0 +1,78 @@
+// Signals, see [here].
+//
+// [here]: https://man7.org/linux/man-pages/man7/signal.7.html
+pub const SIGHUP: usize = 1;
+pub const SIGINT: usize = 2;
+pub const SIGQUIT: usize = 3;
+pub const SIGILL: usize = 4;
+pub const SIGABRT: usize = 6;
+pub const SIGKILL: usize = 9;
+pub const SIGUSR1: usize = 10;
+pub const SIGSEGV: usize = 11;
+pub const SIGUSR2: usize = 12;
+pub const SIGPIPE: usize = 13;
+pub const SIGALRM: usize = 14;
+pub const SIGTERM: usize = 15;
+pub const SIGCHLD: usize = 17;
+pub const SIGSTOP: usize = 19;
+
+// Handlers of [SigAction] that are not functions.
+pub const SIG_DFL: usize = 0;
+pub const SIG_IGN: usize = 1;
+
+// Flags of [SigAction].
+/// Does not block the signal while its handler runs.
+pub const SA_NODEFER: usize = 0x40000000;
+/// Resets the action to [SIG_DFL] once the handler is
+/// called.
+pub const SA_RESETHAND: usize = 0x80000000;
+
+// How [sigprocmask] changes the blocked signals.
+//
+// [sigprocmask]: crate::sigprocmask
+pub const SIG_BLOCK: usize = 0;
+pub const SIG_UNBLOCK: usize = 1;
+pub const SIG_SETMASK: usize = 2;
+
+/// Returns the mask of the signal `signum`.
+pub const fn sig_bit(signum: usize) -> u64 {
+    1 << signum
+}
+
+/// How a task handles a signal, which mirrors the kernel's.
+/// The default one is [SIG_DFL].
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct SigAction {
+    /// The address of the handler, or [SIG_DFL] or
+    /// [SIG_IGN].
+    pub handler: usize,
+    pub flags: usize,
+    /// The address the handler returns to, which is set by
+    /// [sigaction].
+    ///
+    /// [sigaction]: crate::sigaction
+    pub(crate) restorer: usize,
+    /// The signals blocked while the handler runs, besides
+    /// the one handled unless [SA_NODEFER] is set.
+    pub mask: u64,
+}
+
+impl SigAction {
+    /// Returns the action calling the `handler` with the
+    /// signal number.
+    pub fn new(handler: extern "C" fn(usize)) -> Self {
+        Self {
+            handler: handler as usize,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the action ignoring the signal.
+    pub fn ignore() -> Self {
+        Self {
+            handler: SIG_IGN,
+            ..Self::default()
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::{futex_wait, futex_wake};
+
+/// Returned by the kernel objects, e.g., [mutex_lock],
+/// instead of blocking when the deadlock detection finds
+/// that the wait could never end.
+///
+/// [mutex_lock]: crate::mutex_lock
+pub const DEADLOCK: isize = -0xDEAD;
+
+// States of [Mutex].
+const UNLOCKED: u32 = 0;
+const LOCKED: u32 = 1;
+/// Locked, and other threads may be blocked on it.
+const CONTENDED: u32 = 2;
+
+// States of [Once].
+const INCOMPLETE: u32 = 0;
+const RUNNING: u32 = 1;
+const COMPLETE: u32 = 2;
+
+/// A lock that blocks the threads waiting for it through a
+/// futex, instead of spinning.
+pub struct Mutex<T> {
+    state: AtomicU32,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Sync for Mutex<T> {}
+
+impl<T> Mutex<T> {
+    pub const fn new(data: T) -> Self {
+        Self {
+            state: AtomicU32::new(UNLOCKED),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Blocks until the lock is acquired, and returns a guard
+    /// that releases it once dropped.
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        if self.try_acquire() {
+            return MutexGuard { mutex: self };
+        }
+
+        // Whoever unlocks a contended lock wakes a waiter, so
+        // a thread that acquires it here marks it contended
+        // as well, in case there are other waiters.
+        while self.state.swap(CONTENDED, Ordering::Acquire) != UNLOCKED {
+            futex_wait(&self.state, CONTENDED, None);
+        }
+        MutexGuard { mutex: self }
+    }
+
+    /// Acquires the lock if it is free, without blocking.
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        self.try_acquire().then_some(MutexGuard { mutex: self })
+    }
+
+    fn try_acquire(&self) -> bool {
+        self.state
+            .compare_exchange(UNLOCKED, LOCKED, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    fn unlock(&self) {
+        if self.state.swap(UNLOCKED, Ordering::Release) == CONTENDED {
+            futex_wake(&self.state, 1);
+        }
+    }
+}
+
+pub struct MutexGuard<'a, T> {
+    mutex: &'a Mutex<T>,
+}
+
+impl<T> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<T> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<T> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.mutex.unlock();
+    }
+}
+
+/// A condition variable, whose futex word is bumped on each
+/// notification so that a waiter cannot miss one sent after
+/// it releases the [Mutex].
+pub struct Condvar {
+    seq: AtomicU32,
+}
+
+impl Condvar {
+    pub const fn new() -> Self {
+        Self {
+            seq: AtomicU32::new(0),
+        }
+    }
+
+    /// Releases the lock of the `guard` and blocks until
+    /// notified, then acquires the lock again. The wait may
+    /// end spuriously, so the caller should check its
+    /// condition in a loop.
+    pub fn wait<'a, T>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        let seq = self.seq.load(Ordering::Relaxed);
+        let mutex = guard.mutex;
+        drop(guard);
+
+        futex_wait(&self.seq, seq, None);
+        mutex.lock()
+    }
+
+    /// Wakes one of the threads waiting on this.
+    pub fn notify_one(&self) {
+        self.seq.fetch_add(1, Ordering::Relaxed);
+        futex_wake(&self.seq, 1);
+    }
+
+    /// Wakes all the threads waiting on this.
+    pub fn notify_all(&self) {
+        self.seq.fetch_add(1, Ordering::Relaxed);
+        futex_wake(&self.seq, usize::MAX);
+    }
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Runs an initialization only once, while the other threads
+/// calling it block until it completes.
+pub struct Once {
+    state: AtomicU32,
+}
+
+impl Once {
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU32::new(INCOMPLETE),
+        }
+    }
+
+    /// Runs `f` if no thread has run it through this yet, or
+    /// waits until the one running it finishes.
+    pub fn call_once(&self, f: impl FnOnce()) {
+        loop {
+            match self.state.compare_exchange(
+                INCOMPLETE,
+                RUNNING,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    f();
+                    self.state.store(COMPLETE, Ordering::Release);
+                    futex_wake(&self.state, usize::MAX);
+                    return;
+                }
+                Err(COMPLETE) => return,
+                Err(_) => {
+                    futex_wait(&self.state, RUNNING, None);
+                }
+            }
+        }
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.state.load(Ordering::Acquire) == COMPLETE
+    }
+}
+
+impl Default for Once {
+    fn default() -> Self {
+        Self::new()
+    }
+}
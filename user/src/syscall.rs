@@ -1,15 +1,60 @@
-use core::arch::asm;
+use core::arch::{asm, global_asm};
+use core::ptr;
+use core::sync::atomic::AtomicU32;
 
+use crate::fs::{Dirent, Stat};
+use crate::signal::SigAction;
 use crate::task::TaskInfo;
+use crate::time::{TimeSpec, TimeVal};
 
+const SYSCALL_DUP: usize = 23;
+const SYSCALL_DUP2: usize = 24;
+const SYSCALL_MKDIR: usize = 34;
+const SYSCALL_UNLINK: usize = 35;
+const SYSCALL_CHDIR: usize = 49;
+const SYSCALL_OPEN: usize = 56;
+const SYSCALL_CLOSE: usize = 57;
+const SYSCALL_PIPE: usize = 59;
+const SYSCALL_GETDENTS: usize = 61;
+const SYSCALL_READ: usize = 63;
 const SYSCALL_WRITE: usize = 64;
+const SYSCALL_FSTAT: usize = 80;
 const SYSCALL_MMAP: usize = 90;
 const SYSCALL_MUNMAP: usize = 215;
 const SYSCALL_EXIT: usize = 93;
+const SYSCALL_FUTEX: usize = 98;
+const SYSCALL_NANOSLEEP: usize = 101;
+const SYSCALL_CLOCK_GETTIME: usize = 113;
 const SYSCALL_YIELD: usize = 124;
+const SYSCALL_KILL: usize = 129;
+const SYSCALL_SIGACTION: usize = 134;
+const SYSCALL_SIGPROCMASK: usize = 135;
+const SYSCALL_SIGRETURN: usize = 139;
+const SYSCALL_SET_PRIORITY: usize = 140;
+const SYSCALL_GET_TIME: usize = 169;
+const SYSCALL_GETTID: usize = 178;
+const SYSCALL_FORK: usize = 220;
+const SYSCALL_EXEC: usize = 221;
+const SYSCALL_WAITPID: usize = 260;
 const SYSCALL_TASK_INFO: usize = (1 << 63) | 1;
+const SYSCALL_SET_USER_COUNTERS: usize = (1 << 63) | 2;
+const SYSCALL_THREAD_CREATE: usize = (1 << 63) | 3;
+const SYSCALL_WAITTID: usize = (1 << 63) | 4;
+const SYSCALL_MUTEX_CREATE: usize = (1 << 63) | 5;
+const SYSCALL_MUTEX_LOCK: usize = (1 << 63) | 6;
+const SYSCALL_MUTEX_UNLOCK: usize = (1 << 63) | 7;
+const SYSCALL_SEMAPHORE_CREATE: usize = (1 << 63) | 8;
+const SYSCALL_SEMAPHORE_UP: usize = (1 << 63) | 9;
+const SYSCALL_SEMAPHORE_DOWN: usize = (1 << 63) | 10;
+const SYSCALL_CONDVAR_CREATE: usize = (1 << 63) | 11;
+const SYSCALL_CONDVAR_SIGNAL: usize = (1 << 63) | 12;
+const SYSCALL_CONDVAR_WAIT: usize = (1 << 63) | 13;
+const SYSCALL_ENABLE_DEADLOCK_DETECT: usize = (1 << 63) | 14;
 
-fn syscall(id: usize, args: [usize; 3]) -> isize {
+pub(super) const FUTEX_WAIT: usize = 0;
+pub(super) const FUTEX_WAKE: usize = 1;
+
+fn syscall(id: usize, args: [usize; 6]) -> isize {
     let mut result: isize;
     unsafe {
         asm!(
@@ -17,32 +62,262 @@ fn syscall(id: usize, args: [usize; 3]) -> isize {
             inlateout("x10") args[0] => result,
             in("x11") args[1],
             in("x12") args[2],
+            in("x13") args[3],
+            in("x14") args[4],
+            in("x15") args[5],
             in("x17") id
         );
     }
     result
 }
 
+// The code that signal handlers return to, which makes
+// the sigreturn syscall with the stack they started with.
+global_asm!(
+    ".global __sigreturn",
+    "__sigreturn:",
+    "li a7, {id}",
+    "ecall",
+    id = const SYSCALL_SIGRETURN,
+);
+
+unsafe extern "C" {
+    unsafe fn __sigreturn();
+}
+
+/// Returns the address that signal handlers return to.
+pub(super) fn get_sigreturn_addr() -> usize {
+    __sigreturn as usize
+}
+
+pub(super) fn sys_dup(fd: usize) -> isize {
+    syscall(SYSCALL_DUP, [fd, 0, 0, 0, 0, 0])
+}
+
+pub(super) fn sys_dup2(fd: usize, new_fd: usize) -> isize {
+    syscall(SYSCALL_DUP2, [fd, new_fd, 0, 0, 0, 0])
+}
+
+pub(super) fn sys_open(path: &str, flags: usize) -> isize {
+    syscall(
+        SYSCALL_OPEN,
+        [path.as_ptr() as usize, path.len(), flags, 0, 0, 0],
+    )
+}
+
+pub(super) fn sys_mkdir(path: &str) -> isize {
+    syscall(
+        SYSCALL_MKDIR,
+        [path.as_ptr() as usize, path.len(), 0, 0, 0, 0],
+    )
+}
+
+pub(super) fn sys_unlink(path: &str, flags: usize) -> isize {
+    syscall(
+        SYSCALL_UNLINK,
+        [path.as_ptr() as usize, path.len(), flags, 0, 0, 0],
+    )
+}
+
+pub(super) fn sys_chdir(path: &str) -> isize {
+    syscall(
+        SYSCALL_CHDIR,
+        [path.as_ptr() as usize, path.len(), 0, 0, 0, 0],
+    )
+}
+
+pub(super) fn sys_getdents(fd: usize, dirents: &mut [Dirent]) -> isize {
+    syscall(
+        SYSCALL_GETDENTS,
+        [fd, dirents.as_mut_ptr() as usize, dirents.len(), 0, 0, 0],
+    )
+}
+
+pub(super) fn sys_pipe(fds: &mut [i32; 2]) -> isize {
+    syscall(SYSCALL_PIPE, [fds.as_mut_ptr() as usize, 0, 0, 0, 0, 0])
+}
+
+pub(super) fn sys_close(fd: usize) -> isize {
+    syscall(SYSCALL_CLOSE, [fd, 0, 0, 0, 0, 0])
+}
+
+pub(super) fn sys_read(fd: usize, buffer: &mut [u8]) -> isize {
+    syscall(
+        SYSCALL_READ,
+        [fd, buffer.as_mut_ptr() as usize, buffer.len(), 0, 0, 0],
+    )
+}
+
+pub(super) fn sys_fstat(fd: usize, stat: &mut Stat) -> isize {
+    syscall(SYSCALL_FSTAT, [fd, stat as *mut _ as usize, 0, 0, 0, 0])
+}
+
 pub(super) fn sys_write(fd: usize, buffer: &[u8]) -> isize {
-    syscall(SYSCALL_WRITE, [fd, buffer.as_ptr() as usize, buffer.len()])
+    syscall(
+        SYSCALL_WRITE,
+        [fd, buffer.as_ptr() as usize, buffer.len(), 0, 0, 0],
+    )
 }
 
 pub(super) fn sys_exit(xstate: i32) -> isize {
-    syscall(SYSCALL_EXIT, [xstate as usize, 0, 0])
+    syscall(SYSCALL_EXIT, [xstate as usize, 0, 0, 0, 0, 0])
 }
 
 pub(super) fn sys_yield() -> isize {
-    syscall(SYSCALL_YIELD, [0, 0, 0])
+    syscall(SYSCALL_YIELD, [0, 0, 0, 0, 0, 0])
+}
+
+pub(super) fn sys_kill(pid: isize, signum: usize) -> isize {
+    syscall(SYSCALL_KILL, [pid as usize, signum, 0, 0, 0, 0])
+}
+
+pub(super) fn sys_sigaction(
+    signum: usize,
+    action: Option<&SigAction>,
+    prev_action: Option<&mut SigAction>,
+) -> isize {
+    let action = action.map_or(ptr::null(), |action| action as *const _);
+    let prev_action = prev_action.map_or(ptr::null_mut(), |action| action as *mut _);
+    syscall(
+        SYSCALL_SIGACTION,
+        [signum, action as usize, prev_action as usize, 0, 0, 0],
+    )
+}
+
+pub(super) fn sys_sigprocmask(how: usize, set: Option<&u64>, prev_set: Option<&mut u64>) -> isize {
+    let set = set.map_or(ptr::null(), |set| set as *const _);
+    let prev_set = prev_set.map_or(ptr::null_mut(), |set| set as *mut _);
+    syscall(
+        SYSCALL_SIGPROCMASK,
+        [how, set as usize, prev_set as usize, 0, 0, 0],
+    )
+}
+
+pub(super) fn sys_futex(
+    addr: &AtomicU32,
+    op: usize,
+    val: usize,
+    timeout: Option<&TimeSpec>,
+) -> isize {
+    let timeout = timeout.map_or(ptr::null(), |timeout| timeout as *const _);
+    syscall(
+        SYSCALL_FUTEX,
+        [addr.as_ptr().addr(), op, val, timeout as usize, 0, 0],
+    )
+}
+
+pub(super) fn sys_nanosleep(req: &TimeSpec) -> isize {
+    syscall(SYSCALL_NANOSLEEP, [req as *const _ as usize, 0, 0, 0, 0, 0])
+}
+
+pub(super) fn sys_clock_gettime(clock_id: usize, tp: &mut TimeSpec) -> isize {
+    syscall(
+        SYSCALL_CLOCK_GETTIME,
+        [clock_id, tp as *mut _ as usize, 0, 0, 0, 0],
+    )
+}
+
+pub(super) fn sys_get_time(tv: &mut TimeVal) -> isize {
+    syscall(SYSCALL_GET_TIME, [tv as *mut _ as usize, 0, 0, 0, 0, 0])
+}
+
+pub(super) fn sys_set_priority(priority: isize) -> isize {
+    syscall(SYSCALL_SET_PRIORITY, [priority as usize, 0, 0, 0, 0, 0])
+}
+
+pub(super) fn sys_fork() -> isize {
+    syscall(SYSCALL_FORK, [0, 0, 0, 0, 0, 0])
+}
+
+/// The `args` are passed as (address, length) pairs.
+pub(super) fn sys_exec(name: &str, args: &[[usize; 2]]) -> isize {
+    syscall(
+        SYSCALL_EXEC,
+        [
+            name.as_ptr() as usize,
+            name.len(),
+            args.as_ptr() as usize,
+            args.len(),
+            0,
+            0,
+        ],
+    )
+}
+
+pub(super) fn sys_waitpid(pid: isize, exit_code: *mut i32) -> isize {
+    syscall(
+        SYSCALL_WAITPID,
+        [pid as usize, exit_code.addr(), 0, 0, 0, 0],
+    )
+}
+
+pub(super) fn sys_thread_create(entry: usize, arg: usize) -> isize {
+    syscall(SYSCALL_THREAD_CREATE, [entry, arg, 0, 0, 0, 0])
+}
+
+pub(super) fn sys_gettid() -> isize {
+    syscall(SYSCALL_GETTID, [0, 0, 0, 0, 0, 0])
+}
+
+pub(super) fn sys_waittid(tid: isize, exit_code: *mut i32) -> isize {
+    syscall(
+        SYSCALL_WAITTID,
+        [tid as usize, exit_code.addr(), 0, 0, 0, 0],
+    )
+}
+
+pub(super) fn sys_mutex_create() -> isize {
+    syscall(SYSCALL_MUTEX_CREATE, [0, 0, 0, 0, 0, 0])
+}
+
+pub(super) fn sys_mutex_lock(mutex_id: usize) -> isize {
+    syscall(SYSCALL_MUTEX_LOCK, [mutex_id, 0, 0, 0, 0, 0])
+}
+
+pub(super) fn sys_mutex_unlock(mutex_id: usize) -> isize {
+    syscall(SYSCALL_MUTEX_UNLOCK, [mutex_id, 0, 0, 0, 0, 0])
+}
+
+pub(super) fn sys_semaphore_create(count: usize) -> isize {
+    syscall(SYSCALL_SEMAPHORE_CREATE, [count, 0, 0, 0, 0, 0])
+}
+
+pub(super) fn sys_semaphore_up(sem_id: usize) -> isize {
+    syscall(SYSCALL_SEMAPHORE_UP, [sem_id, 0, 0, 0, 0, 0])
+}
+
+pub(super) fn sys_semaphore_down(sem_id: usize) -> isize {
+    syscall(SYSCALL_SEMAPHORE_DOWN, [sem_id, 0, 0, 0, 0, 0])
+}
+
+pub(super) fn sys_condvar_create() -> isize {
+    syscall(SYSCALL_CONDVAR_CREATE, [0, 0, 0, 0, 0, 0])
+}
+
+pub(super) fn sys_condvar_signal(condvar_id: usize) -> isize {
+    syscall(SYSCALL_CONDVAR_SIGNAL, [condvar_id, 0, 0, 0, 0, 0])
+}
+
+pub(super) fn sys_condvar_wait(condvar_id: usize, mutex_id: usize) -> isize {
+    syscall(SYSCALL_CONDVAR_WAIT, [condvar_id, mutex_id, 0, 0, 0, 0])
+}
+
+pub(super) fn sys_enable_deadlock_detect(is_enabled: usize) -> isize {
+    syscall(SYSCALL_ENABLE_DEADLOCK_DETECT, [is_enabled, 0, 0, 0, 0, 0])
 }
 
 pub(super) fn sys_task_info(task_id: usize, data: *mut TaskInfo) -> isize {
-    syscall(SYSCALL_TASK_INFO, [task_id, data.addr(), 0])
+    syscall(SYSCALL_TASK_INFO, [task_id, data.addr(), 0, 0, 0, 0])
+}
+
+pub(super) fn sys_set_user_counters(user_counters: usize) -> isize {
+    syscall(SYSCALL_SET_USER_COUNTERS, [user_counters, 0, 0, 0, 0, 0])
 }
 
 pub(super) fn sys_mmap(addr: usize, len: usize, prot: usize) -> isize {
-    syscall(SYSCALL_MMAP, [addr, len, prot])
+    syscall(SYSCALL_MMAP, [addr, len, prot, 0, 0, 0])
 }
 
 pub(super) fn sys_munmap(addr: usize, len: usize) -> isize {
-    syscall(SYSCALL_MUNMAP, [addr, len, 0])
+    syscall(SYSCALL_MUNMAP, [addr, len, 0, 0, 0, 0])
 }
@@ -1,3 +1,5 @@
+use crate::{get_task_info, thread_create, waittid, yield_now};
+
 const MAX_SYSCALLS_TRACKED: usize = 6;
 
 #[allow(dead_code)]
@@ -23,11 +25,19 @@ impl TaskInfo {
 pub enum TaskState {
     Ready,
     Running,
-    Killed,
-    Exited,
+    Sleeping,
+    Blocked,
+    Zombie,
     Unused,
 }
 
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SchedPolicy {
+    RoundRobin,
+    Stride,
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy)]
 pub struct TaskStatistics {
@@ -45,6 +55,13 @@ pub struct TaskStatistics {
     /// The (syscall_id, called_times) statistics of a task. Can only track
     /// up to [MAX_SYSCALL_TRACKED] different syscalls.
     pub syscall_counts: [(usize, usize); MAX_SYSCALLS_TRACKED],
+    /// The scheduling policy the kernel is built with.
+    pub sched_policy: SchedPolicy,
+    /// The pass value of a task under [SchedPolicy::Stride], or zero under
+    /// the other policies.
+    pub sched_pass: usize,
+    /// The hart that the task last ran on.
+    pub last_hart_id: usize,
 }
 
 impl TaskStatistics {
@@ -57,6 +74,43 @@ impl TaskStatistics {
             mtime_total_waiting: 0,
             switch_count: 0,
             syscall_counts: [(0, 0); MAX_SYSCALLS_TRACKED],
+            sched_policy: SchedPolicy::RoundRobin,
+            sched_pass: 0,
+            last_hart_id: 0,
+        }
+    }
+}
+
+/// Yields until the kernel reports the task `task_id` in
+/// the `state`. It panics if there is no such task.
+pub fn wait_task_state(task_id: isize, state: TaskState) {
+    let mut info = TaskInfo::new_placeholder();
+    loop {
+        assert_eq!(get_task_info(task_id as usize, &raw mut info), 0);
+        if info.state == state {
+            return;
         }
+        yield_now();
+    }
+}
+
+/// Creates `N` threads that run the `entry` with their
+/// indices as the arguments, and returns their IDs. It
+/// panics if a thread cannot be created.
+pub fn spawn_threads<const N: usize>(entry: extern "C" fn(usize) -> !) -> [isize; N] {
+    core::array::from_fn(|i| {
+        let tid = thread_create(entry, i);
+        assert!(tid > 0, "thread_create should succeed");
+        tid
+    })
+}
+
+/// Waits for the threads `tids`, each of which should exit
+/// with 0, and panics otherwise.
+pub fn join_threads(tids: &[isize]) {
+    for &tid in tids {
+        let mut exit_code = -1;
+        assert_eq!(waittid(tid, &mut exit_code), tid);
+        assert_eq!(exit_code, 0, "the thread should not panic");
     }
 }
@@ -0,0 +1,120 @@
+use core::arch::asm;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
+
+use crate::env::{AT_TIMEBASE_FREQ, getauxval};
+
+/// The time since the Unix epoch.
+pub const CLOCK_REALTIME: usize = 0;
+/// The time since system start, which never goes backwards.
+pub const CLOCK_MONOTONIC: usize = 1;
+
+/// Permits reading the cycle counter, see [read_cycle].
+pub const COUNTER_CYCLE: usize = 1 << 0;
+/// Permits reading the time counter, see [read_time].
+pub const COUNTER_TIME: usize = 1 << 1;
+/// Permits reading the retired instruction counter, see
+/// [read_instret].
+pub const COUNTER_INSTRET: usize = 1 << 2;
+
+const NANOS_PER_SEC: usize = 1_000_000_000;
+const NANOS_PER_MILLI: usize = 1_000_000;
+const MILLIS_PER_SEC: usize = 1_000;
+
+/// A duration or a point in time in seconds and nanoseconds
+/// as in POSIX, which mirrors the kernel's.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(C)]
+pub struct TimeSpec {
+    pub sec: usize,
+    /// Less than one second, i.e., 10^9 nanoseconds.
+    pub nsec: usize,
+}
+
+impl TimeSpec {
+    pub fn from_millis(ms: usize) -> Self {
+        Self {
+            sec: ms / MILLIS_PER_SEC,
+            nsec: ms % MILLIS_PER_SEC * NANOS_PER_MILLI,
+        }
+    }
+
+    pub fn as_millis(&self) -> usize {
+        self.sec * MILLIS_PER_SEC + self.nsec / NANOS_PER_MILLI
+    }
+
+    pub fn as_nanos(&self) -> usize {
+        self.sec * NANOS_PER_SEC + self.nsec
+    }
+}
+
+/// A point in time in seconds and microseconds as returned
+/// by gettimeofday, which mirrors the kernel's.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(C)]
+pub struct TimeVal {
+    pub sec: usize,
+    /// Less than one second, i.e., 10^6 microseconds.
+    pub usec: usize,
+}
+
+/// Returns the time counter, which requires [COUNTER_TIME].
+pub fn read_time() -> usize {
+    let result: usize;
+    unsafe { asm!("rdtime {}", lateout(reg) result) };
+    result
+}
+
+/// Returns the cycle counter, which requires [COUNTER_CYCLE].
+pub fn read_cycle() -> usize {
+    let result: usize;
+    unsafe { asm!("rdcycle {}", lateout(reg) result) };
+    result
+}
+
+/// Returns the retired instruction counter, which requires
+/// [COUNTER_INSTRET].
+pub fn read_instret() -> usize {
+    let result: usize;
+    unsafe { asm!("rdinstret {}", lateout(reg) result) };
+    result
+}
+
+static TIMEBASE_FREQUENCY: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the frequency of the time counter in Hz, which
+/// the kernel exports in the auxiliary vector.
+pub fn get_timebase_frequency() -> usize {
+    let mut frequency = TIMEBASE_FREQUENCY.load(Ordering::Relaxed);
+    if frequency == 0 {
+        frequency = getauxval(AT_TIMEBASE_FREQ).expect("Expected the timebase frequency in auxv");
+        TIMEBASE_FREQUENCY.store(frequency, Ordering::Relaxed);
+    }
+    frequency
+}
+
+/// A point of the time counter, which measures durations
+/// without a syscall. It requires [COUNTER_TIME], which a
+/// task has unless it is denied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Instant {
+    ticks: usize,
+}
+
+impl Instant {
+    pub fn now() -> Self {
+        Self { ticks: read_time() }
+    }
+
+    /// Returns the duration from the `earlier` to this one,
+    /// or zero if the `earlier` is in fact later.
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        let ticks = self.ticks.saturating_sub(earlier.ticks) as u128;
+        let nanos = ticks * NANOS_PER_SEC as u128 / get_timebase_frequency() as u128;
+        Duration::from_nanos(nanos as u64)
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Instant::now().duration_since(*self)
+    }
+}